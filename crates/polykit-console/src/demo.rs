@@ -18,9 +18,68 @@ pub struct Fixture {
     pub data: serde_json::Value,
 }
 
+/// JSON type expected at a fixture field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldKind {
+    String,
+    Number,
+    Bool,
+    Array,
+    Object,
+    /// Present, any type (including null)
+    Any,
+}
+
+impl FieldKind {
+    fn matches(&self, value: &serde_json::Value) -> bool {
+        match self {
+            FieldKind::String => value.is_string(),
+            FieldKind::Number => value.is_number(),
+            FieldKind::Bool => value.is_boolean(),
+            FieldKind::Array => value.is_array(),
+            FieldKind::Object => value.is_object(),
+            FieldKind::Any => true,
+        }
+    }
+}
+
+/// Data shape a widget's processor expects: required fields by dotted
+/// path (e.g., `summary.total`) and their types. Extra fields are allowed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WidgetSchema {
+    pub required: Vec<(String, FieldKind)>,
+}
+
+impl WidgetSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn require(mut self, path: &str, kind: FieldKind) -> Self {
+        self.required.push((path.to_string(), kind));
+        self
+    }
+
+    /// Check `data` against the schema, naming the first mismatch.
+    pub fn validate(&self, data: &serde_json::Value) -> Result<(), String> {
+        for (path, kind) in &self.required {
+            let value = path.split('.').try_fold(data, |value, key| value.get(key));
+            match value {
+                None => return Err(format!("missing required field {}", path)),
+                Some(v) if !kind.matches(v) => {
+                    return Err(format!("field {} should be {:?}, got {}", path, kind, v));
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Fixture registry. Apps register fixtures at init time.
 pub struct FixtureRegistry {
     fixtures: std::collections::HashMap<String, serde_json::Value>,
+    schemas: std::collections::HashMap<String, WidgetSchema>,
     demo_mode: bool,
 }
 
@@ -28,13 +87,27 @@ impl FixtureRegistry {
     pub fn new(demo_mode: bool) -> Self {
         Self {
             fixtures: std::collections::HashMap::new(),
+            schemas: std::collections::HashMap::new(),
             demo_mode,
         }
     }
 
-    /// Register a fixture for a widget.
-    pub fn register(&mut self, widget_id: &str, data: serde_json::Value) {
+    /// Declare the data shape a widget expects. Fixtures registered for it
+    /// afterwards are validated against this schema.
+    pub fn set_schema(&mut self, widget_id: &str, schema: WidgetSchema) {
+        self.schemas.insert(widget_id.to_string(), schema);
+    }
+
+    /// Register a fixture for a widget. Rejected if the widget has a schema
+    /// and the fixture doesn't match it.
+    pub fn register(&mut self, widget_id: &str, data: serde_json::Value) -> Result<(), String> {
+        if let Some(schema) = self.schemas.get(widget_id) {
+            schema
+                .validate(&data)
+                .map_err(|e| format!("fixture for {} does not match schema: {}", widget_id, e))?;
+        }
         self.fixtures.insert(widget_id.to_string(), data);
+        Ok(())
     }
 
     /// Get fixture data for a widget (returns None if not in demo mode or no fixture).
@@ -49,3 +122,47 @@ impl FixtureRegistry {
         self.demo_mode
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metering_schema() -> WidgetSchema {
+        WidgetSchema::new()
+            .require("summary.total", FieldKind::Number)
+            .require("series", FieldKind::Array)
+    }
+
+    #[test]
+    fn matching_fixture_is_registered() {
+        let mut registry = FixtureRegistry::new(true);
+        registry.set_schema("metering", metering_schema());
+        let fixture = serde_json::json!({ "summary": { "total": 42 }, "series": [], "extra": 1 });
+        registry.register("metering", fixture.clone()).unwrap();
+        assert_eq!(registry.get("metering"), Some(&fixture));
+    }
+
+    #[test]
+    fn fixture_missing_required_field_is_rejected() {
+        let mut registry = FixtureRegistry::new(true);
+        registry.set_schema("metering", metering_schema());
+        let err = registry.register("metering", serde_json::json!({ "series": [] })).unwrap_err();
+        assert!(err.contains("missing required field summary.total"), "{}", err);
+        assert!(registry.get("metering").is_none());
+    }
+
+    #[test]
+    fn fixture_with_wrong_type_is_rejected() {
+        let mut registry = FixtureRegistry::new(true);
+        registry.set_schema("metering", metering_schema());
+        let fixture = serde_json::json!({ "summary": { "total": "42" }, "series": [] });
+        assert!(registry.register("metering", fixture).is_err());
+    }
+
+    #[test]
+    fn widgets_without_schema_accept_any_fixture() {
+        let mut registry = FixtureRegistry::new(true);
+        registry.register("free-form", serde_json::json!("anything")).unwrap();
+        assert!(registry.get("free-form").is_some());
+    }
+}
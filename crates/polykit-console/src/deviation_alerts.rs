@@ -0,0 +1,123 @@
+//! Deduplicated deviation alerts
+//!
+//! A sustained anomaly reports a deviation every cycle. This gate emits a
+//! `DeviationSelect` for a (metric, circuit) once, then stays quiet until
+//! the cooldown passes or the z-score moves by at least `severity_step`,
+//! so the incident timeline shows one entry per episode.
+
+use std::collections::HashMap;
+
+use crate::event_bus::{EventBus, PolykitEvent};
+
+/// Last alert emitted for a (metric, circuit).
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LastAlert {
+    timestamp_ms: u64,
+    z_score: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviationAlerter {
+    /// Minimum time between alerts for the same (metric, circuit)
+    pub cooldown_ms: u64,
+    /// Change in |z-score| since the last alert that re-alerts early
+    pub severity_step: f64,
+    last: HashMap<(String, String), LastAlert>,
+}
+
+impl DeviationAlerter {
+    pub fn new(cooldown_ms: u64, severity_step: f64) -> Self {
+        Self {
+            cooldown_ms,
+            severity_step,
+            last: HashMap::new(),
+        }
+    }
+
+    /// Report one deviation observation. Emits a `DeviationSelect` and
+    /// returns true if it is new, past the cooldown, or materially more
+    /// or less severe than the last alert; otherwise suppresses it.
+    pub fn observe(
+        &mut self,
+        metric: &str,
+        circuit: &str,
+        z_score: f64,
+        timestamp_ms: u64,
+        bus: &mut EventBus,
+    ) -> bool {
+        let key = (metric.to_string(), circuit.to_string());
+        if let Some(last) = self.last.get(&key) {
+            let cooled = timestamp_ms.saturating_sub(last.timestamp_ms) >= self.cooldown_ms;
+            let shifted = (z_score.abs() - last.z_score.abs()).abs() >= self.severity_step;
+            if !cooled && !shifted {
+                return false;
+            }
+        }
+
+        self.last.insert(key, LastAlert { timestamp_ms, z_score });
+        bus.emit(PolykitEvent::DeviationSelect {
+            metric: metric.to_string(),
+            circuit: circuit.to_string(),
+            z_score,
+            timestamp_ms,
+        });
+        true
+    }
+
+    /// Forget a (metric, circuit), e.g. once its anomaly has resolved, so
+    /// the next deviation alerts immediately.
+    pub fn clear(&mut self, metric: &str, circuit: &str) {
+        self.last.remove(&(metric.to_string(), circuit.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE_MS: u64 = 60_000;
+
+    fn alert_times(bus: &mut EventBus) -> Vec<u64> {
+        bus.drain()
+            .into_iter()
+            .filter_map(|event| match event {
+                PolykitEvent::DeviationSelect { timestamp_ms, .. } => Some(timestamp_ms),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sustained_anomaly_alerts_once_per_cooldown() {
+        let mut alerter = DeviationAlerter::new(10 * MINUTE_MS, 2.0);
+        let mut bus = EventBus::new("polydata");
+        // One observation per minute for 25 minutes at a steady z-score
+        for minute in 0..25 {
+            alerter.observe("latency_p99", "sanitize", 3.1, minute * MINUTE_MS, &mut bus);
+        }
+        assert_eq!(alert_times(&mut bus), [0, 10 * MINUTE_MS, 20 * MINUTE_MS]);
+    }
+
+    #[test]
+    fn severity_jump_re_alerts_within_cooldown() {
+        let mut alerter = DeviationAlerter::new(10 * MINUTE_MS, 2.0);
+        let mut bus = EventBus::new("polydata");
+        assert!(alerter.observe("latency_p99", "sanitize", 3.0, 0, &mut bus));
+        assert!(!alerter.observe("latency_p99", "sanitize", 4.5, MINUTE_MS, &mut bus));
+        assert!(alerter.observe("latency_p99", "sanitize", 6.0, 2 * MINUTE_MS, &mut bus));
+        assert_eq!(alert_times(&mut bus), [0, 2 * MINUTE_MS]);
+    }
+
+    #[test]
+    fn metrics_and_circuits_are_tracked_separately() {
+        let mut alerter = DeviationAlerter::new(10 * MINUTE_MS, 2.0);
+        let mut bus = EventBus::new("polydata");
+        assert!(alerter.observe("latency_p99", "sanitize", 3.0, 0, &mut bus));
+        assert!(alerter.observe("latency_p99", "classify", 3.0, 0, &mut bus));
+        assert!(alerter.observe("error_rate", "sanitize", 3.0, 0, &mut bus));
+        assert!(!alerter.observe("latency_p99", "sanitize", 3.0, 1, &mut bus));
+
+        alerter.clear("latency_p99", "sanitize");
+        assert!(alerter.observe("latency_p99", "sanitize", 3.0, 2, &mut bus));
+    }
+}
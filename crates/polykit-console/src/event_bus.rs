@@ -41,6 +41,21 @@ pub enum PolykitEvent {
         sample_hash: String,
         classification: String,
     },
+    /// Metering usage crossed a warning or hard limit → SLI + capacity highlight
+    MeteringAlert {
+        dimension: String,
+        level: AlertLevel,
+        current: u64,
+        limit: u64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum AlertLevel {
+    /// Usage at or above `warn_ratio × limit`
+    Warning,
+    /// Usage at or above the hard limit
+    Exceeded,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
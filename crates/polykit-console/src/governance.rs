@@ -6,17 +6,27 @@
 
 use serde::{Deserialize, Serialize};
 use crate::event_bus::PolykitEvent;
-use crate::widget_data::{WidgetProcessor, WidgetPayload};
+use crate::widget_data::{WidgetId, WidgetProcessor, WidgetPayload};
 
 /// LI Effects review queue processor.
 /// Subscribes to: {namespace}/li/classification
 pub struct LiReviewQueueProcessor {
-    pub app: String,
+    pub id: WidgetId,
     pub namespace: String,
 }
 
+impl LiReviewQueueProcessor {
+    /// Main instance for `app`.
+    pub fn new(app: &str, namespace: &str) -> Result<Self, String> {
+        Ok(Self {
+            id: WidgetId::new(app, "polykit-li-review-queue", "main")?,
+            namespace: namespace.to_string(),
+        })
+    }
+}
+
 impl WidgetProcessor for LiReviewQueueProcessor {
-    fn widget_type(&self) -> &str { "polykit-li-review-queue" }
+    fn widget_id(&self) -> &WidgetId { &self.id }
 
     fn process(
         &mut self,
@@ -33,7 +43,7 @@ impl WidgetProcessor for LiReviewQueueProcessor {
         }
 
         WidgetPayload {
-            widget_id: format!("{}-li-review-queue", self.app),
+            widget_id: self.id.to_string(),
             data,
             dirty: true,
         }
@@ -43,12 +53,22 @@ impl WidgetProcessor for LiReviewQueueProcessor {
 /// Sanitization log processor.
 /// Subscribes to: {namespace}/li/sanitization
 pub struct SanitizationLogProcessor {
-    pub app: String,
+    pub id: WidgetId,
     pub namespace: String,
 }
 
+impl SanitizationLogProcessor {
+    /// Main instance for `app`.
+    pub fn new(app: &str, namespace: &str) -> Result<Self, String> {
+        Ok(Self {
+            id: WidgetId::new(app, "polykit-sanitization-log", "main")?,
+            namespace: namespace.to_string(),
+        })
+    }
+}
+
 impl WidgetProcessor for SanitizationLogProcessor {
-    fn widget_type(&self) -> &str { "polykit-sanitization-log" }
+    fn widget_id(&self) -> &WidgetId { &self.id }
 
     fn process(
         &mut self,
@@ -64,7 +84,7 @@ impl WidgetProcessor for SanitizationLogProcessor {
         }
 
         WidgetPayload {
-            widget_id: format!("{}-sanitization-log", self.app),
+            widget_id: self.id.to_string(),
             data,
             dirty: true,
         }
@@ -74,12 +94,22 @@ impl WidgetProcessor for SanitizationLogProcessor {
 /// LI Effects feedback dashboard processor.
 /// Subscribes to: {namespace}/li/classification
 pub struct LiFeedbackProcessor {
-    pub app: String,
+    pub id: WidgetId,
     pub namespace: String,
 }
 
+impl LiFeedbackProcessor {
+    /// Main instance for `app`.
+    pub fn new(app: &str, namespace: &str) -> Result<Self, String> {
+        Ok(Self {
+            id: WidgetId::new(app, "polykit-li-feedback", "main")?,
+            namespace: namespace.to_string(),
+        })
+    }
+}
+
 impl WidgetProcessor for LiFeedbackProcessor {
-    fn widget_type(&self) -> &str { "polykit-li-feedback" }
+    fn widget_id(&self) -> &WidgetId { &self.id }
 
     fn process(
         &mut self,
@@ -98,7 +128,7 @@ impl WidgetProcessor for LiFeedbackProcessor {
         }
 
         WidgetPayload {
-            widget_id: format!("{}-li-feedback", self.app),
+            widget_id: self.id.to_string(),
             data,
             dirty: true,
         }
@@ -108,12 +138,22 @@ impl WidgetProcessor for LiFeedbackProcessor {
 /// ESN-AI recommendations processor.
 /// Subscribes to: {namespace}/li/recommendation
 pub struct EsnAiRecommendationsProcessor {
-    pub app: String,
+    pub id: WidgetId,
     pub namespace: String,
 }
 
+impl EsnAiRecommendationsProcessor {
+    /// Main instance for `app`.
+    pub fn new(app: &str, namespace: &str) -> Result<Self, String> {
+        Ok(Self {
+            id: WidgetId::new(app, "polykit-esn-ai-recommendations", "main")?,
+            namespace: namespace.to_string(),
+        })
+    }
+}
+
 impl WidgetProcessor for EsnAiRecommendationsProcessor {
-    fn widget_type(&self) -> &str { "polykit-esn-ai-recommendations" }
+    fn widget_id(&self) -> &WidgetId { &self.id }
 
     fn process(
         &mut self,
@@ -131,7 +171,7 @@ impl WidgetProcessor for EsnAiRecommendationsProcessor {
         }
 
         WidgetPayload {
-            widget_id: format!("{}-esn-ai-recommendations", self.app),
+            widget_id: self.id.to_string(),
             data,
             dirty: true,
         }
@@ -9,3 +9,6 @@ pub mod demo;
 pub mod observability;
 pub mod governance;
 pub mod rbac;
+pub mod metering_alerts;
+pub mod metering_display;
+pub mod deviation_alerts;
@@ -0,0 +1,98 @@
+//! Metering threshold alerts
+//!
+//! Bridges polykit_metering usage into the event bus: when a dimension
+//! crosses its warning ratio or hard limit, a `MeteringAlert` is emitted
+//! so the SLI and capacity widgets can highlight it on the next cycle.
+
+use serde::{Deserialize, Serialize};
+
+use crate::event_bus::{AlertLevel, EventBus, PolykitEvent};
+
+/// Alert thresholds for one metering dimension.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeteringThreshold {
+    /// Dimension name (e.g., "executions", "bandwidth")
+    pub dimension: String,
+    /// Hard limit from the tier
+    pub limit: u64,
+    /// Fraction of `limit` that triggers a warning (e.g., 0.8)
+    pub warn_ratio: f64,
+}
+
+impl MeteringThreshold {
+    pub fn new(dimension: &str, limit: u64, warn_ratio: f64) -> Self {
+        Self {
+            dimension: dimension.to_string(),
+            limit,
+            warn_ratio,
+        }
+    }
+
+    /// Alert level for a usage value, if any.
+    pub fn level_for(&self, usage: u64) -> Option<AlertLevel> {
+        if usage >= self.limit {
+            Some(AlertLevel::Exceeded)
+        } else if usage as f64 >= self.limit as f64 * self.warn_ratio {
+            Some(AlertLevel::Warning)
+        } else {
+            None
+        }
+    }
+
+    /// Compare usage before and after an update and emit a `MeteringAlert`
+    /// only when a higher level is crossed, so steady usage above a
+    /// threshold doesn't re-alert every cycle.
+    pub fn check(&self, previous: u64, current: u64, bus: &mut EventBus) -> Option<AlertLevel> {
+        let level = self.level_for(current)?;
+        if self.level_for(previous) >= Some(level) {
+            return None;
+        }
+
+        bus.emit(PolykitEvent::MeteringAlert {
+            dimension: self.dimension.clone(),
+            level,
+            current,
+            limit: self.limit,
+        });
+        Some(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crossing_warning_emits_alert() {
+        let threshold = MeteringThreshold::new("executions", 1_000, 0.8);
+        let mut bus = EventBus::new("polydata");
+
+        assert_eq!(threshold.check(700, 850, &mut bus), Some(AlertLevel::Warning));
+        match bus.drain().as_slice() {
+            [PolykitEvent::MeteringAlert { dimension, level, current, limit }] => {
+                assert_eq!(dimension, "executions");
+                assert_eq!(*level, AlertLevel::Warning);
+                assert_eq!(*current, 850);
+                assert_eq!(*limit, 1_000);
+            }
+            other => panic!("expected one MeteringAlert, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn steady_usage_does_not_realert() {
+        let threshold = MeteringThreshold::new("executions", 1_000, 0.8);
+        let mut bus = EventBus::new("polydata");
+        assert_eq!(threshold.check(850, 900, &mut bus), None);
+        assert_eq!(threshold.check(100, 200, &mut bus), None);
+        assert!(bus.drain().is_empty());
+    }
+
+    #[test]
+    fn crossing_hard_limit_escalates() {
+        let threshold = MeteringThreshold::new("bandwidth", 1_000, 0.8);
+        let mut bus = EventBus::new("polydata");
+        assert_eq!(threshold.check(900, 1_000, &mut bus), Some(AlertLevel::Exceeded));
+        assert_eq!(bus.drain().len(), 1);
+    }
+}
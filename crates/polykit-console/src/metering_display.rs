@@ -0,0 +1,81 @@
+//! Metering display formatting
+//!
+//! Renders raw dimension values for the SLI dashboard with per-dimension
+//! units, so the TS layer shows the string as-is.
+
+use polykit_core::error::MeteringDimension;
+
+const BYTE_UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// Human-readable value for a dimension: binary byte units with one
+/// decimal for Bandwidth and Storage (e.g., "1.2 GiB"), thousands-grouped
+/// counts for everything else (e.g., "3,400").
+pub fn format_dimension(dimension: MeteringDimension, value: u64) -> String {
+    match dimension {
+        MeteringDimension::Bandwidth | MeteringDimension::Storage => format_bytes(value),
+        _ => group_thousands(value),
+    }
+}
+
+fn format_bytes(value: u64) -> String {
+    if value < 1024 {
+        return format!("{} B", value);
+    }
+    let mut scaled = value as f64;
+    let mut unit = 0;
+    // Move up a unit when rounding to one decimal would show 1024.0
+    while unit + 1 < BYTE_UNITS.len() && (scaled * 10.0).round() >= 10240.0 {
+        scaled /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", scaled, BYTE_UNITS[unit])
+}
+
+fn group_thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage(value: u64) -> String {
+        format_dimension(MeteringDimension::Storage, value)
+    }
+
+    #[test]
+    fn bytes_scale_through_binary_units() {
+        assert_eq!(storage(0), "0 B");
+        assert_eq!(storage(1023), "1023 B");
+        assert_eq!(storage(1024), "1.0 KiB");
+        assert_eq!(storage(1536), "1.5 KiB");
+        assert_eq!(storage(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(storage(1_288_490_188), "1.2 GiB");
+        assert_eq!(format_dimension(MeteringDimension::Bandwidth, 3 << 40), "3.0 TiB");
+    }
+
+    #[test]
+    fn rounding_up_to_the_next_unit_switches_units() {
+        // 1023.96 KiB would print as "1024.0 KiB"
+        assert_eq!(storage(1_048_535), "1.0 MiB");
+        assert_eq!(storage(u64::MAX), "16.0 EiB");
+    }
+
+    #[test]
+    fn counts_are_grouped_by_thousands() {
+        let executions = |value| format_dimension(MeteringDimension::Executions, value);
+        assert_eq!(executions(0), "0");
+        assert_eq!(executions(999), "999");
+        assert_eq!(executions(3_400), "3,400");
+        assert_eq!(executions(1_234_567), "1,234,567");
+        assert_eq!(format_dimension(MeteringDimension::Proofs, 100_000), "100,000");
+    }
+}
@@ -5,17 +5,86 @@
 
 use serde::{Deserialize, Serialize};
 use crate::event_bus::PolykitEvent;
-use crate::widget_data::{WidgetProcessor, WidgetPayload};
+use crate::widget_data::{WidgetId, WidgetProcessor, WidgetPayload};
+
+/// Drill-down focus resolved from an ESN-AI "Investigate" action.
+/// Shared by the circuit health, SLI, and deviation feed widgets.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FocusContext {
+    pub metric: String,
+    pub circuit: Option<String>,
+    pub category: String,
+    pub recommendation_id: String,
+}
+
+impl FocusContext {
+    /// Resolve the active focus from a batch of events. The latest
+    /// InvestigateMetric wins; a later FilterReset clears it.
+    pub fn from_events(events: &[PolykitEvent]) -> Option<Self> {
+        let mut focus = None;
+        for event in events {
+            match event {
+                PolykitEvent::InvestigateMetric { metric, circuit, category, recommendation_id } => {
+                    focus = Some(FocusContext {
+                        metric: metric.clone(),
+                        circuit: circuit.clone(),
+                        category: category.clone(),
+                        recommendation_id: recommendation_id.clone(),
+                    });
+                }
+                PolykitEvent::FilterReset => focus = None,
+                _ => {}
+            }
+        }
+        focus
+    }
+
+    /// Attach the focus to a widget's data under `focus`.
+    pub fn apply(&self, data: &mut serde_json::Value) {
+        data["focus"] = serde_json::json!({
+            "metric": self.metric,
+            "circuit": self.circuit,
+            "category": self.category,
+            "recommendation_id": self.recommendation_id,
+        });
+    }
+}
+
+/// Collect `MeteringAlert` events as highlight entries for a widget.
+fn metering_alerts(events: &[PolykitEvent]) -> Vec<serde_json::Value> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            PolykitEvent::MeteringAlert { dimension, level, current, limit } => Some(serde_json::json!({
+                "dimension": dimension,
+                "level": level,
+                "current": current,
+                "limit": limit,
+            })),
+            _ => None,
+        })
+        .collect()
+}
 
 /// Deviation feed processor.
 /// Subscribes to: {namespace}/metrics/deviations
 pub struct DeviationFeedProcessor {
-    pub app: String,
+    pub id: WidgetId,
     pub namespace: String,
 }
 
+impl DeviationFeedProcessor {
+    /// Main instance for `app`.
+    pub fn new(app: &str, namespace: &str) -> Result<Self, String> {
+        Ok(Self {
+            id: WidgetId::new(app, "polykit-deviation-feed", "main")?,
+            namespace: namespace.to_string(),
+        })
+    }
+}
+
 impl WidgetProcessor for DeviationFeedProcessor {
-    fn widget_type(&self) -> &str { "polykit-deviation-feed" }
+    fn widget_id(&self) -> &WidgetId { &self.id }
 
     fn process(
         &mut self,
@@ -35,8 +104,12 @@ impl WidgetProcessor for DeviationFeedProcessor {
             }
         }
 
+        if let Some(focus) = FocusContext::from_events(events) {
+            focus.apply(&mut data);
+        }
+
         WidgetPayload {
-            widget_id: format!("{}-deviation-feed", self.app),
+            widget_id: self.id.to_string(),
             data,
             dirty: true,
         }
@@ -46,12 +119,22 @@ impl WidgetProcessor for DeviationFeedProcessor {
 /// Capacity forecast processor.
 /// Subscribes to: {namespace}/capacity
 pub struct CapacityForecastProcessor {
-    pub app: String,
+    pub id: WidgetId,
     pub namespace: String,
 }
 
+impl CapacityForecastProcessor {
+    /// Main instance for `app`.
+    pub fn new(app: &str, namespace: &str) -> Result<Self, String> {
+        Ok(Self {
+            id: WidgetId::new(app, "polykit-capacity-forecast", "main")?,
+            namespace: namespace.to_string(),
+        })
+    }
+}
+
 impl WidgetProcessor for CapacityForecastProcessor {
-    fn widget_type(&self) -> &str { "polykit-capacity-forecast" }
+    fn widget_id(&self) -> &WidgetId { &self.id }
 
     fn process(
         &mut self,
@@ -67,8 +150,13 @@ impl WidgetProcessor for CapacityForecastProcessor {
             }
         }
 
+        let alerts = metering_alerts(events);
+        if !alerts.is_empty() {
+            data["metering_alerts"] = serde_json::json!(alerts);
+        }
+
         WidgetPayload {
-            widget_id: format!("{}-capacity-forecast", self.app),
+            widget_id: self.id.to_string(),
             data,
             dirty: true,
         }
@@ -78,17 +166,38 @@ impl WidgetProcessor for CapacityForecastProcessor {
 /// SLI dashboard processor.
 /// Subscribes to: {namespace}/telemetry/sli
 pub struct SliDashboardProcessor {
-    pub app: String,
+    pub id: WidgetId,
     pub namespace: String,
 }
 
+impl SliDashboardProcessor {
+    /// Main instance for `app`.
+    pub fn new(app: &str, namespace: &str) -> Result<Self, String> {
+        Ok(Self {
+            id: WidgetId::new(app, "polykit-sli-dashboard", "main")?,
+            namespace: namespace.to_string(),
+        })
+    }
+}
+
 impl WidgetProcessor for SliDashboardProcessor {
-    fn widget_type(&self) -> &str { "polykit-sli-dashboard" }
+    fn widget_id(&self) -> &WidgetId { &self.id }
+
+    fn process(&mut self, stream_data: &serde_json::Value, events: &[PolykitEvent]) -> WidgetPayload {
+        let mut data = stream_data.clone();
+
+        if let Some(focus) = FocusContext::from_events(events) {
+            focus.apply(&mut data);
+        }
+
+        let alerts = metering_alerts(events);
+        if !alerts.is_empty() {
+            data["metering_alerts"] = serde_json::json!(alerts);
+        }
 
-    fn process(&mut self, stream_data: &serde_json::Value, _events: &[PolykitEvent]) -> WidgetPayload {
         WidgetPayload {
-            widget_id: format!("{}-sli-dashboard", self.app),
-            data: stream_data.clone(),
+            widget_id: self.id.to_string(),
+            data,
             dirty: true,
         }
     }
@@ -97,24 +206,33 @@ impl WidgetProcessor for SliDashboardProcessor {
 /// Circuit health processor.
 /// Subscribes to: {namespace}/telemetry
 pub struct CircuitHealthProcessor {
-    pub app: String,
+    pub id: WidgetId,
     pub namespace: String,
 }
 
+impl CircuitHealthProcessor {
+    /// Main instance for `app`.
+    pub fn new(app: &str, namespace: &str) -> Result<Self, String> {
+        Ok(Self {
+            id: WidgetId::new(app, "polykit-circuit-health", "main")?,
+            namespace: namespace.to_string(),
+        })
+    }
+}
+
 impl WidgetProcessor for CircuitHealthProcessor {
-    fn widget_type(&self) -> &str { "polykit-circuit-health" }
+    fn widget_id(&self) -> &WidgetId { &self.id }
 
     fn process(&mut self, stream_data: &serde_json::Value, events: &[PolykitEvent]) -> WidgetPayload {
         let mut data = stream_data.clone();
 
-        for event in events {
-            if let PolykitEvent::InvestigateMetric { circuit, .. } = event {
-                data["focused_circuit"] = serde_json::json!(circuit);
-            }
+        if let Some(focus) = FocusContext::from_events(events) {
+            data["focused_circuit"] = serde_json::json!(focus.circuit);
+            focus.apply(&mut data);
         }
 
         WidgetPayload {
-            widget_id: format!("{}-circuit-health", self.app),
+            widget_id: self.id.to_string(),
             data,
             dirty: true,
         }
@@ -124,12 +242,22 @@ impl WidgetProcessor for CircuitHealthProcessor {
 /// Incident timeline processor.
 /// Subscribes to: {namespace}/incidents
 pub struct IncidentTimelineProcessor {
-    pub app: String,
+    pub id: WidgetId,
     pub namespace: String,
 }
 
+impl IncidentTimelineProcessor {
+    /// Main instance for `app`.
+    pub fn new(app: &str, namespace: &str) -> Result<Self, String> {
+        Ok(Self {
+            id: WidgetId::new(app, "polykit-incident-timeline", "main")?,
+            namespace: namespace.to_string(),
+        })
+    }
+}
+
 impl WidgetProcessor for IncidentTimelineProcessor {
-    fn widget_type(&self) -> &str { "polykit-incident-timeline" }
+    fn widget_id(&self) -> &WidgetId { &self.id }
 
     fn process(&mut self, stream_data: &serde_json::Value, events: &[PolykitEvent]) -> WidgetPayload {
         let mut data = stream_data.clone();
@@ -141,9 +269,76 @@ impl WidgetProcessor for IncidentTimelineProcessor {
         }
 
         WidgetPayload {
-            widget_id: format!("{}-incident-timeline", self.app),
+            widget_id: self.id.to_string(),
             data,
             dirty: true,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_widget_id_is_the_structured_id() {
+        let mut processor = DeviationFeedProcessor::new("polydata", "polydata").unwrap();
+        let payload = processor.process(&serde_json::json!({}), &[]);
+        assert_eq!(payload.widget_id, "polydata:polykit-deviation-feed:main");
+        assert_eq!(WidgetId::parse(&payload.widget_id).unwrap(), processor.id);
+    }
+
+    #[test]
+    fn widget_ids_differ_across_types_in_one_app() {
+        let mut feed = DeviationFeedProcessor::new("polydata", "polydata").unwrap();
+        let mut sli = SliDashboardProcessor::new("polydata", "polydata").unwrap();
+        let data = serde_json::json!({});
+        assert_ne!(feed.process(&data, &[]).widget_id, sli.process(&data, &[]).widget_id);
+        assert_eq!(sli.widget_type(), "polykit-sli-dashboard");
+    }
+
+    #[test]
+    fn invalid_app_name_is_rejected() {
+        assert!(CircuitHealthProcessor::new("Poly:Data", "polydata").is_err());
+    }
+
+    fn investigate() -> PolykitEvent {
+        PolykitEvent::InvestigateMetric {
+            metric: "p99_latency_ms".to_string(),
+            circuit: Some("payments".to_string()),
+            category: "latency".to_string(),
+            recommendation_id: "rec-42".to_string(),
+        }
+    }
+
+    #[test]
+    fn investigate_focuses_all_drill_down_widgets() {
+        let mut processors: Vec<Box<dyn WidgetProcessor>> = vec![
+            Box::new(CircuitHealthProcessor::new("polydata", "polydata").unwrap()),
+            Box::new(SliDashboardProcessor::new("polydata", "polydata").unwrap()),
+            Box::new(DeviationFeedProcessor::new("polydata", "polydata").unwrap()),
+        ];
+        let events = [investigate()];
+        for processor in processors.iter_mut() {
+            let payload = processor.process(&serde_json::json!({}), &events);
+            let focus = &payload.data["focus"];
+            assert_eq!(focus["metric"], "p99_latency_ms", "{}", payload.widget_id);
+            assert_eq!(focus["recommendation_id"], "rec-42", "{}", payload.widget_id);
+            assert_eq!(focus["circuit"], "payments", "{}", payload.widget_id);
+        }
+    }
+
+    #[test]
+    fn latest_investigate_wins_and_reset_clears() {
+        let other = PolykitEvent::InvestigateMetric {
+            metric: "error_rate".to_string(),
+            circuit: None,
+            category: "errors".to_string(),
+            recommendation_id: "rec-7".to_string(),
+        };
+        let focus = FocusContext::from_events(&[investigate(), other.clone()]).unwrap();
+        assert_eq!(focus.metric, "error_rate");
+        assert_eq!(focus.circuit, None);
+        assert!(FocusContext::from_events(&[other, PolykitEvent::FilterReset]).is_none());
+    }
+}
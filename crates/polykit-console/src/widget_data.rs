@@ -4,22 +4,78 @@
 //! JSON payloads for the TS layer. Each widget type has a processor
 //! that runs in WASM.
 
+use std::panic::{self, AssertUnwindSafe};
+
+use polykit_sanitize::{SanitizeOptions, TransformMode};
 use serde::{Deserialize, Serialize};
 use crate::event_bus::{EventBus, PolykitEvent};
 
 /// Render-ready payload returned to TS for a specific widget instance.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WidgetPayload {
+    /// `WidgetId::to_string()` of the processor that produced it
     pub widget_id: String,
     pub data: serde_json::Value,
     /// If true, the widget should re-render
     pub dirty: bool,
 }
 
+/// Structured widget instance id, `{app}:{widget_type}:{instance}`
+/// (e.g., `polydata:polykit-deviation-feed:main`). Parts are restricted to
+/// `[a-z0-9_-]`, so the separator can't appear inside one and two distinct
+/// (app, type, instance) triples never format to the same id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WidgetId {
+    pub app: String,
+    pub widget_type: String,
+    pub instance: String,
+}
+
+impl WidgetId {
+    pub fn new(app: &str, widget_type: &str, instance: &str) -> Result<Self, String> {
+        for (name, part) in [("app", app), ("widget_type", widget_type), ("instance", instance)] {
+            if part.is_empty() {
+                return Err(format!("widget id {} is empty", name));
+            }
+            if let Some(c) = part
+                .chars()
+                .find(|c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || *c == '-' || *c == '_'))
+            {
+                return Err(format!("widget id {} {:?} contains {:?}", name, part, c));
+            }
+        }
+        Ok(Self {
+            app: app.to_string(),
+            widget_type: widget_type.to_string(),
+            instance: instance.to_string(),
+        })
+    }
+
+    /// Recover the parts of an id produced by `to_string`.
+    pub fn parse(id: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = id.split(':').collect();
+        match parts.as_slice() {
+            [app, widget_type, instance] => Self::new(app, widget_type, instance),
+            _ => Err(format!("malformed widget id {:?}: expected app:type:instance", id)),
+        }
+    }
+}
+
+impl std::fmt::Display for WidgetId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.app, self.widget_type, self.instance)
+    }
+}
+
 /// Widget data processor trait. Each widget type implements this.
 pub trait WidgetProcessor {
+    /// Instance this processor renders; its string form is the payload `widget_id`.
+    fn widget_id(&self) -> &WidgetId;
+
     /// Unique widget type ID (e.g., "polykit-deviation-feed")
-    fn widget_type(&self) -> &str;
+    fn widget_type(&self) -> &str {
+        &self.widget_id().widget_type
+    }
 
     /// Process incoming stream data and event bus events.
     /// Returns a render-ready payload for the TS layer.
@@ -28,34 +84,442 @@ pub trait WidgetProcessor {
         stream_data: &serde_json::Value,
         events: &[PolykitEvent],
     ) -> WidgetPayload;
+
+    /// Relative work units one `process` call costs, for cycle budgets.
+    fn cost(&self) -> u32 {
+        1
+    }
 }
 
 /// Registry of widget processors.
 pub struct WidgetRegistry {
     processors: Vec<Box<dyn WidgetProcessor>>,
+    /// Work units allowed per `process_all` cycle (`None` = unbounded)
+    cycle_budget: Option<u32>,
+    /// Where the next budgeted cycle starts its round-robin
+    next_start: usize,
+    /// Events a deferred processor hasn't seen yet, per processor
+    backlog: Vec<Vec<PolykitEvent>>,
 }
 
 impl WidgetRegistry {
     pub fn new() -> Self {
         Self {
             processors: Vec::new(),
+            cycle_budget: None,
+            next_start: 0,
+            backlog: Vec::new(),
         }
     }
 
     pub fn register(&mut self, processor: Box<dyn WidgetProcessor>) {
         self.processors.push(processor);
+        self.backlog.push(Vec::new());
+    }
+
+    /// Register a processor whose output may carry free-text PII
+    /// (e.g., log widgets). Its payloads are redacted before emitting.
+    pub fn register_sensitive(&mut self, processor: Box<dyn WidgetProcessor>) {
+        self.register(Box::new(SanitizingProcessor::new(processor)));
+    }
+
+    /// Cap the summed `WidgetProcessor::cost` run per `process_all` cycle.
+    /// Widgets that don't fit are deferred to a later cycle.
+    pub fn set_cycle_budget(&mut self, budget: Option<u32>) {
+        self.cycle_budget = budget;
     }
 
     /// Process all widgets with current stream data and event bus state.
+    /// A processor that panics or returns a malformed payload yields an
+    /// error payload for its widget; the others render normally.
+    ///
+    /// With a cycle budget, widgets run round-robin from the first one
+    /// deferred last cycle, which always runs; others run while they fit
+    /// the remaining budget. A deferred widget gets a pending payload and
+    /// keeps its undelivered events, and is first in line next cycle, so
+    /// none is starved.
     pub fn process_all(
         &mut self,
         stream_data: &serde_json::Value,
         bus: &mut EventBus,
     ) -> Vec<WidgetPayload> {
         let events = bus.drain();
-        self.processors
+        let Some(budget) = self.cycle_budget else {
+            return self
+                .processors
+                .iter_mut()
+                .zip(self.backlog.iter_mut())
+                .map(|(p, backlog)| {
+                    backlog.extend(events.iter().cloned());
+                    let events = std::mem::take(backlog);
+                    process_isolated(p.as_mut(), stream_data, &events).unwrap_or_else(|e| e)
+                })
+                .collect();
+        };
+
+        let count = self.processors.len();
+        let mut payloads: Vec<Option<WidgetPayload>> = vec![None; count];
+        let mut spent = 0u32;
+        let mut first_deferred = None;
+        for offset in 0..count {
+            let i = (self.next_start + offset) % count;
+            let processor = self.processors[i].as_mut();
+            self.backlog[i].extend(events.iter().cloned());
+
+            let cost = processor.cost();
+            if offset > 0 && spent.saturating_add(cost) > budget {
+                first_deferred.get_or_insert(i);
+                payloads[i] = Some(WidgetPayload {
+                    widget_id: processor.widget_id().to_string(),
+                    data: serde_json::json!({ "pending": true }),
+                    dirty: false,
+                });
+                continue;
+            }
+            spent = spent.saturating_add(cost);
+            let events = std::mem::take(&mut self.backlog[i]);
+            payloads[i] =
+                Some(process_isolated(processor, stream_data, &events).unwrap_or_else(|e| e));
+        }
+        if let Some(i) = first_deferred {
+            self.next_start = i;
+        }
+        payloads.into_iter().flatten().collect()
+    }
+
+    /// Replay a recorded event log through the registered processors.
+    pub fn replay(
+        &mut self,
+        events: &[PolykitEvent],
+        initial_stream_data: &serde_json::Value,
+    ) -> Vec<WidgetPayload> {
+        replay(events, &mut self.processors, initial_stream_data)
+    }
+}
+
+/// Wraps a processor and runs the sanitize pipeline over its payload data,
+/// so PII in stream data never reaches the TS layer.
+pub struct SanitizingProcessor {
+    inner: Box<dyn WidgetProcessor>,
+    options: SanitizeOptions,
+}
+
+impl SanitizingProcessor {
+    /// Redaction-only defaults: tokens would still be a join key in JS.
+    pub fn new(inner: Box<dyn WidgetProcessor>) -> Self {
+        let options = SanitizeOptions {
+            mode: TransformMode::Redact,
+            ..SanitizeOptions::default()
+        };
+        Self::with_options(inner, options)
+    }
+
+    pub fn with_options(inner: Box<dyn WidgetProcessor>, options: SanitizeOptions) -> Self {
+        Self { inner, options }
+    }
+}
+
+impl WidgetProcessor for SanitizingProcessor {
+    fn widget_id(&self) -> &WidgetId {
+        self.inner.widget_id()
+    }
+
+    fn process(&mut self, stream_data: &serde_json::Value, events: &[PolykitEvent]) -> WidgetPayload {
+        let mut payload = self.inner.process(stream_data, events);
+        payload.data = polykit_sanitize::sanitize_with(&payload.data, &self.options).sanitized_data;
+        payload
+    }
+}
+
+/// Run one processor behind an error boundary. `Err` carries the error
+/// payload to render in place of the widget.
+///
+/// Note: on wasm32 with `panic = "abort"` a panic still aborts the module;
+/// the boundary catches panics on unwinding targets (native hosts, tests).
+fn process_isolated(
+    processor: &mut dyn WidgetProcessor,
+    stream_data: &serde_json::Value,
+    events: &[PolykitEvent],
+) -> Result<WidgetPayload, WidgetPayload> {
+    let widget_id = processor.widget_id().to_string();
+    let widget_type = processor.widget_type().to_string();
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| processor.process(stream_data, events)));
+
+    let message = match outcome {
+        Ok(payload) if !payload.widget_id.is_empty() => return Ok(payload),
+        Ok(_) => "processor returned a payload without a widget_id".to_string(),
+        Err(panic) => panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "processor panicked".to_string()),
+    };
+
+    Err(WidgetPayload {
+        widget_id,
+        data: serde_json::json!({
+            "error": message,
+            "widget_type": widget_type,
+        }),
+        dirty: true,
+    })
+}
+
+/// Reconstruct widget state by replaying an event log, one event per cycle.
+///
+/// Each processor's output feeds its next cycle, so the final payloads match
+/// a live run that saw the same events. Deterministic for deterministic
+/// processors.
+pub fn replay(
+    events: &[PolykitEvent],
+    processors: &mut [Box<dyn WidgetProcessor>],
+    initial_stream_data: &serde_json::Value,
+) -> Vec<WidgetPayload> {
+    if events.is_empty() {
+        return processors
+            .iter_mut()
+            .map(|p| process_isolated(p.as_mut(), initial_stream_data, &[]).unwrap_or_else(|e| e))
+            .collect();
+    }
+
+    let mut states = vec![initial_stream_data.clone(); processors.len()];
+    let mut payloads = Vec::new();
+    for event in events {
+        payloads = processors
             .iter_mut()
-            .map(|p| p.process(stream_data, &events))
-            .collect()
+            .zip(states.iter_mut())
+            .map(|(p, state)| {
+                match process_isolated(p.as_mut(), state, std::slice::from_ref(event)) {
+                    Ok(payload) => {
+                        *state = payload.data.clone();
+                        payload
+                    }
+                    // Keep the last good state so later cycles can recover
+                    Err(error_payload) => error_payload,
+                }
+            })
+            .collect();
+    }
+    payloads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::governance::LiReviewQueueProcessor;
+
+    fn review_queue() -> Box<dyn WidgetProcessor> {
+        Box::new(LiReviewQueueProcessor::new("polydata", "polydata").unwrap())
+    }
+
+    fn filter(tag: &str) -> PolykitEvent {
+        PolykitEvent::ClassificationFilter { tag: Some(tag.to_string()) }
+    }
+
+    #[test]
+    fn replay_matches_live_run() {
+        let log = vec![filter("pii"), filter("phi"), PolykitEvent::FilterReset, filter("pci")];
+        let initial = serde_json::json!({ "queue": [{ "sample_hash": "ab12" }] });
+
+        // Live: one event per render cycle, each cycle fed the last payload
+        let mut registry = WidgetRegistry::new();
+        registry.register(review_queue());
+        let mut bus = EventBus::new("polydata");
+        let mut state = initial.clone();
+        let mut live = Vec::new();
+        for event in &log {
+            bus.emit(event.clone());
+            live = registry.process_all(&state, &mut bus);
+            state = live[0].data.clone();
+        }
+
+        let replayed = replay(&log, &mut [review_queue()], &initial);
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(replayed[0].widget_id, live[0].widget_id);
+        assert_eq!(replayed[0].data, live[0].data);
+        assert_eq!(replayed[0].data["classification_filter"], "pci");
+    }
+
+    #[test]
+    fn replay_is_deterministic() {
+        let log = vec![filter("pii"), filter("phi")];
+        let initial = serde_json::json!({});
+        let first = replay(&log, &mut [review_queue()], &initial);
+        let second = replay(&log, &mut [review_queue()], &initial);
+        assert_eq!(first[0].data, second[0].data);
+    }
+
+    #[test]
+    fn empty_log_renders_initial_data() {
+        let initial = serde_json::json!({ "queue": [] });
+        let payloads = replay(&[], &mut [review_queue()], &initial);
+        assert_eq!(payloads[0].data, initial);
+    }
+
+    struct Panicking(WidgetId);
+
+    impl WidgetProcessor for Panicking {
+        fn widget_id(&self) -> &WidgetId {
+            &self.0
+        }
+
+        fn process(&mut self, _: &serde_json::Value, _: &[PolykitEvent]) -> WidgetPayload {
+            panic!("feed unavailable")
+        }
+    }
+
+    fn panicking() -> Box<dyn WidgetProcessor> {
+        Box::new(Panicking(WidgetId::new("polydata", "broken-widget", "main").unwrap()))
+    }
+
+    #[test]
+    fn panicking_processor_yields_error_payload() {
+        let mut registry = WidgetRegistry::new();
+        registry.register(review_queue());
+        registry.register(panicking());
+        registry.register(review_queue());
+
+        let payloads = registry.process_all(&serde_json::json!({}), &mut EventBus::new("polydata"));
+        assert_eq!(payloads.len(), 3);
+        assert_eq!(payloads[0].widget_id, "polydata:polykit-li-review-queue:main");
+        assert_eq!(payloads[2].widget_id, "polydata:polykit-li-review-queue:main");
+        assert!(payloads[0].data.get("error").is_none());
+
+        let error = &payloads[1];
+        assert_eq!(error.widget_id, "polydata:broken-widget:main");
+        assert_eq!(error.data["error"], "feed unavailable");
+        assert_eq!(error.data["widget_type"], "broken-widget");
+        assert!(error.dirty);
+    }
+
+    struct Unlabelled(WidgetId);
+
+    impl WidgetProcessor for Unlabelled {
+        fn widget_id(&self) -> &WidgetId {
+            &self.0
+        }
+
+        fn process(&mut self, _: &serde_json::Value, _: &[PolykitEvent]) -> WidgetPayload {
+            WidgetPayload { widget_id: String::new(), data: serde_json::json!({}), dirty: true }
+        }
+    }
+
+    #[test]
+    fn payload_without_widget_id_yields_error_payload() {
+        let id = WidgetId::new("polydata", "unlabelled", "main").unwrap();
+        let mut processor = Unlabelled(id);
+        let error = process_isolated(&mut processor, &serde_json::json!({}), &[]).unwrap_err();
+        assert_eq!(error.widget_id, "polydata:unlabelled:main");
+        assert!(error.data["error"].as_str().unwrap().contains("without a widget_id"));
+    }
+
+    /// Passes stream data straight through, like a raw log widget.
+    struct Passthrough(WidgetId);
+
+    impl WidgetProcessor for Passthrough {
+        fn widget_id(&self) -> &WidgetId {
+            &self.0
+        }
+
+        fn process(&mut self, data: &serde_json::Value, _: &[PolykitEvent]) -> WidgetPayload {
+            WidgetPayload { widget_id: self.0.to_string(), data: data.clone(), dirty: true }
+        }
+    }
+
+    fn log_widget() -> Box<dyn WidgetProcessor> {
+        Box::new(Passthrough(WidgetId::new("polydata", "logs", "main").unwrap()))
+    }
+
+    #[test]
+    fn sensitive_widget_payload_is_redacted() {
+        let mut registry = WidgetRegistry::new();
+        registry.register_sensitive(log_widget());
+        let stream = serde_json::json!({
+            "logs": [
+                { "level": "info", "user": "alice@example.com" },
+                { "level": "warn", "user": "system" },
+            ]
+        });
+
+        let payloads = registry.process_all(&stream, &mut EventBus::new("polydata"));
+        assert_eq!(payloads[0].widget_id, "polydata:logs:main");
+        assert!(!payloads[0].data.to_string().contains("alice@example.com"));
+        assert_eq!(payloads[0].data["logs"][0]["level"], "info");
+        assert_eq!(payloads[0].data["logs"][1]["user"], "system");
+    }
+
+    #[test]
+    fn unflagged_widget_payload_is_untouched() {
+        let mut registry = WidgetRegistry::new();
+        registry.register(log_widget());
+        let stream = serde_json::json!({ "logs": [{ "user": "alice@example.com" }] });
+        let payloads = registry.process_all(&stream, &mut EventBus::new("polydata"));
+        assert_eq!(payloads[0].data, stream);
+    }
+
+    /// Renders `{"ran": true}` at a fixed cost per call.
+    struct Expensive(WidgetId, u32);
+
+    impl WidgetProcessor for Expensive {
+        fn widget_id(&self) -> &WidgetId {
+            &self.0
+        }
+
+        fn process(&mut self, _: &serde_json::Value, _: &[PolykitEvent]) -> WidgetPayload {
+            let data = serde_json::json!({ "ran": true });
+            WidgetPayload { widget_id: self.0.to_string(), data, dirty: true }
+        }
+
+        fn cost(&self) -> u32 {
+            self.1
+        }
+    }
+
+    fn expensive(instance: &str, cost: u32) -> Box<dyn WidgetProcessor> {
+        Box::new(Expensive(WidgetId::new("polydata", "chart", instance).unwrap(), cost))
+    }
+
+    fn ran(payloads: &[WidgetPayload]) -> Vec<bool> {
+        payloads.iter().map(|p| p.data.get("ran").is_some()).collect()
+    }
+
+    #[test]
+    fn budget_defers_widgets_to_the_next_cycle() {
+        let mut registry = WidgetRegistry::new();
+        for instance in ["a", "b", "c", "d"] {
+            registry.register(expensive(instance, 3));
+        }
+        registry.set_cycle_budget(Some(6));
+        let mut bus = EventBus::new("polydata");
+
+        let first = registry.process_all(&serde_json::json!({}), &mut bus);
+        assert_eq!(ran(&first), [true, true, false, false]);
+        assert_eq!(first[2].widget_id, "polydata:chart:c");
+        assert_eq!(first[2].data, serde_json::json!({ "pending": true }));
+        assert!(!first[2].dirty);
+
+        let second = registry.process_all(&serde_json::json!({}), &mut bus);
+        assert_eq!(ran(&second), [false, false, true, true]);
+    }
+
+    #[test]
+    fn no_widget_is_starved_under_a_tight_budget() {
+        let mut registry = WidgetRegistry::new();
+        for (instance, cost) in [("a", 2), ("b", 5), ("c", 2), ("d", 9)] {
+            registry.register(expensive(instance, cost));
+        }
+        // Smaller than the most expensive widget, which still runs when first in line
+        registry.set_cycle_budget(Some(4));
+        let mut bus = EventBus::new("polydata");
+
+        let mut runs = [0; 4];
+        for _ in 0..8 {
+            let payloads = registry.process_all(&serde_json::json!({}), &mut bus);
+            assert_eq!(payloads.len(), 4);
+            for (count, ran) in runs.iter_mut().zip(ran(&payloads)) {
+                *count += ran as u32;
+            }
+        }
+        assert!(runs.iter().all(|&count| count >= 2), "{:?}", runs);
     }
 }
@@ -0,0 +1,109 @@
+//! Cooperative cancellation for long-running WASM calls
+//!
+//! Heavy loops poll a token every `CHECK_INTERVAL` items and stop early
+//! with a `Cancelled` result instead of running to completion. A token
+//! trips when a holder of a clone cancels it, or, for a budgeted token,
+//! once its clock passes the deadline. WASM is single-threaded, so the JS
+//! host can't cancel mid-call; exports take a time budget instead.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::clock::Clock;
+
+/// Items processed between cancellation checks.
+pub const CHECK_INTERVAL: usize = 64;
+
+/// Shared cancellation flag, with an optional deadline.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Deadline>,
+}
+
+#[derive(Clone)]
+struct Deadline {
+    clock: Arc<dyn Clock + Send + Sync>,
+    at_ms: u64,
+}
+
+impl fmt::Debug for Deadline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deadline").field("at_ms", &self.at_ms).finish()
+    }
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Token that cancels itself once `clock` reaches `budget_ms` past now.
+    pub fn with_budget(clock: Arc<dyn Clock + Send + Sync>, budget_ms: u64) -> Self {
+        let at_ms = clock.now_ms().saturating_add(budget_ms);
+        Self { cancelled: Arc::default(), deadline: Some(Deadline { clock, at_ms }) }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Cancelled explicitly, or past the deadline (which then sticks).
+    pub fn is_cancelled(&self) -> bool {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return true;
+        }
+        match &self.deadline {
+            Some(deadline) if deadline.clock.now_ms() >= deadline.at_ms => {
+                self.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Poll on every `CHECK_INTERVAL`-th item (including the first).
+    pub fn should_stop(&self, processed: usize) -> bool {
+        processed.is_multiple_of(CHECK_INTERVAL) && self.is_cancelled()
+    }
+}
+
+/// Returned when an operation stops early. `partial` holds whatever was
+/// safe to return (may be `()` when partial output would be unsafe).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cancelled<T> {
+    pub partial: T,
+    /// Items fully processed before stopping
+    pub processed: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_only_on_interval() {
+        let token = CancellationToken::new();
+        let host = token.clone();
+        assert!(!token.should_stop(0));
+        host.cancel();
+        assert!(token.should_stop(0));
+        assert!(!token.should_stop(CHECK_INTERVAL - 1));
+        assert!(token.should_stop(CHECK_INTERVAL * 3));
+    }
+
+    #[test]
+    fn budgeted_token_trips_at_its_deadline() {
+        let clock = Arc::new(crate::clock::FakeClock::new(1_000));
+        let token = CancellationToken::with_budget(clock.clone(), 50);
+        assert!(!token.should_stop(0));
+        clock.advance(49);
+        assert!(!token.is_cancelled());
+        clock.advance(1);
+        assert!(token.should_stop(CHECK_INTERVAL));
+        // Stays cancelled even if the clock goes backwards
+        clock.set(0);
+        assert!(token.clone().is_cancelled());
+    }
+}
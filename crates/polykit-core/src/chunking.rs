@@ -0,0 +1,162 @@
+//! Content-defined chunking for scatter/storage deduplication
+//!
+//! Splits data on content boundaries found by a Gear rolling hash, so an
+//! edit only reshapes the chunks around it and unchanged regions keep
+//! their chunk hashes (and dedupe across versions). Each chunk is
+//! identified by its BLAKE3 hash.
+
+use serde::{Deserialize, Serialize};
+use crate::error::{PolykitError, Result};
+
+/// A content-defined chunk of the input.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chunk {
+    pub offset: usize,
+    pub len: usize,
+    /// BLAKE3 of the chunk bytes
+    pub hash: [u8; 32],
+}
+
+/// Split `data` into content-defined chunks.
+///
+/// Chunks are at least `min` bytes (except the last), at most `max` bytes,
+/// and average roughly `avg` bytes (rounded up to a power of two).
+pub fn content_defined_chunks(data: &[u8], min: usize, avg: usize, max: usize) -> Result<Vec<Chunk>> {
+    if min == 0 || min > avg || avg > max {
+        return Err(PolykitError::Storage(format!(
+            "chunk sizes must satisfy 0 < min <= avg <= max (got {}/{}/{})",
+            min, avg, max
+        )));
+    }
+
+    let mask = (avg.next_power_of_two() as u64) - 1;
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < data.len() {
+        let len = next_boundary(&data[start..], min, max, mask);
+        chunks.push(Chunk {
+            offset: start,
+            len,
+            hash: blake3::hash(&data[start..start + len]).into(),
+        });
+        start += len;
+    }
+
+    Ok(chunks)
+}
+
+/// Length of the next chunk at the start of `data`.
+fn next_boundary(data: &[u8], min: usize, max: usize, mask: u64) -> usize {
+    if data.len() <= min {
+        return data.len();
+    }
+
+    let end = data.len().min(max);
+    let mut hash: u64 = 0;
+    for (i, byte) in data.iter().enumerate().take(end).skip(min) {
+        hash = (hash << 1).wrapping_add(GEAR[*byte as usize]);
+        if hash & mask == 0 {
+            return i + 1;
+        }
+    }
+    end
+}
+
+/// Gear table: 256 pseudo-random u64s (splitmix64, fixed seed) so chunk
+/// boundaries are stable across builds and platforms.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{Rng, SeededRng};
+
+    const MIN: usize = 256;
+    const AVG: usize = 1024;
+    const MAX: usize = 4096;
+
+    fn document(len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        SeededRng::seed_from_u64(42).fill_bytes(&mut data);
+        data
+    }
+
+    fn hashes(chunks: &[Chunk]) -> Vec<[u8; 32]> {
+        chunks.iter().map(|c| c.hash).collect()
+    }
+
+    #[test]
+    fn chunks_cover_input_within_bounds() {
+        let data = document(64 * 1024);
+        let chunks = content_defined_chunks(&data, MIN, AVG, MAX).unwrap();
+        let mut offset = 0;
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.offset, offset);
+            assert!(chunk.len <= MAX);
+            assert!(chunk.len >= MIN || i == chunks.len() - 1);
+            let bytes = &data[offset..offset + chunk.len];
+            assert_eq!(chunk.hash, <[u8; 32]>::from(blake3::hash(bytes)));
+            offset += chunk.len;
+        }
+        assert_eq!(offset, data.len());
+    }
+
+    #[test]
+    fn insertion_only_changes_nearby_chunks() {
+        let original = document(64 * 1024);
+        let mut edited = original.clone();
+        let at = original.len() / 2;
+        edited.splice(at..at, b"inserted bytes".iter().copied());
+
+        let before = content_defined_chunks(&original, MIN, AVG, MAX).unwrap();
+        let after = content_defined_chunks(&edited, MIN, AVG, MAX).unwrap();
+
+        // Chunks wholly before the edit are untouched
+        let prefix: Vec<_> = before.iter().filter(|c| c.offset + c.len <= at).collect();
+        assert!(!prefix.is_empty());
+        for (a, b) in prefix.iter().zip(&after) {
+            assert_eq!(a.hash, b.hash);
+        }
+
+        // Boundaries resynchronize: the tails match chunk-for-chunk
+        let (old, new) = (hashes(&before), hashes(&after));
+        let shared_tail =
+            old.iter().rev().zip(new.iter().rev()).take_while(|(a, b)| a == b).count();
+        let changed = new.len() - prefix.len() - shared_tail;
+        assert!(shared_tail > old.len() / 3, "only {} of {} chunks kept", shared_tail, old.len());
+        assert!(changed <= 3, "{} chunks changed", changed);
+    }
+
+    #[test]
+    fn chunking_is_deterministic() {
+        let data = document(16 * 1024);
+        assert_eq!(
+            content_defined_chunks(&data, MIN, AVG, MAX).unwrap(),
+            content_defined_chunks(&data, MIN, AVG, MAX).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_sizes_are_rejected() {
+        assert!(content_defined_chunks(b"data", 0, AVG, MAX).is_err());
+        assert!(content_defined_chunks(b"data", AVG, MIN, MAX).is_err());
+        assert!(content_defined_chunks(b"data", MIN, MAX, AVG).is_err());
+        assert!(content_defined_chunks(&[], MIN, AVG, MAX).unwrap().is_empty());
+    }
+}
@@ -4,7 +4,13 @@
 //! Classification drives scatter policy (k-of-n erasure coding,
 //! number of jurisdictions), retention, and access control.
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::clock::Clock;
+use crate::crypto;
+use crate::error::{PolykitError, Result};
 
 /// Data classification tiers, ordered by sensitivity.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
@@ -62,6 +68,86 @@ pub struct ScatterPolicy {
     pub jurisdictions: u32,
 }
 
+/// One shard's placement.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardAssignment {
+    pub index: u32,
+    pub provider: String,
+    pub jurisdiction: String,
+}
+
+/// Concrete shard-to-provider placement satisfying a ScatterPolicy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardPlan {
+    pub k: u32,
+    pub n: u32,
+    pub shards: Vec<ShardAssignment>,
+}
+
+impl ShardPlan {
+    /// Number of distinct jurisdictions shards were placed in.
+    pub fn jurisdiction_count(&self) -> usize {
+        let mut seen: Vec<&str> = self.shards.iter().map(|s| s.jurisdiction.as_str()).collect();
+        seen.sort_unstable();
+        seen.dedup();
+        seen.len()
+    }
+}
+
+impl ScatterPolicy {
+    /// Assign n shards to providers, round-robin across jurisdictions in
+    /// name order (providers in name order within each), so the result is
+    /// deterministic and spreads as widely as possible. Each provider holds
+    /// at most one shard.
+    pub fn plan(&self, providers: &BTreeMap<String, Vec<String>>) -> Result<ShardPlan> {
+        let pools: Vec<(&String, Vec<&String>)> = providers
+            .iter()
+            .filter(|(_, p)| !p.is_empty())
+            .map(|(j, p)| {
+                let mut sorted: Vec<&String> = p.iter().collect();
+                sorted.sort();
+                sorted.dedup();
+                (j, sorted)
+            })
+            .collect();
+
+        if pools.len() < self.jurisdictions as usize {
+            return Err(PolykitError::ClassificationViolation(format!(
+                "scatter needs {} jurisdictions, {} available",
+                self.jurisdictions,
+                pools.len()
+            )));
+        }
+        let available: usize = pools.iter().map(|(_, p)| p.len()).sum();
+        if available < self.n as usize {
+            return Err(PolykitError::ClassificationViolation(format!(
+                "scatter needs {} providers, {} available",
+                self.n, available
+            )));
+        }
+
+        let mut shards = Vec::with_capacity(self.n as usize);
+        let mut round = 0;
+        while shards.len() < self.n as usize {
+            for (jurisdiction, pool) in &pools {
+                if shards.len() == self.n as usize {
+                    break;
+                }
+                if let Some(provider) = pool.get(round) {
+                    shards.push(ShardAssignment {
+                        index: shards.len() as u32,
+                        provider: (*provider).clone(),
+                        jurisdiction: (*jurisdiction).clone(),
+                    });
+                }
+            }
+            round += 1;
+        }
+
+        Ok(ShardPlan { k: self.k, n: self.n, shards })
+    }
+}
+
 /// A classification rule: pattern → classification.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClassificationRule {
@@ -71,18 +157,466 @@ pub struct ClassificationRule {
     pub classification: Classification,
 }
 
-/// Classification policy: ordered list of rules + minimum floor.
+/// A floor applied to every path under a namespace prefix.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceMinimum {
+    /// Namespace prefix (e.g., "/finance"); matches on segment boundaries
+    pub prefix: String,
+    pub classification: Classification,
+}
+
+/// Seven years, in milliseconds (365-day years).
+pub const SEVEN_YEARS_MS: u64 = 7 * 365 * 86_400_000;
+
+/// How long data at each tier must be kept, in milliseconds. Tiers without
+/// an entry have no mandated retention; the app decides when to delete.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RetentionSchedule {
+    by_tier: BTreeMap<Classification, u64>,
+}
+
+impl RetentionSchedule {
+    /// No mandated retention for any tier.
+    pub fn none() -> Self {
+        Self { by_tier: BTreeMap::new() }
+    }
+
+    pub fn with(mut self, tier: Classification, retention_ms: u64) -> Self {
+        self.by_tier.insert(tier, retention_ms);
+        self
+    }
+
+    pub fn retention_ms(&self, tier: Classification) -> Option<u64> {
+        self.by_tier.get(&tier).copied()
+    }
+
+    /// Per tier, the longer of the two retentions.
+    pub fn merge(&self, other: &RetentionSchedule) -> RetentionSchedule {
+        let mut merged = self.clone();
+        for (tier, ms) in &other.by_tier {
+            let entry = merged.by_tier.entry(*tier).or_insert(*ms);
+            *entry = (*entry).max(*ms);
+        }
+        merged
+    }
+}
+
+impl Default for RetentionSchedule {
+    /// Seven years for Confidential and above, matching the `retention 7y`
+    /// on the compliance-tagged streams in `polykit_sanitize.fl` and
+    /// `polykit_compliance.fl`. That covers HIPAA's six-year retention of
+    /// required documentation (45 CFR 164.316(b)(2)(i)). Public and
+    /// Internal data have no mandated retention.
+    fn default() -> Self {
+        Self::none()
+            .with(Classification::Confidential, SEVEN_YEARS_MS)
+            .with(Classification::Restricted, SEVEN_YEARS_MS)
+            .with(Classification::Sovereign, SEVEN_YEARS_MS)
+    }
+}
+
+/// Classification policy: ordered list of rules + minimum floors.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClassificationPolicy {
     pub rules: Vec<ClassificationRule>,
     /// Minimum classification for all data (floor)
     pub minimum: Option<Classification>,
+    /// Per-namespace floors; the highest applicable one wins
+    #[serde(default)]
+    pub namespace_minimums: Vec<NamespaceMinimum>,
+    /// Retention per tier; omitted in JSON means the default schedule
+    #[serde(default)]
+    pub retention: RetentionSchedule,
+}
+
+impl ClassificationPolicy {
+    /// Mandated retention for data at `tier`, if any.
+    pub fn retention_ms(&self, tier: Classification) -> Option<u64> {
+        self.retention.retention_ms(tier)
+    }
+}
+
+/// A policy whose rule patterns have been validated by [`PolicyBuilder`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompiledPolicy {
+    policy: ClassificationPolicy,
+}
+
+impl CompiledPolicy {
+    /// Classify a path (equivalent to [`classify`] on the underlying policy).
+    pub fn classify(&self, path: &str) -> Classification {
+        classify(path, &self.policy)
+    }
+
+    pub fn policy(&self) -> &ClassificationPolicy {
+        &self.policy
+    }
+
+    pub fn into_policy(self) -> ClassificationPolicy {
+        self.policy
+    }
+}
+
+/// Builder for constructing classification policies.
+pub struct PolicyBuilder {
+    rules: Vec<ClassificationRule>,
+    minimum: Option<Classification>,
+    namespace_minimums: Vec<NamespaceMinimum>,
+    retention: RetentionSchedule,
+}
+
+impl PolicyBuilder {
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            minimum: None,
+            namespace_minimums: Vec::new(),
+            retention: RetentionSchedule::default(),
+        }
+    }
+
+    pub fn rule(mut self, pattern: &str, classification: Classification) -> Self {
+        self.rules.push(ClassificationRule {
+            pattern: pattern.to_string(),
+            classification,
+        });
+        self
+    }
+
+    pub fn minimum(mut self, tier: Classification) -> Self {
+        self.minimum = Some(tier);
+        self
+    }
+
+    pub fn namespace_minimum(mut self, prefix: &str, tier: Classification) -> Self {
+        self.namespace_minimums.push(NamespaceMinimum {
+            prefix: prefix.to_string(),
+            classification: tier,
+        });
+        self
+    }
+
+    /// Replace the default retention schedule.
+    pub fn retention(mut self, retention: RetentionSchedule) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Validate every rule pattern and produce a compiled policy.
+    pub fn build(self) -> Result<CompiledPolicy> {
+        for rule in &self.rules {
+            validate_pattern(&rule.pattern)?;
+        }
+        Ok(CompiledPolicy {
+            policy: ClassificationPolicy {
+                rules: self.rules,
+                minimum: self.minimum,
+                namespace_minimums: self.namespace_minimums,
+                retention: self.retention,
+            },
+        })
+    }
+}
+
+impl Default for PolicyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Layer a regional overlay onto a base policy. Since `classify` takes the
+/// highest applicable tier, the merged policy is at least as strict as
+/// either input: overlays can tighten the base but never loosen it.
+/// Retention likewise keeps the longer period per tier.
+pub fn merge_regional(
+    base: &ClassificationPolicy,
+    overlay: &ClassificationPolicy,
+) -> ClassificationPolicy {
+    let mut merged = base.clone();
+    merged.rules.extend(overlay.rules.iter().cloned());
+    merged.minimum = base.minimum.max(overlay.minimum);
+    merged.namespace_minimums.extend(overlay.namespace_minimums.iter().cloned());
+    merged.retention = base.retention.merge(&overlay.retention);
+    merged
+}
+
+/// Lint finding tagged with the policy it came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegionalLintFinding {
+    /// Region code (e.g., "us"), or `None` for the global policy
+    pub region: Option<String>,
+    pub finding: LintFinding,
+}
+
+/// Output of [`RegionalPolicyBuilder::build`]: the global policy, one
+/// merged policy per region, and lint findings across all of them.
+#[derive(Debug, Clone)]
+pub struct RegionalPolicy {
+    pub global: CompiledPolicy,
+    pub regional: BTreeMap<String, CompiledPolicy>,
+    pub findings: Vec<RegionalLintFinding>,
+}
+
+impl RegionalPolicy {
+    /// The policy for a region, falling back to global for regions
+    /// without an overlay.
+    pub fn for_region(&self, region: &str) -> &CompiledPolicy {
+        self.regional.get(region).unwrap_or(&self.global)
+    }
+}
+
+/// Assembles a global base policy, namespace minimums, and per-region
+/// overlays, then compiles and lints everything in one `build`.
+pub struct RegionalPolicyBuilder {
+    base: PolicyBuilder,
+    overlays: BTreeMap<String, ClassificationPolicy>,
+}
+
+impl RegionalPolicyBuilder {
+    pub fn new(base: ClassificationPolicy) -> Self {
+        Self {
+            base: PolicyBuilder {
+                rules: base.rules,
+                minimum: base.minimum,
+                namespace_minimums: base.namespace_minimums,
+                retention: base.retention,
+            },
+            overlays: BTreeMap::new(),
+        }
+    }
+
+    /// Add a namespace floor to the global base (and so to every region).
+    pub fn namespace_minimum(mut self, prefix: &str, tier: Classification) -> Self {
+        self.base = self.base.namespace_minimum(prefix, tier);
+        self
+    }
+
+    /// Add an overlay for a region code (e.g., "us", "eu"). A second
+    /// overlay for the same region is merged into the first.
+    pub fn overlay(mut self, region: &str, overlay: ClassificationPolicy) -> Self {
+        let merged = match self.overlays.remove(region) {
+            Some(existing) => merge_regional(&existing, &overlay),
+            None => overlay,
+        };
+        self.overlays.insert(region.to_string(), merged);
+        self
+    }
+
+    /// Compile the global and regional policies (failing on any invalid
+    /// pattern) and lint them. Findings on base rules are reported once,
+    /// under global; regional findings cover overlay rules only.
+    pub fn build(self) -> Result<RegionalPolicy> {
+        let global = self.base.build()?;
+        let mut findings: Vec<RegionalLintFinding> = lint_policy(global.policy())
+            .into_iter()
+            .map(|finding| RegionalLintFinding { region: None, finding })
+            .collect();
+
+        let base_rules = global.policy().rules.len();
+        let mut regional = BTreeMap::new();
+        for (region, overlay) in self.overlays {
+            let merged = merge_regional(global.policy(), &overlay);
+            for rule in &merged.rules[base_rules..] {
+                validate_pattern(&rule.pattern)?;
+            }
+            findings.extend(
+                lint_policy(&merged)
+                    .into_iter()
+                    .filter(|finding| finding.rule >= base_rules)
+                    .map(|finding| RegionalLintFinding { region: Some(region.clone()), finding }),
+            );
+            regional.insert(region, CompiledPolicy { policy: merged });
+        }
+
+        Ok(RegionalPolicy { global, regional, findings })
+    }
+}
+
+/// Check that a pattern is one of the forms `glob_match` understands:
+/// `**`, `*.ext`, `prefix/**`, or an exact path without wildcards.
+pub fn validate_pattern(pattern: &str) -> Result<()> {
+    let invalid = |reason: &str| {
+        Err(PolykitError::ClassificationViolation(format!(
+            "invalid pattern {:?}: {}",
+            pattern, reason
+        )))
+    };
+
+    if pattern.is_empty() {
+        return invalid("empty pattern");
+    }
+    if pattern == "**" {
+        return Ok(());
+    }
+    if let Some(ext) = pattern.strip_prefix("*.") {
+        if ext.is_empty() || ext.contains(['*', '/']) {
+            return invalid("extension must be non-empty and wildcard-free");
+        }
+        return Ok(());
+    }
+    if let Some(prefix) = pattern.strip_suffix("/**") {
+        if prefix.is_empty() || prefix.contains('*') {
+            return invalid("prefix must be non-empty and wildcard-free");
+        }
+        return Ok(());
+    }
+    if pattern.contains('*') {
+        return invalid("wildcards are only supported as `**`, `*.ext`, or `prefix/**`");
+    }
+    Ok(())
+}
+
+/// CSV header used by [`export_policy_csv`] and accepted by [`import_policy_csv`].
+pub const POLICY_CSV_HEADER: &str = "pattern,classification,kind";
+
+/// Header of the original two-column format, still accepted on import.
+const LEGACY_POLICY_CSV_HEADER: &str = "pattern,classification";
+
+/// Parse a policy from CSV with columns `pattern,classification,kind`.
+/// `kind` is `rule` (the default when the column is omitted), `namespace`
+/// for a namespace floor (the pattern column holds the prefix), or
+/// `minimum` for the global floor (pattern `**`). The header row is
+/// optional; blank lines are skipped. Tier names and patterns are
+/// validated, and errors name the offending line.
+pub fn import_policy_csv(csv: &str) -> Result<ClassificationPolicy> {
+    let mut policy = ClassificationPolicy {
+        rules: Vec::new(),
+        minimum: None,
+        namespace_minimums: Vec::new(),
+        retention: RetentionSchedule::default(),
+    };
+
+    for (i, line) in csv.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim();
+        let is_header = line.eq_ignore_ascii_case(POLICY_CSV_HEADER)
+            || line.eq_ignore_ascii_case(LEGACY_POLICY_CSV_HEADER);
+        if line.is_empty() || (i == 0 && is_header) {
+            continue;
+        }
+        let line_error = |msg: String| {
+            PolykitError::ClassificationViolation(format!("line {}: {}", line_no, msg))
+        };
+
+        let fields = split_csv_line(line).map_err(line_error)?;
+        if fields.len() != 2 && fields.len() != 3 {
+            return Err(line_error(format!("expected 2 or 3 columns, got {}", fields.len())));
+        }
+
+        let pattern = fields[0].trim();
+        let tier = fields[1].trim();
+        let kind = fields.get(2).map_or("rule", |k| k.trim());
+        let classification = Classification::from_str(tier)
+            .ok_or_else(|| line_error(format!("unknown classification {:?}", tier)))?;
+
+        match kind {
+            "rule" => {
+                validate_pattern(pattern).map_err(|e| match e {
+                    PolykitError::ClassificationViolation(msg) => line_error(msg),
+                    other => other,
+                })?;
+                policy.rules.push(ClassificationRule {
+                    pattern: pattern.to_string(),
+                    classification,
+                });
+            }
+            "namespace" => {
+                if !pattern.starts_with('/') || pattern.contains('*') {
+                    return Err(line_error(format!(
+                        "namespace prefix {:?} must be absolute and wildcard-free",
+                        pattern
+                    )));
+                }
+                policy.namespace_minimums.push(NamespaceMinimum {
+                    prefix: pattern.to_string(),
+                    classification,
+                });
+            }
+            "minimum" => {
+                if pattern != "**" {
+                    let msg = format!("minimum pattern must be **, not {:?}", pattern);
+                    return Err(line_error(msg));
+                }
+                if policy.minimum.is_some() {
+                    return Err(line_error("duplicate minimum".to_string()));
+                }
+                policy.minimum = Some(classification);
+            }
+            other => return Err(line_error(format!("unknown row kind {:?}", other))),
+        }
+    }
+
+    Ok(policy)
+}
+
+/// Serialize a policy to CSV: the minimum floor, then namespace floors,
+/// then rules, each tagged with its row kind so [`import_policy_csv`]
+/// restores the same policy.
+pub fn export_policy_csv(policy: &ClassificationPolicy) -> String {
+    let mut out = String::from(POLICY_CSV_HEADER);
+    out.push('\n');
+
+    let minimum = policy.minimum.map(|tier| ("**", tier, "minimum"));
+    let namespaces = policy
+        .namespace_minimums
+        .iter()
+        .map(|ns| (ns.prefix.as_str(), ns.classification, "namespace"));
+    let rules = policy.rules.iter().map(|r| (r.pattern.as_str(), r.classification, "rule"));
+    for (pattern, tier, kind) in minimum.into_iter().chain(namespaces).chain(rules) {
+        out.push_str(&quote_csv_field(pattern));
+        out.push(',');
+        out.push_str(tier.as_str());
+        out.push(',');
+        out.push_str(kind);
+        out.push('\n');
+    }
+
+    out
+}
+
+fn quote_csv_field(field: &str) -> String {
+    if field.contains([',', '"']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn split_csv_line(line: &str) -> std::result::Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("unterminated quoted field".to_string());
+    }
+    fields.push(current);
+    Ok(fields)
 }
 
 /// Evaluate classification for a given path against a policy.
 pub fn classify(path: &str, policy: &ClassificationPolicy) -> Classification {
     let mut result = policy.minimum.unwrap_or(Classification::Public);
 
+    for ns in &policy.namespace_minimums {
+        if in_namespace(&ns.prefix, path) && ns.classification > result {
+            result = ns.classification;
+        }
+    }
+
     for rule in &policy.rules {
         if glob_match(&rule.pattern, path) && rule.classification > result {
             result = rule.classification;
@@ -92,6 +626,212 @@ pub fn classify(path: &str, policy: &ClassificationPolicy) -> Classification {
     result
 }
 
+/// Why a policy rule can't affect classification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LintKind {
+    /// Same pattern and tier as the rule at index `of`
+    Duplicate { of: usize },
+    /// Every path this rule matches is also matched by the rule at index
+    /// `by`, at the same or a higher tier
+    Shadowed { by: usize },
+    /// The tier is at or below the policy minimum, so the rule never raises it
+    BelowMinimum,
+    /// The pattern is malformed and `glob_match` would never match it as intended
+    Unmatchable { reason: String },
+}
+
+/// A lint finding for the rule at index `rule` in `policy.rules`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintFinding {
+    pub rule: usize,
+    pub pattern: String,
+    pub kind: LintKind,
+}
+
+/// Find rules that never change the outcome of `classify`: duplicates,
+/// rules shadowed by a broader rule of the same or higher tier, rules
+/// under the policy floor, and malformed patterns. At most one finding
+/// is reported per rule.
+pub fn lint_policy(policy: &ClassificationPolicy) -> Vec<LintFinding> {
+    let rules = &policy.rules;
+    let valid: Vec<bool> = rules.iter().map(|r| validate_pattern(&r.pattern).is_ok()).collect();
+    let mut findings = Vec::new();
+
+    for (i, rule) in rules.iter().enumerate() {
+        let kind = if let Err(e) = validate_pattern(&rule.pattern) {
+            Some(LintKind::Unmatchable { reason: e.to_string() })
+        } else if let Some(of) = (0..i).find(|&j| {
+            rules[j].pattern == rule.pattern && rules[j].classification == rule.classification
+        }) {
+            Some(LintKind::Duplicate { of })
+        } else if policy.minimum.is_some_and(|min| rule.classification <= min) {
+            Some(LintKind::BelowMinimum)
+        } else {
+            (0..rules.len())
+                .find(|&j| {
+                    let other = &rules[j];
+                    j != i
+                        && valid[j]
+                        && other.pattern != rule.pattern
+                        && other.classification >= rule.classification
+                        && pattern_covers(&other.pattern, &rule.pattern)
+                })
+                .or_else(|| {
+                    // Same pattern, different tier: the lower one never wins
+                    (0..rules.len()).find(|&j| {
+                        rules[j].pattern == rule.pattern
+                            && rules[j].classification > rule.classification
+                    })
+                })
+                .map(|by| LintKind::Shadowed { by })
+        };
+        if let Some(kind) = kind {
+            findings.push(LintFinding { rule: i, pattern: rule.pattern.clone(), kind });
+        }
+    }
+    findings
+}
+
+/// Whether every path matched by `inner` is also matched by `outer`
+/// (both valid patterns, see `validate_pattern`).
+fn pattern_covers(outer: &str, inner: &str) -> bool {
+    if outer == "**" {
+        return true;
+    }
+    if inner == "**" {
+        return false;
+    }
+    if let Some(ext) = outer.strip_prefix("*.") {
+        return inner.strip_prefix("*.") == Some(ext)
+            || (!inner.contains('*') && glob_match(outer, inner));
+    }
+    if let Some(prefix) = outer.strip_suffix("/**") {
+        return match inner.strip_suffix("/**") {
+            Some(inner_prefix) => in_namespace(prefix, inner_prefix),
+            None => !inner.starts_with("*.") && in_namespace(prefix, inner),
+        };
+    }
+    outer == inner
+}
+
+/// Classify many paths, stopping early if `token` is cancelled. On
+/// cancellation the classifications computed so far are returned.
+pub fn classify_batch(
+    paths: &[&str],
+    policy: &ClassificationPolicy,
+    token: &CancellationToken,
+) -> std::result::Result<Vec<Classification>, Cancelled<Vec<Classification>>> {
+    let mut results = Vec::with_capacity(paths.len());
+    for (i, path) in paths.iter().enumerate() {
+        if token.should_stop(i) {
+            return Err(Cancelled { processed: results.len(), partial: results });
+        }
+        results.push(classify(path, policy));
+    }
+    Ok(results)
+}
+
+/// Direction of a tier transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TierChange {
+    Upgrade,
+    Declassification,
+    Unchanged,
+}
+
+/// Evidence record for a change in a path's classification. Parallels the
+/// sanitize audit entry: witness hash, hash-chain link, ML-DSA-87 signature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassificationAuditRecord {
+    pub timestamp_ms: u64,
+    pub path: String,
+    pub from: Classification,
+    pub to: Classification,
+    pub change: TierChange,
+    pub actor: String,
+    pub justification: String,
+    /// SHA3-256 over the fields above (hex)
+    pub witness_hash: String,
+    /// Link over the previous record's chain_hash and this witness_hash
+    pub chain_hash: String,
+    pub witness_signature: Vec<u8>,
+    pub witness_key_id: String,
+}
+
+impl ClassificationAuditRecord {
+    /// Link this record after `prev_chain_hash` ("" starts a new chain).
+    pub fn chain_after(&mut self, prev_chain_hash: &str) {
+        let mut input = prev_chain_hash.as_bytes().to_vec();
+        input.extend_from_slice(self.witness_hash.as_bytes());
+        self.chain_hash = hex(&crypto::sha3_256(&input));
+    }
+
+    /// Sign the witness hash with the witness key.
+    pub fn sign(&mut self, key_id: &str, secret_key: &[u8]) -> Result<()> {
+        self.witness_signature = crypto::sign(secret_key, self.witness_hash.as_bytes())?;
+        self.witness_key_id = key_id.to_string();
+        Ok(())
+    }
+}
+
+/// Record a classification change for `path`. `clock` supplies the
+/// timestamp (`HostClock` in production, `FakeClock` in tests).
+pub fn audit_change(
+    path: &str,
+    from: Classification,
+    to: Classification,
+    actor: &str,
+    justification: &str,
+    clock: &dyn Clock,
+) -> ClassificationAuditRecord {
+    let timestamp_ms = clock.now_ms();
+    let change = match to.cmp(&from) {
+        std::cmp::Ordering::Greater => TierChange::Upgrade,
+        std::cmp::Ordering::Less => TierChange::Declassification,
+        std::cmp::Ordering::Equal => TierChange::Unchanged,
+    };
+    // Length-prefixed so a "::" inside a path or justification cannot shift
+    // bytes between fields and collide with a different record.
+    let mut witness_input = Vec::new();
+    for field in [
+        &timestamp_ms.to_be_bytes()[..],
+        path.as_bytes(),
+        from.as_str().as_bytes(),
+        to.as_str().as_bytes(),
+        actor.as_bytes(),
+        justification.as_bytes(),
+    ] {
+        witness_input.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        witness_input.extend_from_slice(field);
+    }
+
+    ClassificationAuditRecord {
+        timestamp_ms,
+        path: path.to_string(),
+        from,
+        to,
+        change,
+        actor: actor.to_string(),
+        justification: justification.to_string(),
+        witness_hash: hex(&crypto::sha3_256(&witness_input)),
+        chain_hash: String::new(),
+        witness_signature: Vec::new(),
+        witness_key_id: String::new(),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn in_namespace(prefix: &str, path: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
 fn glob_match(pattern: &str, path: &str) -> bool {
     // Simplified glob matching — production uses estream-kernel::patterns::glob
     if pattern == "**" {
@@ -101,7 +841,458 @@ fn glob_match(pattern: &str, path: &str) -> bool {
         return path.ends_with(&format!(".{}", ext));
     }
     if let Some(prefix) = pattern.strip_suffix("/**") {
-        return path.starts_with(prefix);
+        return in_namespace(prefix, path);
     }
     path == pattern
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATHS: &[&str] = &[
+        "/finance/q3.xlsx",
+        "/finance/budget.txt",
+        "/hr/payroll.csv",
+        "/public/readme.md",
+        "/notes.xlsx",
+        "/sovereign",
+    ];
+
+    #[test]
+    fn builder_matches_manual_policy() {
+        let manual = ClassificationPolicy {
+            rules: vec![
+                ClassificationRule {
+                    pattern: "*.xlsx".to_string(),
+                    classification: Classification::Internal,
+                },
+                ClassificationRule {
+                    pattern: "/finance/**".to_string(),
+                    classification: Classification::Confidential,
+                },
+                ClassificationRule {
+                    pattern: "/sovereign".to_string(),
+                    classification: Classification::Sovereign,
+                },
+            ],
+            minimum: Some(Classification::Public),
+            namespace_minimums: vec![NamespaceMinimum {
+                prefix: "/hr".to_string(),
+                classification: Classification::Restricted,
+            }],
+            retention: RetentionSchedule::default(),
+        };
+        let built = PolicyBuilder::new()
+            .rule("*.xlsx", Classification::Internal)
+            .rule("/finance/**", Classification::Confidential)
+            .rule("/sovereign", Classification::Sovereign)
+            .minimum(Classification::Public)
+            .namespace_minimum("/hr", Classification::Restricted)
+            .build()
+            .unwrap();
+
+        for path in PATHS {
+            assert_eq!(built.classify(path), classify(path, &manual), "{}", path);
+        }
+        assert_eq!(built.classify("/finance/q3.xlsx"), Classification::Confidential);
+        assert_eq!(built.classify("/hr/payroll.csv"), Classification::Restricted);
+    }
+
+    #[test]
+    fn builder_rejects_invalid_pattern() {
+        let err = PolicyBuilder::new().rule("/fin*/x", Classification::Internal).build();
+        assert!(err.is_err());
+    }
+
+    fn rule_pairs(policy: &ClassificationPolicy) -> Vec<(String, Classification)> {
+        policy.rules.iter().map(|r| (r.pattern.clone(), r.classification)).collect()
+    }
+
+    #[test]
+    fn import_rejects_unknown_tier() {
+        let err = import_policy_csv("pattern,classification\n*.xlsx,TopSecret\n").unwrap_err();
+        assert!(format!("{:?}", err).contains("line 2"), "{:?}", err);
+    }
+
+    #[test]
+    fn import_reads_rules_in_order() {
+        let csv = "pattern,classification\n\
+                   *.xlsx,internal\n\
+                   \"/finance/**\",confidential\n\
+                   \n\
+                   /sovereign,sovereign\n";
+        let policy = import_policy_csv(csv).unwrap();
+        assert_eq!(
+            rule_pairs(&policy),
+            vec![
+                ("*.xlsx".to_string(), Classification::Internal),
+                ("/finance/**".to_string(), Classification::Confidential),
+                ("/sovereign".to_string(), Classification::Sovereign),
+            ]
+        );
+        assert!(import_policy_csv("/fin*/x,internal\n").is_err());
+        assert!(import_policy_csv("*.xlsx,internal,extra\n").is_err());
+    }
+
+    #[test]
+    fn rules_round_trip_through_csv() {
+        let policy = PolicyBuilder::new()
+            .rule("*.xlsx", Classification::Internal)
+            .rule("/reports/a,b.csv", Classification::Restricted)
+            .rule("/finance/**", Classification::Confidential)
+            .build()
+            .unwrap()
+            .into_policy();
+        let imported = import_policy_csv(&export_policy_csv(&policy)).unwrap();
+        assert_eq!(rule_pairs(&imported), rule_pairs(&policy));
+    }
+
+    fn floors() -> ClassificationPolicy {
+        PolicyBuilder::new()
+            .rule("*.xlsx", Classification::Internal)
+            .rule("/finance/**", Classification::Restricted)
+            .minimum(Classification::Public)
+            .namespace_minimum("/finance", Classification::Confidential)
+            .namespace_minimum("/", Classification::Internal)
+            .build()
+            .unwrap()
+            .into_policy()
+    }
+
+    #[test]
+    fn namespace_floor_applies_only_inside_namespace() {
+        let policy = PolicyBuilder::new()
+            .minimum(Classification::Public)
+            .namespace_minimum("/finance", Classification::Confidential)
+            .build()
+            .unwrap();
+        assert_eq!(policy.classify("/finance/x"), Classification::Confidential);
+        assert_eq!(policy.classify("/finance"), Classification::Confidential);
+        assert_eq!(policy.classify("/public/y"), Classification::Public);
+        assert_eq!(policy.classify("/financial/x"), Classification::Public);
+    }
+
+    #[test]
+    fn prefix_glob_respects_segment_boundaries() {
+        assert!(glob_match("/finance/**", "/finance/q3.xlsx"));
+        assert!(glob_match("/finance/**", "/finance"));
+        assert!(!glob_match("/finance/**", "/financial/x"));
+    }
+
+    #[test]
+    fn floors_round_trip_through_csv() {
+        let policy = floors();
+        let imported = import_policy_csv(&export_policy_csv(&policy)).unwrap();
+
+        assert_eq!(imported.minimum, Some(Classification::Public));
+        let prefixes: Vec<_> = imported
+            .namespace_minimums
+            .iter()
+            .map(|ns| (ns.prefix.as_str(), ns.classification))
+            .collect();
+        assert_eq!(
+            prefixes,
+            vec![("/finance", Classification::Confidential), ("/", Classification::Internal)]
+        );
+        assert_eq!(rule_pairs(&imported), rule_pairs(&policy));
+        for path in PATHS.iter().chain(&["/financial/x", "relative.txt"]) {
+            assert_eq!(classify(path, &imported), classify(path, &policy), "{}", path);
+        }
+    }
+
+    #[test]
+    fn legacy_two_column_csv_still_imports() {
+        let policy = import_policy_csv("pattern,classification\n*.xlsx,internal\n").unwrap();
+        assert_eq!(rule_pairs(&policy), vec![("*.xlsx".to_string(), Classification::Internal)]);
+    }
+
+    #[test]
+    fn invalid_floor_rows_are_rejected() {
+        assert!(import_policy_csv("finance,confidential,namespace\n").is_err());
+        assert!(import_policy_csv("/finance/**,confidential,namespace\n").is_err());
+        assert!(import_policy_csv("/x,public,minimum\n").is_err());
+        assert!(import_policy_csv("**,public,minimum\n**,internal,minimum\n").is_err());
+        assert!(import_policy_csv("*.xlsx,internal,floor\n").is_err());
+    }
+
+    #[test]
+    fn upgrade_is_audited_with_actor() {
+        let clock = crate::clock::FakeClock::new(1_000);
+        let record = audit_change(
+            "/finance/q3.xlsx",
+            Classification::Internal,
+            Classification::Restricted,
+            "alice",
+            "contains forecasts",
+            &clock,
+        );
+        assert_eq!(record.change, TierChange::Upgrade);
+        assert_eq!(record.from, Classification::Internal);
+        assert_eq!(record.to, Classification::Restricted);
+        assert_eq!(record.actor, "alice");
+        assert_eq!(record.timestamp_ms, 1_000);
+        assert!(!record.witness_hash.is_empty());
+    }
+
+    #[test]
+    fn declassification_is_audited_and_chains() {
+        let clock = crate::clock::FakeClock::new(2_000);
+        let mut upgrade = audit_change(
+            "/hr/payroll.csv",
+            Classification::Public,
+            Classification::Sovereign,
+            "bob",
+            "payroll",
+            &clock,
+        );
+        upgrade.chain_after("");
+        let mut declassified = audit_change(
+            "/hr/payroll.csv",
+            Classification::Sovereign,
+            Classification::Internal,
+            "carol",
+            "redacted copy",
+            &clock,
+        );
+        declassified.chain_after(&upgrade.chain_hash);
+        assert_eq!(declassified.change, TierChange::Declassification);
+        assert_eq!(declassified.from, Classification::Sovereign);
+        assert_eq!(declassified.to, Classification::Internal);
+        assert_eq!(declassified.actor, "carol");
+        assert_ne!(declassified.chain_hash, upgrade.chain_hash);
+    }
+
+    #[test]
+    fn witness_fields_cannot_be_shifted() {
+        let clock = crate::clock::FakeClock::new(0);
+        let a = audit_change(
+            "/a",
+            Classification::Public,
+            Classification::Internal,
+            "x::y",
+            "z",
+            &clock,
+        );
+        let b = audit_change(
+            "/a",
+            Classification::Public,
+            Classification::Internal,
+            "x",
+            "y::z",
+            &clock,
+        );
+        assert_ne!(a.witness_hash, b.witness_hash);
+    }
+
+    fn providers(layout: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        layout
+            .iter()
+            .map(|(j, p)| (j.to_string(), p.iter().map(|s| s.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn confidential_plan_spreads_across_three_jurisdictions() {
+        let available = providers(&[
+            ("ch", &["ch-a", "ch-b", "ch-c"]),
+            ("de", &["de-a", "de-b"]),
+            ("is", &["is-a", "is-b", "is-c"]),
+        ]);
+        let policy = Classification::Confidential.scatter_policy();
+        let plan = policy.plan(&available).unwrap();
+
+        assert_eq!((plan.k, plan.n), (5, 7));
+        assert_eq!(plan.shards.len(), 7);
+        assert!(plan.jurisdiction_count() >= policy.jurisdictions as usize);
+        let indices: Vec<u32> = plan.shards.iter().map(|s| s.index).collect();
+        assert_eq!(indices, (0..7).collect::<Vec<_>>());
+        let mut providers: Vec<&str> = plan.shards.iter().map(|s| s.provider.as_str()).collect();
+        providers.sort_unstable();
+        providers.dedup();
+        assert_eq!(providers.len(), 7, "each provider holds at most one shard");
+    }
+
+    #[test]
+    fn plan_is_deterministic() {
+        let available = providers(&[("is", &["is-b", "is-a"]), ("ch", &["ch-a", "ch-b"])]);
+        let policy = Classification::Public.scatter_policy();
+        let plan = policy.plan(&available).unwrap();
+        assert_eq!(plan.shards, policy.plan(&available).unwrap().shards);
+        let placed: Vec<&str> = plan.shards.iter().map(|s| s.provider.as_str()).collect();
+        assert_eq!(placed, ["ch-a", "is-a", "ch-b"]);
+    }
+
+    #[test]
+    fn insufficient_providers_are_rejected() {
+        let policy = Classification::Confidential.scatter_policy();
+        let two_jurisdictions = providers(&[("ch", &["a", "b", "c", "d"]), ("de", &["e", "f"])]);
+        assert!(policy.plan(&two_jurisdictions).is_err());
+        let too_few = providers(&[("ch", &["a", "b"]), ("de", &["c", "d"]), ("is", &["e", "f"])]);
+        assert!(policy.plan(&too_few).is_err());
+    }
+
+    #[test]
+    fn default_retention_covers_confidential_and_above() {
+        let policy = PolicyBuilder::new().build().unwrap().into_policy();
+        assert_eq!(policy.retention_ms(Classification::Public), None);
+        assert_eq!(policy.retention_ms(Classification::Internal), None);
+        let sensitive =
+            [Classification::Confidential, Classification::Restricted, Classification::Sovereign];
+        for tier in sensitive {
+            assert_eq!(policy.retention_ms(tier), Some(SEVEN_YEARS_MS));
+        }
+    }
+
+    #[test]
+    fn retention_comes_from_the_policy() {
+        let policy: ClassificationPolicy = serde_json::from_str(
+            r#"{"rules": [], "minimum": null, "retention": {"Internal": 1000, "Sovereign": 5000}}"#,
+        )
+        .unwrap();
+        assert_eq!(policy.retention_ms(Classification::Internal), Some(1_000));
+        assert_eq!(policy.retention_ms(Classification::Confidential), None);
+
+        let omitted: ClassificationPolicy =
+            serde_json::from_str(r#"{"rules": [], "minimum": null}"#).unwrap();
+        assert_eq!(omitted.retention, RetentionSchedule::default());
+    }
+
+    #[test]
+    fn regional_overlay_keeps_the_longer_retention() {
+        let base = PolicyBuilder::new().build().unwrap().into_policy();
+        let longer = RetentionSchedule::none().with(Classification::Sovereign, 2 * SEVEN_YEARS_MS);
+        let overlay = PolicyBuilder::new()
+            .retention(longer)
+            .build()
+            .unwrap()
+            .into_policy();
+        let merged = merge_regional(&base, &overlay);
+        assert_eq!(merged.retention_ms(Classification::Sovereign), Some(2 * SEVEN_YEARS_MS));
+        assert_eq!(merged.retention_ms(Classification::Confidential), Some(SEVEN_YEARS_MS));
+    }
+
+    fn rules(rules: &[(&str, Classification)]) -> ClassificationPolicy {
+        ClassificationPolicy {
+            rules: rules
+                .iter()
+                .map(|(pattern, classification)| ClassificationRule {
+                    pattern: pattern.to_string(),
+                    classification: *classification,
+                })
+                .collect(),
+            minimum: None,
+            namespace_minimums: Vec::new(),
+            retention: RetentionSchedule::default(),
+        }
+    }
+
+    #[test]
+    fn lint_reports_shadowed_and_duplicate_rules() {
+        let policy = rules(&[
+            ("/finance/**", Classification::Restricted),
+            ("/finance/reports/**", Classification::Confidential),
+            ("*.xlsx", Classification::Internal),
+            ("*.xlsx", Classification::Internal),
+        ]);
+        let findings = lint_policy(&policy);
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].rule, 1);
+        assert_eq!(findings[0].kind, LintKind::Shadowed { by: 0 });
+        assert_eq!(findings[1].rule, 3);
+        assert_eq!(findings[1].kind, LintKind::Duplicate { of: 2 });
+    }
+
+    #[test]
+    fn narrower_rule_at_a_higher_tier_is_not_shadowed() {
+        let policy = rules(&[
+            ("/finance/**", Classification::Internal),
+            ("/finance/payroll/**", Classification::Restricted),
+            ("/financials/**", Classification::Public),
+        ]);
+        assert!(lint_policy(&policy).is_empty());
+    }
+
+    #[test]
+    fn lint_reports_unmatchable_and_below_minimum_rules() {
+        let mut policy = rules(&[
+            ("/fin*/x", Classification::Restricted),
+            ("/public/**", Classification::Public),
+        ]);
+        policy.minimum = Some(Classification::Internal);
+        let findings = lint_policy(&policy);
+        assert!(matches!(findings[0].kind, LintKind::Unmatchable { .. }));
+        assert_eq!(findings[1].kind, LintKind::BelowMinimum);
+    }
+
+    #[test]
+    fn regional_builder_compiles_and_lints_in_one_pass() {
+        let global = rules(&[
+            ("*.xlsx", Classification::Internal),
+            ("/finance/**", Classification::Confidential),
+        ]);
+        let us = rules(&[
+            ("/finance/payroll/**", Classification::Restricted),
+            // Already covered by the base /finance/** rule
+            ("/finance/q3/**", Classification::Internal),
+        ]);
+        let assembled = RegionalPolicyBuilder::new(global)
+            .namespace_minimum("/finance", Classification::Confidential)
+            .overlay("us", us)
+            .build()
+            .unwrap();
+
+        let us = assembled.for_region("us");
+        assert_eq!(us.classify("/finance/payroll/june.csv"), Classification::Restricted);
+        assert_eq!(us.classify("/finance/readme.md"), Classification::Confidential);
+        assert_eq!(us.classify("/notes.xlsx"), Classification::Internal);
+        // Regions without an overlay fall back to the global policy
+        let eu = assembled.for_region("eu");
+        assert_eq!(eu.classify("/finance/payroll/june.csv"), Classification::Confidential);
+
+        assert_eq!(assembled.findings.len(), 1);
+        let finding = &assembled.findings[0];
+        assert_eq!(finding.region.as_deref(), Some("us"));
+        assert_eq!(finding.finding.pattern, "/finance/q3/**");
+        assert_eq!(finding.finding.kind, LintKind::Shadowed { by: 1 });
+    }
+
+    #[test]
+    fn regional_builder_rejects_invalid_overlay_patterns() {
+        let result = RegionalPolicyBuilder::new(rules(&[]))
+            .overlay("eu", rules(&[("/gdpr*/x", Classification::Restricted)]))
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn cancelled_batch_stops_at_next_check() {
+        let policy = PolicyBuilder::new()
+            .rule("/finance/**", Classification::Confidential)
+            .build()
+            .unwrap()
+            .into_policy();
+        let paths: Vec<String> = (0..10_000).map(|i| format!("/finance/{}.csv", i)).collect();
+        let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+
+        let token = CancellationToken::new();
+        let all = classify_batch(&paths, &policy, &token).unwrap();
+        assert_eq!(all.len(), paths.len());
+
+        token.cancel();
+        let cancelled = classify_batch(&paths, &policy, &token).unwrap_err();
+        assert_eq!(cancelled.processed, 0);
+        assert!(cancelled.partial.is_empty());
+    }
+
+    #[test]
+    fn exhausted_budget_stops_a_batch() {
+        let policy = PolicyBuilder::new().build().unwrap().into_policy();
+        let paths = ["/a.txt"; 100];
+        let clock = std::sync::Arc::new(crate::clock::FakeClock::new(0));
+        let token = CancellationToken::with_budget(clock.clone(), 10);
+        assert_eq!(classify_batch(&paths, &policy, &token).unwrap().len(), 100);
+        clock.advance(10);
+        assert!(classify_batch(&paths, &policy, &token).is_err());
+    }
+}
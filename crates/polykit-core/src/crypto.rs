@@ -0,0 +1,1092 @@
+//! PQ crypto host bindings
+//!
+//! ML-DSA signing and verification are implemented by the identity
+//! circuit (circuits/fl/polykit_identity.fl). This module is the thin
+//! bridge the hand-written runtime crates use to reach them, plus the
+//! SHA3-256 hash shared by audit and sync, and the HKDF / AES-256-GCM
+//! primitives used for field-level encryption and sealed messages.
+//! Hybrid Ed25519+ML-DSA-87 signatures serve partners that still require
+//! a classical signature during the PQ transition.
+//!
+//! All randomness goes through `Rng`: the WASM sandbox has no system RNG,
+//! so production uses host entropy and tests use a seeded ChaCha20 stream.
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use crate::error::{PolykitError, Result};
+use crate::secret::Secret;
+
+/// ML-DSA-87 public key size (FIPS 204, Level 5)
+pub const MLDSA87_PUBLIC_KEY_LEN: usize = 2592;
+/// ML-DSA-87 secret key size
+pub const MLDSA87_SECRET_KEY_LEN: usize = 4896;
+/// ML-DSA-87 signature size
+pub const MLDSA87_SIGNATURE_LEN: usize = 4627;
+
+/// ML-DSA-65 public key size (FIPS 204, Level 3)
+pub const MLDSA65_PUBLIC_KEY_LEN: usize = 1952;
+/// ML-DSA-65 secret key size
+pub const MLDSA65_SECRET_KEY_LEN: usize = 4032;
+/// ML-DSA-65 signature size
+pub const MLDSA65_SIGNATURE_LEN: usize = 3309;
+
+/// Ed25519 public key size (RFC 8032)
+pub const ED25519_PUBLIC_KEY_LEN: usize = 32;
+/// Ed25519 secret key (seed) size
+pub const ED25519_SECRET_KEY_LEN: usize = 32;
+/// Ed25519 signature size
+pub const ED25519_SIGNATURE_LEN: usize = 64;
+
+/// ML-KEM-1024 public (encapsulation) key size (FIPS 203, Level 5)
+pub const MLKEM1024_PUBLIC_KEY_LEN: usize = 1568;
+/// ML-KEM-1024 secret (decapsulation) key size
+pub const MLKEM1024_SECRET_KEY_LEN: usize = 3168;
+/// ML-KEM-1024 ciphertext size
+pub const MLKEM1024_CIPHERTEXT_LEN: usize = 1568;
+
+/// SHA3-256 digest.
+pub fn sha3_256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha3_256::new();
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// AES-256-GCM nonce size
+pub const AEAD_NONCE_LEN: usize = 12;
+
+/// Derive a 32-byte subkey with HKDF-SHA3-256.
+pub fn hkdf_sha3_256(ikm: &[u8], info: &[u8]) -> Result<Secret<[u8; 32]>> {
+    let mut okm = Secret::new([0u8; 32]);
+    Hkdf::<Sha3_256>::new(None, ikm)
+        .expand(info, &mut *okm)
+        .map_err(|_| PolykitError::Crypto("HKDF expand failed".to_string()))?;
+    Ok(okm)
+}
+
+/// Seal `plaintext` with AES-256-GCM. Output is ciphertext || tag.
+pub fn aead_seal(key: &[u8; 32], nonce: &[u8; AEAD_NONCE_LEN], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|_| PolykitError::Crypto("invalid AES-256-GCM key".to_string()))?;
+    cipher
+        .encrypt(Nonce::from_slice(nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| PolykitError::Crypto("AES-256-GCM seal failed".to_string()))
+}
+
+/// Open an AES-256-GCM ciphertext. Fails on a wrong key, nonce, or AAD.
+pub fn aead_open(key: &[u8; 32], nonce: &[u8; AEAD_NONCE_LEN], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|_| PolykitError::Crypto("invalid AES-256-GCM key".to_string()))?;
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| PolykitError::Crypto("AES-256-GCM open failed".to_string()))
+}
+
+/// Signature scheme. Encoded signatures start with the scheme's tag byte,
+/// so they self-describe; the public key length then pins the scheme, so a
+/// signature can't be checked under a different one than its key's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Algorithm {
+    /// ML-DSA-87 (FIPS 204, Level 5)
+    #[default]
+    MlDsa87,
+    /// ML-DSA-65 (FIPS 204, Level 3): smaller keys and signatures
+    MlDsa65,
+}
+
+impl Algorithm {
+    pub fn tag(&self) -> u8 {
+        match self {
+            Algorithm::MlDsa87 => 0x87,
+            Algorithm::MlDsa65 => 0x65,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0x87 => Some(Algorithm::MlDsa87),
+            0x65 => Some(Algorithm::MlDsa65),
+            _ => None,
+        }
+    }
+
+    pub fn public_key_len(&self) -> usize {
+        match self {
+            Algorithm::MlDsa87 => MLDSA87_PUBLIC_KEY_LEN,
+            Algorithm::MlDsa65 => MLDSA65_PUBLIC_KEY_LEN,
+        }
+    }
+
+    pub fn secret_key_len(&self) -> usize {
+        match self {
+            Algorithm::MlDsa87 => MLDSA87_SECRET_KEY_LEN,
+            Algorithm::MlDsa65 => MLDSA65_SECRET_KEY_LEN,
+        }
+    }
+
+    pub fn signature_len(&self) -> usize {
+        match self {
+            Algorithm::MlDsa87 => MLDSA87_SIGNATURE_LEN,
+            Algorithm::MlDsa65 => MLDSA65_SIGNATURE_LEN,
+        }
+    }
+
+    pub fn security_level(&self) -> SecurityLevel {
+        match self {
+            Algorithm::MlDsa87 => SecurityLevel::Level5,
+            Algorithm::MlDsa65 => SecurityLevel::Level3,
+        }
+    }
+}
+
+/// NIST PQC security category, ordered weakest to strongest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SecurityLevel {
+    /// At least as hard to break as AES-128
+    Level1,
+    /// At least as hard to break as AES-192
+    Level3,
+    /// At least as hard to break as AES-256
+    Level5,
+}
+
+/// Key encapsulation scheme (FIPS 203), implemented by the identity circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum KemAlgorithm {
+    /// ML-KEM-1024 (Level 5)
+    #[default]
+    MlKem1024,
+    /// ML-KEM-768 (Level 3)
+    MlKem768,
+}
+
+impl KemAlgorithm {
+    pub fn security_level(&self) -> SecurityLevel {
+        match self {
+            KemAlgorithm::MlKem1024 => SecurityLevel::Level5,
+            KemAlgorithm::MlKem768 => SecurityLevel::Level3,
+        }
+    }
+}
+
+/// The primitives a deployment is configured with. Defaults are the
+/// Level 5 pair, ML-DSA-87 and ML-KEM-1024.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct CryptoConfig {
+    pub signature: Algorithm,
+    pub kem: KemAlgorithm,
+}
+
+impl CryptoConfig {
+    /// The weakest configured primitive's level.
+    pub fn security_level(&self) -> SecurityLevel {
+        self.signature.security_level().min(self.kem.security_level())
+    }
+
+    /// Error naming every configured primitive below `min`, so a deployment
+    /// can refuse to start on a downgrade.
+    pub fn require_level(&self, min: SecurityLevel) -> Result<()> {
+        let below: Vec<String> = [
+            (format!("{:?}", self.signature), self.signature.security_level()),
+            (format!("{:?}", self.kem), self.kem.security_level()),
+        ]
+        .into_iter()
+        .filter(|(_, level)| *level < min)
+        .map(|(name, level)| format!("{} is {:?}", name, level))
+        .collect();
+        if below.is_empty() {
+            return Ok(());
+        }
+        Err(PolykitError::Crypto(format!("below required {:?}: {}", min, below.join(", "))))
+    }
+}
+
+/// Check the default primitives against `min` (call at init).
+pub fn require_level(min: SecurityLevel) -> Result<()> {
+    CryptoConfig::default().require_level(min)
+}
+
+/// A signature tagged with its scheme.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub algorithm: Algorithm,
+    pub bytes: Vec<u8>,
+}
+
+impl Signature {
+    /// Tag byte followed by the raw signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(1 + self.bytes.len());
+        out.push(self.algorithm.tag());
+        out.extend_from_slice(&self.bytes);
+        out
+    }
+
+    pub fn from_bytes(encoded: &[u8]) -> Result<Self> {
+        let (&tag, bytes) = encoded
+            .split_first()
+            .ok_or_else(|| PolykitError::Crypto("empty signature".to_string()))?;
+        let algorithm = Algorithm::from_tag(tag)
+            .ok_or_else(|| PolykitError::Crypto(format!("unknown signature scheme {:#04x}", tag)))?;
+        Ok(Signature { algorithm, bytes: bytes.to_vec() })
+    }
+}
+
+/// Sign a message with an ML-DSA-87 secret key. Returns the encoded
+/// (tagged) signature.
+pub fn sign(secret_key: &[u8], message: &[u8]) -> Result<Vec<u8>> {
+    sign_with(Algorithm::default(), secret_key, message).map(|sig| sig.to_bytes())
+}
+
+/// Sign under an explicit scheme.
+pub fn sign_with(algorithm: Algorithm, secret_key: &[u8], message: &[u8]) -> Result<Signature> {
+    if secret_key.len() != algorithm.secret_key_len() {
+        return Err(PolykitError::Crypto(format!(
+            "{:?} secret key must be {} bytes, got {}",
+            algorithm,
+            algorithm.secret_key_len(),
+            secret_key.len()
+        )));
+    }
+    let bytes = match algorithm {
+        Algorithm::MlDsa87 => host::mldsa87_sign(secret_key, message),
+        Algorithm::MlDsa65 => host::mldsa65_sign(secret_key, message),
+    };
+    Ok(Signature { algorithm, bytes })
+}
+
+/// Verify an encoded (tagged) signature. Malformed keys are an error;
+/// a well-formed signature that doesn't verify is `Ok(false)`, as is one
+/// whose tag is missing or unknown.
+pub fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<bool> {
+    match Signature::from_bytes(signature) {
+        Ok(sig) => verify_signature(public_key, message, &sig),
+        Err(_) => Ok(false),
+    }
+}
+
+/// Verify a tagged signature. The public key must be sized for the
+/// signature's scheme; otherwise the schemes don't match and it's an error.
+pub fn verify_signature(public_key: &[u8], message: &[u8], signature: &Signature) -> Result<bool> {
+    let algorithm = signature.algorithm;
+    if public_key.len() != algorithm.public_key_len() {
+        return Err(PolykitError::Crypto(format!(
+            "{:?} public key must be {} bytes, got {}",
+            algorithm,
+            algorithm.public_key_len(),
+            public_key.len()
+        )));
+    }
+    if signature.bytes.len() != algorithm.signature_len() {
+        return Ok(false);
+    }
+    Ok(match algorithm {
+        Algorithm::MlDsa87 => host::mldsa87_verify(public_key, message, &signature.bytes),
+        Algorithm::MlDsa65 => host::mldsa65_verify(public_key, message, &signature.bytes),
+    })
+}
+
+/// How a signature is produced: PQ-only, or hybrid for systems that still
+/// require a classical signature alongside ML-DSA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SignatureScheme {
+    /// ML-DSA-87 alone, encoded as by `sign`
+    #[default]
+    MlDsa87,
+    /// Ed25519 and ML-DSA-87 composite; verifies only if both do. Keys are
+    /// the Ed25519 key followed by the ML-DSA-87 key.
+    Ed25519MlDsa87,
+}
+
+/// Composite signature tag. ML-DSA-only signatures keep `Algorithm`'s tags.
+const HYBRID_SIGNATURE_TAG: u8 = 0xed;
+
+impl SignatureScheme {
+    pub fn public_key_len(&self) -> usize {
+        match self {
+            SignatureScheme::MlDsa87 => MLDSA87_PUBLIC_KEY_LEN,
+            SignatureScheme::Ed25519MlDsa87 => ED25519_PUBLIC_KEY_LEN + MLDSA87_PUBLIC_KEY_LEN,
+        }
+    }
+
+    pub fn secret_key_len(&self) -> usize {
+        match self {
+            SignatureScheme::MlDsa87 => MLDSA87_SECRET_KEY_LEN,
+            SignatureScheme::Ed25519MlDsa87 => ED25519_SECRET_KEY_LEN + MLDSA87_SECRET_KEY_LEN,
+        }
+    }
+
+    /// Encoded signature length, tag included.
+    pub fn signature_len(&self) -> usize {
+        match self {
+            SignatureScheme::MlDsa87 => 1 + MLDSA87_SIGNATURE_LEN,
+            SignatureScheme::Ed25519MlDsa87 => 1 + ED25519_SIGNATURE_LEN + MLDSA87_SIGNATURE_LEN,
+        }
+    }
+
+    /// Level of the PQ component; Ed25519 adds no post-quantum strength.
+    pub fn security_level(&self) -> SecurityLevel {
+        SecurityLevel::Level5
+    }
+}
+
+/// Sign under `scheme`. For the hybrid scheme both components sign a
+/// domain-separated message, so neither can be stripped off and passed
+/// as a standalone signature over `message`.
+pub fn sign_scheme(scheme: SignatureScheme, secret_key: &[u8], message: &[u8]) -> Result<Vec<u8>> {
+    if secret_key.len() != scheme.secret_key_len() {
+        return Err(PolykitError::Crypto(format!(
+            "{:?} secret key must be {} bytes, got {}",
+            scheme,
+            scheme.secret_key_len(),
+            secret_key.len()
+        )));
+    }
+    match scheme {
+        SignatureScheme::MlDsa87 => sign(secret_key, message),
+        SignatureScheme::Ed25519MlDsa87 => {
+            let (ed25519_key, mldsa_key) = secret_key.split_at(ED25519_SECRET_KEY_LEN);
+            let composite = hybrid_message(message);
+            let mut out = Vec::with_capacity(scheme.signature_len());
+            out.push(HYBRID_SIGNATURE_TAG);
+            out.extend_from_slice(&host::ed25519_sign(ed25519_key, &composite));
+            out.extend_from_slice(&sign_with(Algorithm::MlDsa87, mldsa_key, &composite)?.bytes);
+            Ok(out)
+        }
+    }
+}
+
+/// Verify a signature from `sign_scheme`. A hybrid signature is valid only
+/// if both components verify. Malformed keys are an error; a malformed or
+/// non-matching signature is `Ok(false)`.
+pub fn verify_scheme(
+    scheme: SignatureScheme,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<bool> {
+    if public_key.len() != scheme.public_key_len() {
+        return Err(PolykitError::Crypto(format!(
+            "{:?} public key must be {} bytes, got {}",
+            scheme,
+            scheme.public_key_len(),
+            public_key.len()
+        )));
+    }
+    match scheme {
+        SignatureScheme::MlDsa87 => verify(public_key, message, signature),
+        SignatureScheme::Ed25519MlDsa87 => {
+            if signature.len() != scheme.signature_len() || signature[0] != HYBRID_SIGNATURE_TAG {
+                return Ok(false);
+            }
+            let (ed25519_sig, mldsa_sig) = signature[1..].split_at(ED25519_SIGNATURE_LEN);
+            let (ed25519_key, mldsa_key) = public_key.split_at(ED25519_PUBLIC_KEY_LEN);
+            let composite = hybrid_message(message);
+            let mldsa = Signature { algorithm: Algorithm::MlDsa87, bytes: mldsa_sig.to_vec() };
+            // Evaluate both so timing doesn't reveal which component failed
+            let classical = host::ed25519_verify(ed25519_key, &composite, ed25519_sig);
+            let post_quantum = verify_signature(mldsa_key, &composite, &mldsa)?;
+            Ok(classical & post_quantum)
+        }
+    }
+}
+
+fn hybrid_message(message: &[u8]) -> Vec<u8> {
+    let mut out = b"poly-hybrid-sig-v1".to_vec();
+    out.extend_from_slice(&(message.len() as u64).to_be_bytes());
+    out.extend_from_slice(message);
+    out
+}
+
+/// Generate a key pair for `scheme` as `(public_key, secret_key)`, in the
+/// concatenated layout `sign_scheme` and `verify_scheme` expect.
+pub fn scheme_keypair(scheme: SignatureScheme, rng: &mut dyn Rng) -> (Vec<u8>, Secret<Vec<u8>>) {
+    match scheme {
+        SignatureScheme::MlDsa87 => signing_keypair(rng),
+        SignatureScheme::Ed25519MlDsa87 => {
+            let (mut public_key, ed25519_secret) = host::ed25519_keypair(rng);
+            let mut secret_key = Secret::new(ed25519_secret);
+            let (mldsa_public, mldsa_secret) = signing_keypair(rng);
+            public_key.extend_from_slice(&mldsa_public);
+            secret_key.extend_from_slice(&mldsa_secret);
+            (public_key, secret_key)
+        }
+    }
+}
+
+/// Generate an ML-KEM-1024 key pair as `(public_key, secret_key)`, e.g.
+/// an ephemeral key for a single exchange.
+pub fn kem_keypair(rng: &mut dyn Rng) -> (Vec<u8>, Secret<Vec<u8>>) {
+    let (public_key, secret_key) = host::mlkem1024_keypair(rng);
+    (public_key, Secret::new(secret_key))
+}
+
+/// Generate a fresh ML-DSA-87 key pair as `(public_key, secret_key)`.
+pub fn signing_keypair(rng: &mut dyn Rng) -> (Vec<u8>, Secret<Vec<u8>>) {
+    let (public_key, secret_key) = host::mldsa87_keypair(rng);
+    (public_key, Secret::new(secret_key))
+}
+
+/// Recover the shared secret an ML-KEM-1024 ciphertext encapsulates.
+pub fn kem_decapsulate(secret_key: &[u8], kem_ciphertext: &[u8]) -> Result<Secret<[u8; 32]>> {
+    if secret_key.len() != MLKEM1024_SECRET_KEY_LEN {
+        return Err(PolykitError::Crypto(format!(
+            "ML-KEM-1024 secret key must be {} bytes, got {}",
+            MLKEM1024_SECRET_KEY_LEN,
+            secret_key.len()
+        )));
+    }
+    if kem_ciphertext.len() != MLKEM1024_CIPHERTEXT_LEN {
+        return Err(PolykitError::Crypto("malformed ML-KEM-1024 ciphertext".to_string()));
+    }
+    Ok(Secret::new(host::mlkem1024_decapsulate(secret_key, kem_ciphertext)))
+}
+
+/// HKDF info for message keys derived from an ML-KEM shared secret.
+const SEAL_KEY_INFO: &[u8] = b"polykit-seal-v1";
+
+/// A message encrypted to one recipient and signed by its sender:
+/// ML-KEM-1024 encapsulation, AES-256-GCM payload, ML-DSA signature over
+/// everything else. The embedded sender key proves integrity only; callers
+/// decide whether they trust that sender.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedMessage {
+    pub kem_ciphertext: Vec<u8>,
+    pub nonce: [u8; AEAD_NONCE_LEN],
+    pub ciphertext: Vec<u8>,
+    pub sender_public_key: Vec<u8>,
+    /// Encoded (tagged) signature over `signed_bytes(aad)`
+    pub signature: Vec<u8>,
+}
+
+impl SealedMessage {
+    /// Bytes covered by the sender's signature.
+    pub fn signed_bytes(&self, aad: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for field in [&self.kem_ciphertext[..], &self.nonce, &self.ciphertext, aad] {
+            out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            out.extend_from_slice(field);
+        }
+        out
+    }
+}
+
+/// Encrypt `plaintext` to `recipient_public_key` (ML-KEM-1024) and sign it
+/// with the sender's ML-DSA-87 key. `aad` is bound but not transmitted.
+pub fn seal(
+    recipient_public_key: &[u8],
+    sender_secret_key: &[u8],
+    sender_public_key: &[u8],
+    plaintext: &[u8],
+    aad: &[u8],
+    rng: &mut dyn Rng,
+) -> Result<SealedMessage> {
+    if recipient_public_key.len() != MLKEM1024_PUBLIC_KEY_LEN {
+        return Err(PolykitError::Crypto(format!(
+            "ML-KEM-1024 public key must be {} bytes, got {}",
+            MLKEM1024_PUBLIC_KEY_LEN,
+            recipient_public_key.len()
+        )));
+    }
+    let (kem_ciphertext, shared_secret) = host::mlkem1024_encapsulate(recipient_public_key, rng);
+    let key = hkdf_sha3_256(&Secret::new(shared_secret)[..], SEAL_KEY_INFO)?;
+    let mut nonce = [0u8; AEAD_NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+    let ciphertext = aead_seal(&key, &nonce, aad, plaintext)?;
+
+    let mut sealed = SealedMessage {
+        kem_ciphertext,
+        nonce,
+        ciphertext,
+        sender_public_key: sender_public_key.to_vec(),
+        signature: Vec::new(),
+    };
+    sealed.signature = sign(sender_secret_key, &sealed.signed_bytes(aad))?;
+    Ok(sealed)
+}
+
+/// Verify, decapsulate, and decrypt one sealed message. The signature is
+/// checked first, so a forged message costs no decapsulation.
+pub fn open(secret_key: &[u8], sealed: &SealedMessage, aad: &[u8]) -> Result<Vec<u8>> {
+    if secret_key.len() != MLKEM1024_SECRET_KEY_LEN {
+        return Err(PolykitError::Crypto(format!(
+            "ML-KEM-1024 secret key must be {} bytes, got {}",
+            MLKEM1024_SECRET_KEY_LEN,
+            secret_key.len()
+        )));
+    }
+    if sealed.kem_ciphertext.len() != MLKEM1024_CIPHERTEXT_LEN {
+        return Err(PolykitError::Crypto("malformed ML-KEM-1024 ciphertext".to_string()));
+    }
+    if !verify(&sealed.sender_public_key, &sealed.signed_bytes(aad), &sealed.signature)? {
+        return Err(PolykitError::Crypto("sealed message signature invalid".to_string()));
+    }
+    let shared_secret = kem_decapsulate(secret_key, &sealed.kem_ciphertext)?;
+    let key = hkdf_sha3_256(&shared_secret[..], SEAL_KEY_INFO)?;
+    aead_open(&key, &sealed.nonce, aad, &sealed.ciphertext)
+}
+
+/// Open a batch of inbound messages in one call. Message `i` is opened
+/// with `secret_keys[i]` and `aads[i]`; each gets its own outcome, so one
+/// bad message doesn't fail the rest. Messages without a matching key or
+/// AAD are errors.
+pub fn open_batch(
+    secret_keys: &[&[u8]],
+    sealed_messages: &[SealedMessage],
+    aads: &[&[u8]],
+) -> Vec<Result<Vec<u8>>> {
+    sealed_messages
+        .iter()
+        .enumerate()
+        .map(|(i, sealed)| match (secret_keys.get(i), aads.get(i)) {
+            (Some(secret_key), Some(aad)) => open(secret_key, sealed, aad),
+            _ => Err(PolykitError::Crypto(format!("no key or AAD for sealed message {}", i))),
+        })
+        .collect()
+}
+
+/// Whether the host crypto bindings (ML-DSA-87, entropy) are present.
+pub fn backend_available() -> bool {
+    host::backend_available()
+}
+
+/// Source of random bytes. Nothing in PolyKit should reach for std RNG.
+pub trait Rng {
+    fn fill_bytes(&mut self, dest: &mut [u8]);
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        self.fill_bytes(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+}
+
+/// Entropy from the host (production). Panics if the host import isn't
+/// bound: nonces and keys must never fall back to predictable bytes.
+#[derive(Debug, Default)]
+pub struct HostRng;
+
+impl Rng for HostRng {
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        host::random_bytes(dest);
+    }
+}
+
+/// Deterministic ChaCha20 keystream. Same seed, same bytes — for tests
+/// and reproducible demos only, never for key material.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    key: [u32; 8],
+    counter: u64,
+    block: [u8; 64],
+    pos: usize,
+}
+
+impl SeededRng {
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        let mut key = [0u32; 8];
+        for (i, word) in key.iter_mut().enumerate() {
+            *word = u32::from_le_bytes([seed[i * 4], seed[i * 4 + 1], seed[i * 4 + 2], seed[i * 4 + 3]]);
+        }
+        Self { key, counter: 0, block: [0u8; 64], pos: 64 }
+    }
+
+    pub fn seed_from_u64(seed: u64) -> Self {
+        Self::from_seed(sha3_256(&seed.to_le_bytes()))
+    }
+
+    fn refill(&mut self) {
+        self.block = chacha20_block(&self.key, self.counter);
+        self.counter = self.counter.wrapping_add(1);
+        self.pos = 0;
+    }
+}
+
+impl Rng for SeededRng {
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest.iter_mut() {
+            if self.pos == 64 {
+                self.refill();
+            }
+            *byte = self.block[self.pos];
+            self.pos += 1;
+        }
+    }
+}
+
+/// One ChaCha20 block (RFC 8439 quarter-round, 20 rounds) with a 64-bit
+/// counter and zero nonce.
+fn chacha20_block(key: &[u32; 8], counter: u64) -> [u8; 64] {
+    const SIGMA: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+    let mut input = [0u32; 16];
+    input[..4].copy_from_slice(&SIGMA);
+    input[4..12].copy_from_slice(key);
+    input[12] = counter as u32;
+    input[13] = (counter >> 32) as u32;
+
+    let mut x = input;
+    for _ in 0..10 {
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 1, 5, 9, 13);
+        quarter_round(&mut x, 2, 6, 10, 14);
+        quarter_round(&mut x, 3, 7, 11, 15);
+        quarter_round(&mut x, 0, 5, 10, 15);
+        quarter_round(&mut x, 1, 6, 11, 12);
+        quarter_round(&mut x, 2, 7, 8, 13);
+        quarter_round(&mut x, 3, 4, 9, 14);
+    }
+
+    let mut out = [0u8; 64];
+    for i in 0..16 {
+        out[i * 4..i * 4 + 4].copy_from_slice(&x[i].wrapping_add(input[i]).to_le_bytes());
+    }
+    out
+}
+
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(16);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(12);
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(8);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(7);
+}
+
+#[cfg(not(any(test, feature = "test-crypto")))]
+mod host {
+    // In production: host imports estream::mldsa87_sign / estream::mldsa87_verify,
+    // backed by the sign_message / verify_signature circuit exports.
+
+    pub fn mldsa87_sign(_secret_key: &[u8], _message: &[u8]) -> Vec<u8> {
+        vec![0u8; super::MLDSA87_SIGNATURE_LEN] // Stub
+    }
+
+    pub fn mldsa87_verify(_public_key: &[u8], _message: &[u8], _signature: &[u8]) -> bool {
+        false // Stub — fail closed until the host import is bound
+    }
+
+    // In production: host imports estream::mldsa65_sign / estream::mldsa65_verify
+    pub fn mldsa65_sign(_secret_key: &[u8], _message: &[u8]) -> Vec<u8> {
+        vec![0u8; super::MLDSA65_SIGNATURE_LEN] // Stub
+    }
+
+    pub fn mldsa65_verify(_public_key: &[u8], _message: &[u8], _signature: &[u8]) -> bool {
+        false // Stub — fail closed until the host import is bound
+    }
+
+    // In production: host imports estream::mlkem1024_encapsulate / estream::mlkem1024_decapsulate,
+    // backed by the encapsulate_key / decapsulate_key circuit exports.
+    pub fn mlkem1024_encapsulate(
+        _public_key: &[u8],
+        rng: &mut dyn super::Rng,
+    ) -> (Vec<u8>, [u8; 32]) {
+        let mut shared_secret = [0u8; 32];
+        rng.fill_bytes(&mut shared_secret);
+        (vec![0u8; super::MLKEM1024_CIPHERTEXT_LEN], shared_secret) // Stub
+    }
+
+    pub fn mlkem1024_decapsulate(_secret_key: &[u8], _ciphertext: &[u8]) -> [u8; 32] {
+        [0u8; 32] // Stub
+    }
+
+    // In production: host import estream::mlkem1024_keypair
+    pub fn mlkem1024_keypair(rng: &mut dyn super::Rng) -> (Vec<u8>, Vec<u8>) {
+        let mut seed = [0u8; 64];
+        rng.fill_bytes(&mut seed);
+        (
+            vec![0u8; super::MLKEM1024_PUBLIC_KEY_LEN],
+            vec![0u8; super::MLKEM1024_SECRET_KEY_LEN],
+        ) // Stub
+    }
+
+    // In production: host import estream::mldsa87_keypair
+    pub fn mldsa87_keypair(rng: &mut dyn super::Rng) -> (Vec<u8>, Vec<u8>) {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        (
+            vec![0u8; super::MLDSA87_PUBLIC_KEY_LEN],
+            vec![0u8; super::MLDSA87_SECRET_KEY_LEN],
+        ) // Stub
+    }
+
+    // In production: host imports estream::ed25519_sign / estream::ed25519_verify /
+    // estream::ed25519_keypair, for hybrid signatures only.
+    pub fn ed25519_sign(_secret_key: &[u8], _message: &[u8]) -> [u8; super::ED25519_SIGNATURE_LEN] {
+        [0u8; super::ED25519_SIGNATURE_LEN] // Stub
+    }
+
+    pub fn ed25519_verify(_public_key: &[u8], _message: &[u8], _signature: &[u8]) -> bool {
+        false // Stub — fail closed until the host import is bound
+    }
+
+    pub fn ed25519_keypair(rng: &mut dyn super::Rng) -> (Vec<u8>, Vec<u8>) {
+        let mut secret_key = vec![0u8; super::ED25519_SECRET_KEY_LEN];
+        rng.fill_bytes(&mut secret_key);
+        (vec![0u8; super::ED25519_PUBLIC_KEY_LEN], secret_key) // Stub
+    }
+
+    // In production: probes for the estream::mldsa87_* host imports
+    pub fn backend_available() -> bool {
+        false // Stub — nothing above is bound yet
+    }
+
+    // In production: host import estream::random_bytes
+    pub fn random_bytes(_dest: &mut [u8]) {
+        // Stub — never hand out predictable bytes as entropy
+        panic!("estream::random_bytes host import is not bound");
+    }
+}
+
+/// In-process stand-ins for the host imports, for tests (`test-crypto`).
+/// Signatures are a hash of the public key and message, so anyone holding
+/// the public key can forge one: they exercise encodings and failure paths,
+/// not security.
+#[cfg(any(test, feature = "test-crypto"))]
+mod host {
+    use super::{sha3_256, Rng, SeededRng};
+    use std::cell::RefCell;
+
+    /// `len` bytes of SHA3-256 counter-mode output over `label` and `parts`.
+    fn expand(label: &[u8], parts: &[&[u8]], len: usize) -> Vec<u8> {
+        let mut input = label.to_vec();
+        for part in parts {
+            input.extend_from_slice(&(part.len() as u32).to_be_bytes());
+            input.extend_from_slice(part);
+        }
+        let mut out = Vec::with_capacity(len + 32);
+        let mut counter = 0u32;
+        while out.len() < len {
+            let mut block = input.clone();
+            block.extend_from_slice(&counter.to_be_bytes());
+            out.extend_from_slice(&sha3_256(&block));
+            counter += 1;
+        }
+        out.truncate(len);
+        out
+    }
+
+    /// Secret key is a 32-byte seed expanded to `sk_len`; the public key is
+    /// derived from the seed, so signing can recompute it.
+    fn keypair(
+        label: &[u8],
+        rng: &mut dyn Rng,
+        pk_len: usize,
+        sk_len: usize,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        let mut secret_key = seed.to_vec();
+        secret_key.extend(expand(b"fake-sk", &[label, &seed], sk_len - 32));
+        (public_from_secret(label, &secret_key, pk_len), secret_key)
+    }
+
+    fn public_from_secret(label: &[u8], secret_key: &[u8], pk_len: usize) -> Vec<u8> {
+        expand(
+            b"fake-pk",
+            &[label, &secret_key[..secret_key.len().min(32)]],
+            pk_len,
+        )
+    }
+
+    fn sign(
+        label: &[u8],
+        secret_key: &[u8],
+        message: &[u8],
+        pk_len: usize,
+        sig_len: usize,
+    ) -> Vec<u8> {
+        let public_key = public_from_secret(label, secret_key, pk_len);
+        expand(b"fake-sig", &[label, &public_key, message], sig_len)
+    }
+
+    fn verify(label: &[u8], public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        signature == expand(b"fake-sig", &[label, public_key, message], signature.len()).as_slice()
+    }
+
+    pub fn mldsa87_sign(secret_key: &[u8], message: &[u8]) -> Vec<u8> {
+        sign(
+            b"mldsa87",
+            secret_key,
+            message,
+            super::MLDSA87_PUBLIC_KEY_LEN,
+            super::MLDSA87_SIGNATURE_LEN,
+        )
+    }
+
+    pub fn mldsa87_verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        signature.len() == super::MLDSA87_SIGNATURE_LEN
+            && verify(b"mldsa87", public_key, message, signature)
+    }
+
+    pub fn mldsa65_sign(secret_key: &[u8], message: &[u8]) -> Vec<u8> {
+        sign(
+            b"mldsa65",
+            secret_key,
+            message,
+            super::MLDSA65_PUBLIC_KEY_LEN,
+            super::MLDSA65_SIGNATURE_LEN,
+        )
+    }
+
+    pub fn mldsa65_verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        signature.len() == super::MLDSA65_SIGNATURE_LEN
+            && verify(b"mldsa65", public_key, message, signature)
+    }
+
+    pub fn mlkem1024_encapsulate(public_key: &[u8], rng: &mut dyn Rng) -> (Vec<u8>, [u8; 32]) {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        let ciphertext = expand(b"fake-kem-ct", &[&seed], super::MLKEM1024_CIPHERTEXT_LEN);
+        let shared_secret = kem_shared_secret(public_key, &ciphertext);
+        (ciphertext, shared_secret)
+    }
+
+    pub fn mlkem1024_decapsulate(secret_key: &[u8], ciphertext: &[u8]) -> [u8; 32] {
+        let public_key =
+            public_from_secret(b"mlkem1024", secret_key, super::MLKEM1024_PUBLIC_KEY_LEN);
+        kem_shared_secret(&public_key, ciphertext)
+    }
+
+    fn kem_shared_secret(public_key: &[u8], ciphertext: &[u8]) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&expand(b"fake-kem-ss", &[public_key, ciphertext], 32));
+        out
+    }
+
+    pub fn mlkem1024_keypair(rng: &mut dyn Rng) -> (Vec<u8>, Vec<u8>) {
+        keypair(
+            b"mlkem1024",
+            rng,
+            super::MLKEM1024_PUBLIC_KEY_LEN,
+            super::MLKEM1024_SECRET_KEY_LEN,
+        )
+    }
+
+    pub fn mldsa87_keypair(rng: &mut dyn Rng) -> (Vec<u8>, Vec<u8>) {
+        keypair(
+            b"mldsa87",
+            rng,
+            super::MLDSA87_PUBLIC_KEY_LEN,
+            super::MLDSA87_SECRET_KEY_LEN,
+        )
+    }
+
+    pub fn ed25519_sign(secret_key: &[u8], message: &[u8]) -> [u8; super::ED25519_SIGNATURE_LEN] {
+        let mut out = [0u8; super::ED25519_SIGNATURE_LEN];
+        out.copy_from_slice(&sign(
+            b"ed25519",
+            secret_key,
+            message,
+            super::ED25519_PUBLIC_KEY_LEN,
+            super::ED25519_SIGNATURE_LEN,
+        ));
+        out
+    }
+
+    pub fn ed25519_verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        signature.len() == super::ED25519_SIGNATURE_LEN
+            && verify(b"ed25519", public_key, message, signature)
+    }
+
+    pub fn ed25519_keypair(rng: &mut dyn Rng) -> (Vec<u8>, Vec<u8>) {
+        keypair(
+            b"ed25519",
+            rng,
+            super::ED25519_PUBLIC_KEY_LEN,
+            super::ED25519_SECRET_KEY_LEN,
+        )
+    }
+
+    pub fn backend_available() -> bool {
+        true
+    }
+
+    thread_local! {
+        static RNG: RefCell<SeededRng> = RefCell::new(SeededRng::seed_from_u64(0x7e57));
+    }
+
+    pub fn random_bytes(dest: &mut [u8]) {
+        RNG.with(|rng| rng.borrow_mut().fill_bytes(dest));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(rng: &mut dyn Rng, len: usize) -> Vec<u8> {
+        let mut out = vec![0u8; len];
+        rng.fill_bytes(&mut out);
+        out
+    }
+
+    #[test]
+    fn seeded_rng_is_reproducible() {
+        let a = bytes(&mut SeededRng::seed_from_u64(1), 200);
+        let b = bytes(&mut SeededRng::seed_from_u64(1), 200);
+        assert_eq!(a, b);
+        assert_eq!(SeededRng::seed_from_u64(9).next_u64(), SeededRng::seed_from_u64(9).next_u64());
+    }
+
+    #[test]
+    fn distinct_seeds_diverge() {
+        let a = bytes(&mut SeededRng::seed_from_u64(1), 64);
+        let b = bytes(&mut SeededRng::seed_from_u64(2), 64);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn output_does_not_depend_on_read_sizes() {
+        let whole = bytes(&mut SeededRng::seed_from_u64(3), 150);
+        let mut rng = SeededRng::seed_from_u64(3);
+        let mut pieces = bytes(&mut rng, 30);
+        pieces.extend(bytes(&mut rng, 70));
+        pieces.extend(bytes(&mut rng, 50));
+        assert_eq!(whole, pieces);
+    }
+
+    #[test]
+    fn zero_key_matches_chacha20_keystream() {
+        // ChaCha20 keystream for an all-zero key, nonce, and counter
+        let expected = [
+            0x76, 0xb8, 0xe0, 0xad, 0xa0, 0xf1, 0x3d, 0x90, 0x40, 0x5d, 0x6a, 0xe5, 0x53, 0x86,
+            0xbd, 0x28, 0xbd, 0xd2, 0x19, 0xb8, 0xa0, 0x8d, 0xed, 0x1a, 0xa8, 0x36, 0xef, 0xcc,
+            0x8b, 0x77, 0x0d, 0xc7,
+        ];
+        assert_eq!(bytes(&mut SeededRng::from_seed([0u8; 32]), 32), expected);
+    }
+
+    #[test]
+    fn default_primitives_meet_level5() {
+        assert_eq!(CryptoConfig::default().security_level(), SecurityLevel::Level5);
+        assert!(require_level(SecurityLevel::Level5).is_ok());
+    }
+
+    #[test]
+    fn lower_level_config_is_rejected() {
+        let config = CryptoConfig { signature: Algorithm::MlDsa65, kem: KemAlgorithm::MlKem1024 };
+        assert_eq!(config.security_level(), SecurityLevel::Level3);
+        assert!(config.require_level(SecurityLevel::Level3).is_ok());
+        let err = config.require_level(SecurityLevel::Level5).unwrap_err().to_string();
+        assert!(err.contains("MlDsa65 is Level3"));
+        assert!(!err.contains("MlKem1024"));
+    }
+
+    #[test]
+    fn every_downgraded_primitive_is_named() {
+        let config = CryptoConfig { signature: Algorithm::MlDsa65, kem: KemAlgorithm::MlKem768 };
+        let err = config.require_level(SecurityLevel::Level5).unwrap_err().to_string();
+        assert!(err.contains("MlDsa65 is Level3") && err.contains("MlKem768 is Level3"));
+    }
+
+    fn sealed_to(recipient: &[u8], plaintext: &[u8], aad: &[u8], seed: u64) -> SealedMessage {
+        let mut rng = SeededRng::seed_from_u64(seed);
+        let (sender_pk, sender_sk) = signing_keypair(&mut rng);
+        seal(recipient, &sender_sk, &sender_pk, plaintext, aad, &mut rng).unwrap()
+    }
+
+    #[test]
+    fn sealed_message_opens_with_the_recipient_key() {
+        let (public_key, secret_key) = kem_keypair(&mut SeededRng::seed_from_u64(20));
+        let sealed = sealed_to(&public_key, b"hello", b"chat-1", 21);
+        assert_eq!(open(&secret_key, &sealed, b"chat-1").unwrap(), b"hello");
+        assert!(open(&secret_key, &sealed, b"chat-2").is_err());
+    }
+
+    #[test]
+    fn open_batch_reports_each_message_separately() {
+        let (alice_pk, alice_sk) = kem_keypair(&mut SeededRng::seed_from_u64(20));
+        let (_, mallory_sk) = kem_keypair(&mut SeededRng::seed_from_u64(22));
+        let good = sealed_to(&alice_pk, b"first", b"aad", 30);
+        let mut corrupted = sealed_to(&alice_pk, b"second", b"aad", 31);
+        corrupted.ciphertext[0] ^= 1;
+        let wrong_key = sealed_to(&alice_pk, b"third", b"aad", 32);
+        let also_good = sealed_to(&alice_pk, b"fourth", b"aad", 33);
+
+        let outcomes = open_batch(
+            &[&alice_sk, &alice_sk, &mallory_sk, &alice_sk],
+            &[good, corrupted, wrong_key, also_good],
+            &[b"aad", b"aad", b"aad", b"aad"],
+        );
+        assert_eq!(outcomes.len(), 4);
+        assert_eq!(outcomes[0].as_ref().unwrap(), b"first");
+        assert!(outcomes[1].is_err());
+        assert!(outcomes[2].is_err());
+        assert_eq!(outcomes[3].as_ref().unwrap(), b"fourth");
+    }
+
+    #[test]
+    fn open_batch_without_a_key_for_a_message_is_an_error() {
+        let (public_key, secret_key) = kem_keypair(&mut SeededRng::seed_from_u64(20));
+        let sealed = sealed_to(&public_key, b"only", b"", 40);
+        let outcomes = open_batch(&[&secret_key], &[sealed.clone(), sealed], &[b"", b""]);
+        assert!(outcomes[0].is_ok());
+        assert!(outcomes[1].is_err());
+    }
+
+    #[test]
+    fn hybrid_signature_verifies_only_with_both_components() {
+        let scheme = SignatureScheme::Ed25519MlDsa87;
+        let (public_key, secret_key) = scheme_keypair(scheme, &mut SeededRng::seed_from_u64(1));
+        assert_eq!(public_key.len(), scheme.public_key_len());
+        assert_eq!(secret_key.len(), scheme.secret_key_len());
+
+        let signature = sign_scheme(scheme, &secret_key, b"partner payload").unwrap();
+        assert_eq!(signature.len(), scheme.signature_len());
+        assert!(verify_scheme(scheme, &public_key, b"partner payload", &signature).unwrap());
+        assert!(!verify_scheme(scheme, &public_key, b"other payload", &signature).unwrap());
+
+        // Corrupt the Ed25519 half, then the ML-DSA half
+        for index in [1, scheme.signature_len() - 1] {
+            let mut tampered = signature.clone();
+            tampered[index] ^= 1;
+            assert!(!verify_scheme(scheme, &public_key, b"partner payload", &tampered).unwrap());
+        }
+        assert!(!verify_scheme(scheme, &public_key, b"partner payload", &signature[1..]).unwrap());
+    }
+
+    #[test]
+    fn hybrid_components_cannot_be_stripped() {
+        let scheme = SignatureScheme::Ed25519MlDsa87;
+        let (public_key, secret_key) = scheme_keypair(scheme, &mut SeededRng::seed_from_u64(2));
+        let signature = sign_scheme(scheme, &secret_key, b"partner payload").unwrap();
+        let mldsa = Signature {
+            algorithm: Algorithm::MlDsa87,
+            bytes: signature[1 + ED25519_SIGNATURE_LEN..].to_vec(),
+        };
+        let mldsa_key = &public_key[ED25519_PUBLIC_KEY_LEN..];
+        assert!(!verify(mldsa_key, b"partner payload", &mldsa.to_bytes()).unwrap());
+        assert!(!verify_scheme(
+            SignatureScheme::MlDsa87,
+            mldsa_key,
+            b"partner payload",
+            &mldsa.to_bytes()
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn ml_dsa_scheme_matches_plain_signatures() {
+        let scheme = SignatureScheme::MlDsa87;
+        assert_eq!(SignatureScheme::default(), scheme);
+        let (public_key, secret_key) = scheme_keypair(scheme, &mut SeededRng::seed_from_u64(3));
+        let signature = sign_scheme(scheme, &secret_key, b"message").unwrap();
+        assert_eq!(signature, sign(&secret_key, b"message").unwrap());
+        assert!(verify_scheme(scheme, &public_key, b"message", &signature).unwrap());
+    }
+
+    #[test]
+    fn scheme_key_lengths_are_enforced() {
+        let hybrid = SignatureScheme::Ed25519MlDsa87;
+        let (public_key, secret_key) = signing_keypair(&mut SeededRng::seed_from_u64(4));
+        assert!(sign_scheme(hybrid, &secret_key, b"message").is_err());
+        let signature = sign(&secret_key, b"message").unwrap();
+        assert!(verify_scheme(hybrid, &public_key, b"message", &signature).is_err());
+        assert_eq!(hybrid.security_level(), SecurityLevel::Level5);
+    }
+}
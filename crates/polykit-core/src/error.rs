@@ -1,3 +1,5 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,6 +12,99 @@ pub enum PolykitError {
     Storage(String),
     Sanitization(String),
     Unauthorized { required_role: String, actual_roles: Vec<String> },
+    /// Two apps in one instance would share key material or topics:
+    /// `field` is `hkdf_context` or `lex_namespace`
+    ContextCollision { field: String, app_id: String, existing_app_id: String, value: String },
+    /// An error annotated with call-site context (outermost first when displayed)
+    Context { context: String, source: Box<PolykitError> },
+}
+
+impl PolykitError {
+    /// Wrap this error with call-site context.
+    pub fn context(self, context: impl Into<String>) -> Self {
+        PolykitError::Context { context: context.into(), source: Box::new(self) }
+    }
+
+    /// The underlying error, with all context layers removed.
+    pub fn root(&self) -> &PolykitError {
+        match self {
+            PolykitError::Context { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// Context messages, outermost first.
+    pub fn context_chain(&self) -> Vec<&str> {
+        let mut chain = Vec::new();
+        let mut current = self;
+        while let PolykitError::Context { context, source } = current {
+            chain.push(context.as_str());
+            current = source;
+        }
+        chain
+    }
+
+    /// Non-zero status code for the FLIR ABI `evaluate` export, keyed on
+    /// the root error so context layers don't change the code.
+    pub fn status_code(&self) -> i32 {
+        match self.root() {
+            PolykitError::IdentityDerivation(_) => 1,
+            PolykitError::Crypto(_) => 2,
+            PolykitError::MeteringLimit { .. } => 3,
+            PolykitError::ClassificationViolation(_) => 4,
+            PolykitError::Wire(_) => 5,
+            PolykitError::Storage(_) => 6,
+            PolykitError::Sanitization(_) => 7,
+            PolykitError::Unauthorized { .. } => 8,
+            PolykitError::ContextCollision { .. } => 9,
+            PolykitError::Context { .. } => unreachable!("root() strips context"),
+        }
+    }
+}
+
+impl fmt::Display for PolykitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolykitError::IdentityDerivation(msg) => write!(f, "identity derivation: {}", msg),
+            PolykitError::Crypto(msg) => write!(f, "crypto: {}", msg),
+            PolykitError::MeteringLimit { dimension, current, limit } => {
+                write!(f, "metering limit: {:?} at {} exceeds {}", dimension, current, limit)
+            }
+            PolykitError::ClassificationViolation(msg) => write!(f, "classification: {}", msg),
+            PolykitError::Wire(msg) => write!(f, "wire: {}", msg),
+            PolykitError::Storage(msg) => write!(f, "storage: {}", msg),
+            PolykitError::Sanitization(msg) => write!(f, "sanitization: {}", msg),
+            PolykitError::Unauthorized { required_role, actual_roles } => {
+                write!(f, "unauthorized: requires {}, has {:?}", required_role, actual_roles)
+            }
+            PolykitError::ContextCollision { field, app_id, existing_app_id, value } => write!(
+                f,
+                "context collision: {} {:?} of app {} is already used by app {}",
+                field, value, app_id, existing_app_id
+            ),
+            PolykitError::Context { context, source } => write!(f, "{}: {}", context, source),
+        }
+    }
+}
+
+impl std::error::Error for PolykitError {}
+
+/// `.context()` on results, like anyhow's, for PolykitError.
+pub trait ResultExt<T> {
+    fn context(self, context: impl Into<String>) -> Result<T>;
+
+    /// Lazily built context, for messages that format values.
+    fn with_context<F: FnOnce() -> String>(self, f: F) -> Result<T>;
+}
+
+impl<T> ResultExt<T> for Result<T> {
+    fn context(self, context: impl Into<String>) -> Result<T> {
+        self.map_err(|e| e.context(context))
+    }
+
+    fn with_context<F: FnOnce() -> String>(self, f: F) -> Result<T> {
+        self.map_err(|e| e.context(f()))
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -22,6 +117,47 @@ pub enum MeteringDimension {
     Proofs,
     Circuits,
     MpcSessions,
+    MpcSessionTime,
 }
 
 pub type Result<T> = core::result::Result<T, PolykitError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::{create_app_context, pseudonym::derive_pseudonym_seed};
+
+    #[test]
+    fn context_wraps_identity_derivation_error() {
+        let ctx = create_app_context("poly-mail", "poly-mail-v1", "polylabs.mail");
+        let err = derive_pseudonym_seed(&[7; 32], &ctx, "")
+            .context("deriving pseudonym")
+            .with_context(|| format!("app {}", ctx.app_id))
+            .map(|_| ())
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "app poly-mail: deriving pseudonym: identity derivation: pseudonym recipient is empty"
+        );
+        assert_eq!(err.context_chain(), ["app poly-mail", "deriving pseudonym"]);
+        assert!(matches!(err.root(), PolykitError::IdentityDerivation(_)));
+    }
+
+    #[test]
+    fn ok_results_pass_through_untouched() {
+        let ok: Result<u8> = Ok(3);
+        assert_eq!(ok.context("unused").unwrap(), 3);
+        let plain = PolykitError::Wire("closed".to_string());
+        assert!(plain.context_chain().is_empty());
+        assert_eq!(plain.to_string(), "wire: closed");
+    }
+
+    #[test]
+    fn status_code_ignores_context_layers() {
+        let root = PolykitError::Storage("disk full".to_string());
+        let wrapped = root.clone().context("saving draft").context("app poly-docs");
+        assert_eq!(root.status_code(), 6);
+        assert_eq!(wrapped.status_code(), root.status_code());
+    }
+}
@@ -1,10 +1,33 @@
 //! SPARK identity runtime helpers
 //!
 //! Key derivation and crypto operations are now in polykit_identity.fl.
-//! This module provides only the AppContext struct and topic formatting
-//! helpers used by the WASM shim and React hooks.
+//! This module provides the AppContext struct and topic formatting
+//! helpers used by the WASM shim and React hooks, seed provenance checks,
+//! rotation-chain checks, signed identity attestations, challenge-response
+//! proofs for out-of-band logins, and the device-linking exchange that
+//! runs one identity on several devices.
+//! Master seed recovery lives in [`recovery`], scoped guest identities
+//! in [`delegation`], shared group keys in [`group`], per-recipient
+//! identities in [`pseudonym`], contact verification in
+//! [`safety_number`], the seed's word encoding in [`mnemonic`], and
+//! encrypted installation backups in [`backup`].
+
+pub mod backup;
+pub mod delegation;
+pub mod group;
+pub mod mnemonic;
+pub mod pseudonym;
+pub mod recovery;
+pub mod safety_number;
+
+use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
+use crate::clock::Clock;
+use crate::crypto::{self, Rng, SealedMessage};
+use crate::error::{PolykitError, Result};
+use crate::secret::Secret;
+use crate::wire::WireSession;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppContext {
@@ -21,6 +44,29 @@ pub fn create_app_context(app_id: &str, hkdf_context: &str, lex_namespace: &str)
     }
 }
 
+impl AppContext {
+    /// Split a versioned HKDF context (`poly-data-v2`) into its base and
+    /// version. `None` for unversioned contexts.
+    pub fn context_version(&self) -> Option<(&str, u32)> {
+        let (base, version) = self.hkdf_context.rsplit_once("-v")?;
+        if base.is_empty() || version.is_empty() || !version.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some((base, version.parse().ok()?))
+    }
+
+    /// The same context at another version (`poly-data-v1` → `poly-data-v2`).
+    pub fn with_context_version(&self, version: u32) -> Result<AppContext> {
+        let (base, _) = self.context_version().ok_or_else(|| {
+            PolykitError::IdentityDerivation(format!(
+                "hkdf context {:?} is not versioned (expected <name>-v<N>)",
+                self.hkdf_context
+            ))
+        })?;
+        Ok(AppContext { hkdf_context: format!("{}-v{}", base, version), ..self.clone() })
+    }
+}
+
 pub fn format_user_topic(ctx: &AppContext, user_id: &[u8; 16], suffix: &str) -> String {
     let user_hex: String = user_id.iter().map(|b| format!("{:02x}", b)).collect();
     format!("{}.{}.{}", ctx.lex_namespace, user_hex, suffix)
@@ -29,3 +75,1600 @@ pub fn format_user_topic(ctx: &AppContext, user_id: &[u8; 16], suffix: &str) ->
 pub fn format_global_topic(ctx: &AppContext, suffix: &str) -> String {
     format!("lex://estream/apps/{}/{}", ctx.lex_namespace, suffix)
 }
+
+// --- Context registry ---
+
+/// Every AppContext in use in one WASM instance. Two apps sharing an
+/// `hkdf_context` would derive identical keys from the same seed, and
+/// nested lex namespaces would let one app's topics fall under the
+/// other's, so both are rejected when the second app registers.
+#[derive(Debug, Clone, Default)]
+pub struct AppContextRegistry {
+    contexts: BTreeMap<String, AppContext>,
+}
+
+impl AppContextRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `ctx`, replacing any earlier context for the same app (e.g.,
+    /// on re-init). Fails with `PolykitError::ContextCollision` if another
+    /// app already uses its HKDF context or an overlapping namespace.
+    pub fn register(&mut self, ctx: &AppContext) -> Result<()> {
+        for existing in self.contexts.values().filter(|c| c.app_id != ctx.app_id) {
+            let field = if existing.hkdf_context == ctx.hkdf_context {
+                ("hkdf_context", &ctx.hkdf_context)
+            } else if namespaces_overlap(&existing.lex_namespace, &ctx.lex_namespace) {
+                ("lex_namespace", &ctx.lex_namespace)
+            } else {
+                continue;
+            };
+            return Err(PolykitError::ContextCollision {
+                field: field.0.to_string(),
+                app_id: ctx.app_id.clone(),
+                existing_app_id: existing.app_id.clone(),
+                value: field.1.clone(),
+            });
+        }
+        self.contexts.insert(ctx.app_id.clone(), ctx.clone());
+        Ok(())
+    }
+
+    /// Create and register a context in one step.
+    pub fn create(
+        &mut self,
+        app_id: &str,
+        hkdf_context: &str,
+        lex_namespace: &str,
+    ) -> Result<AppContext> {
+        let ctx = create_app_context(app_id, hkdf_context, lex_namespace);
+        self.register(&ctx)?;
+        Ok(ctx)
+    }
+
+    pub fn get(&self, app_id: &str) -> Option<&AppContext> {
+        self.contexts.get(app_id)
+    }
+
+    /// Registered contexts, ordered by app_id.
+    pub fn contexts(&self) -> impl Iterator<Item = &AppContext> {
+        self.contexts.values()
+    }
+
+    pub fn remove(&mut self, app_id: &str) -> Option<AppContext> {
+        self.contexts.remove(app_id)
+    }
+}
+
+/// Equal, or one nested in the other at a `.` or `/` boundary.
+fn namespaces_overlap(a: &str, b: &str) -> bool {
+    let nested = |outer: &str, inner: &str| {
+        inner
+            .strip_prefix(outer)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '/']))
+    };
+    nested(a, b) || nested(b, a)
+}
+
+// --- Per-topic keys ---
+//
+// master seed ─HKDF(hkdf_context)→ app key ─HKDF(namespace, user_id, topic)→ topic key
+//
+// Each stream gets its own symmetric key, so a leaked topic key exposes
+// one stream only, and the app key can be cached without the master seed.
+
+/// App-level key: HKDF-SHA3-256 of the master seed under the app's context.
+pub fn derive_app_key(master_seed: &[u8; 32], ctx: &AppContext) -> Result<Secret<[u8; 32]>> {
+    let mut info = b"poly-app-key-v1".to_vec();
+    info.extend_from_slice(ctx.hkdf_context.as_bytes());
+    crypto::hkdf_sha3_256(master_seed, &info)
+}
+
+/// Symmetric key for one lex topic (e.g., from `format_user_topic`).
+pub fn derive_topic_key(
+    app_key: &[u8; 32],
+    ctx: &AppContext,
+    user_id: &[u8; 16],
+    topic: &str,
+) -> Result<Secret<[u8; 32]>> {
+    let mut info = b"poly-topic-key-v1".to_vec();
+    for field in [ctx.lex_namespace.as_bytes(), user_id, topic.as_bytes()] {
+        info.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        info.extend_from_slice(field);
+    }
+    crypto::hkdf_sha3_256(app_key, &info)
+}
+
+/// Keys from the derive_keys circuit (`DerivedKeys` in polykit_identity.fl).
+/// Not Serialize: it holds both secret keys, which are zeroized on drop.
+#[derive(Debug, Clone)]
+pub struct DerivedIdentity {
+    pub user_id: [u8; 16],
+    pub signing_public_key: Vec<u8>,
+    pub signing_secret_key: Secret<Vec<u8>>,
+    pub encryption_public_key: Vec<u8>,
+    pub encryption_secret_key: Secret<Vec<u8>>,
+}
+
+impl DerivedIdentity {
+    /// Wipe both secret keys now instead of whenever the value is dropped.
+    pub fn destroy(mut self) {
+        self.signing_secret_key.zeroize();
+        self.encryption_secret_key.zeroize();
+    }
+}
+
+/// Derive the identity for `ctx` via the derive_keys circuit.
+pub fn derive_identity(master_seed: &[u8; 32], ctx: &AppContext) -> Result<DerivedIdentity> {
+    host::derive_keys(master_seed, ctx.hkdf_context.as_bytes())
+}
+
+// --- Seed attestation ---
+//
+// Blob layout (produced by the native keystore bridge):
+//   version u8 | platform u8 | len u32 | seed commitment (32) | len u32 | evidence
+// The evidence is the platform's own attestation (e.g., an Android key
+// attestation certificate chain) over the seed commitment.
+
+/// Seed attestation blob format version.
+pub const SEED_ATTESTATION_VERSION: u8 = 1;
+
+/// Where an attested seed is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttestationPlatform {
+    /// No hardware evidence; the blob only commits to the seed
+    Software,
+    AndroidTee,
+    AndroidStrongBox,
+    AppleSecureEnclave,
+}
+
+impl AttestationPlatform {
+    pub fn tag(&self) -> u8 {
+        match self {
+            AttestationPlatform::Software => 0,
+            AttestationPlatform::AndroidTee => 1,
+            AttestationPlatform::AndroidStrongBox => 2,
+            AttestationPlatform::AppleSecureEnclave => 3,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(AttestationPlatform::Software),
+            1 => Some(AttestationPlatform::AndroidTee),
+            2 => Some(AttestationPlatform::AndroidStrongBox),
+            3 => Some(AttestationPlatform::AppleSecureEnclave),
+            _ => None,
+        }
+    }
+
+    pub fn is_hardware_backed(&self) -> bool {
+        *self != AttestationPlatform::Software
+    }
+}
+
+/// Provenance of a master seed, from a verified attestation blob.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedAttestation {
+    pub platform: AttestationPlatform,
+    /// `seed_commitment` of the attested seed
+    pub seed_commitment: [u8; 32],
+}
+
+impl SeedAttestation {
+    pub fn is_hardware_backed(&self) -> bool {
+        self.platform.is_hardware_backed()
+    }
+}
+
+/// Commitment the platform attests to; the seed itself never leaves the
+/// keystore.
+pub fn seed_commitment(master_seed: &[u8; 32]) -> [u8; 32] {
+    let mut input = b"poly-seed-commitment-v1".to_vec();
+    input.extend_from_slice(master_seed);
+    crypto::sha3_256(&input)
+}
+
+/// Parse a seed attestation blob and verify its platform evidence.
+/// Software blobs carry no evidence and verify as software provenance.
+pub fn verify_seed_attestation(attestation_blob: &[u8]) -> Result<SeedAttestation> {
+    let invalid = |message: &str| {
+        PolykitError::IdentityDerivation(format!("seed attestation: {}", message))
+    };
+    let (&version, rest) = attestation_blob.split_first().ok_or_else(|| invalid("empty blob"))?;
+    if version != SEED_ATTESTATION_VERSION {
+        return Err(invalid(&format!("unsupported version {}", version)));
+    }
+    let (&tag, mut rest) = rest.split_first().ok_or_else(|| invalid("missing platform"))?;
+    let platform = AttestationPlatform::from_tag(tag)
+        .ok_or_else(|| invalid(&format!("unknown platform tag {}", tag)))?;
+
+    let mut fields = Vec::with_capacity(2);
+    for _ in 0..2 {
+        let (len, tail) = rest.split_first_chunk::<4>().ok_or_else(|| invalid("truncated"))?;
+        let len = u32::from_be_bytes(*len) as usize;
+        if tail.len() < len {
+            return Err(invalid("truncated"));
+        }
+        let (field, tail) = tail.split_at(len);
+        fields.push(field);
+        rest = tail;
+    }
+    if !rest.is_empty() {
+        return Err(invalid("trailing bytes"));
+    }
+    let seed_commitment: [u8; 32] =
+        fields[0].try_into().map_err(|_| invalid("seed commitment must be 32 bytes"))?;
+    let evidence = fields[1];
+
+    match platform {
+        AttestationPlatform::Software if !evidence.is_empty() => {
+            return Err(invalid("software attestation carries evidence"));
+        }
+        AttestationPlatform::Software => {}
+        _ => {
+            if !host::verify_platform_attestation(platform.tag(), evidence, &seed_commitment) {
+                return Err(PolykitError::Crypto(format!(
+                    "{:?} attestation evidence rejected",
+                    platform
+                )));
+            }
+        }
+    }
+    Ok(SeedAttestation { platform, seed_commitment })
+}
+
+/// `derive_identity` gated on the seed's attestation: the blob must verify
+/// and commit to `master_seed`, and with `require_hardware` a software
+/// seed is refused. Returns the identity with the seed's provenance.
+pub fn derive_identity_attested(
+    master_seed: &[u8; 32],
+    ctx: &AppContext,
+    attestation_blob: &[u8],
+    require_hardware: bool,
+) -> Result<(DerivedIdentity, SeedAttestation)> {
+    let attestation = verify_seed_attestation(attestation_blob)?;
+    if attestation.seed_commitment != seed_commitment(master_seed) {
+        return Err(PolykitError::IdentityDerivation(
+            "seed attestation is for a different seed".to_string(),
+        ));
+    }
+    if require_hardware && !attestation.is_hardware_backed() {
+        return Err(PolykitError::IdentityDerivation(
+            "seed is not hardware-backed".to_string(),
+        ));
+    }
+    Ok((derive_identity(master_seed, ctx)?, attestation))
+}
+
+// --- Derivation paths ---
+
+/// Structured derivation path, e.g. `m/poly-data/upload-signing/0`.
+/// Each segment feeds one HKDF step, so every node is hardened: a child
+/// seed reveals nothing about its parent or siblings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DerivationPath {
+    segments: Vec<String>,
+}
+
+impl DerivationPath {
+    /// The root `m` (the master seed itself).
+    pub fn root() -> Self {
+        Self { segments: Vec::new() }
+    }
+
+    /// Parse `m/segment/...`; segments are `[a-z0-9_-]+`.
+    pub fn parse(path: &str) -> Result<Self> {
+        let mut parts = path.split('/');
+        if parts.next() != Some("m") {
+            return Err(PolykitError::IdentityDerivation(format!(
+                "derivation path {:?} must start with m",
+                path
+            )));
+        }
+        parts.try_fold(Self::root(), |parent, segment| parent.child(segment))
+    }
+
+    /// Extend the path by one segment.
+    pub fn child(mut self, segment: &str) -> Result<Self> {
+        let valid = !segment.is_empty()
+            && segment
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+        if !valid {
+            return Err(PolykitError::IdentityDerivation(format!(
+                "invalid derivation path segment {:?}",
+                segment
+            )));
+        }
+        self.segments.push(segment.to_string());
+        Ok(self)
+    }
+
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+}
+
+impl std::fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "m")?;
+        for segment in &self.segments {
+            write!(f, "/{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+/// Seed at `path`: one HKDF-SHA3-256 step per segment from the master seed.
+pub fn derive_path_seed(
+    master_seed: &[u8; 32],
+    path: &DerivationPath,
+) -> Result<Secret<[u8; 32]>> {
+    path.segments.iter().try_fold(Secret::new(*master_seed), |seed, segment| {
+        let mut info = b"poly-path-v1".to_vec();
+        info.extend_from_slice(segment.as_bytes());
+        crypto::hkdf_sha3_256(&seed[..], &info)
+    })
+}
+
+/// Purpose-specific key pairs for `ctx` at `path` (e.g., one per upload
+/// signer or backup key). The root path gives `derive_identity`'s keys.
+pub fn derive_identity_at(
+    master_seed: &[u8; 32],
+    ctx: &AppContext,
+    path: &DerivationPath,
+) -> Result<DerivedIdentity> {
+    let seed = derive_path_seed(master_seed, path)?;
+    derive_identity(&seed, ctx)
+}
+
+// --- Context migration ---
+
+/// A move from one HKDF context version to a later one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextMigration {
+    pub from: AppContext,
+    pub to: AppContext,
+}
+
+impl ContextMigration {
+    /// Migrate `ctx` (which must be versioned) to `to_version`.
+    pub fn new(ctx: &AppContext, to_version: u32) -> Result<Self> {
+        let from_version = ctx.context_version().map(|(_, v)| v);
+        if from_version.is_some_and(|v| v >= to_version) {
+            return Err(PolykitError::IdentityDerivation(format!(
+                "cannot migrate {} to v{}: not a newer version",
+                ctx.hkdf_context, to_version
+            )));
+        }
+        Ok(Self { from: ctx.clone(), to: ctx.with_context_version(to_version)? })
+    }
+}
+
+/// Data to move from the old identity's topics to the new one's. Topic
+/// keys are re-derived from each identity; no key material is stored here.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReencryptionPlan {
+    pub from_context: String,
+    pub to_context: String,
+    pub from_user_id: [u8; 16],
+    pub to_user_id: [u8; 16],
+    pub steps: Vec<ReencryptionStep>,
+}
+
+/// Decrypt everything on `from_topic` and re-encrypt it onto `to_topic`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReencryptionStep {
+    pub from_topic: String,
+    pub to_topic: String,
+}
+
+/// Both identities during a migration window, and the plan to move data.
+#[derive(Clone)]
+pub struct VersionedIdentities {
+    pub previous: DerivedIdentity,
+    pub current: DerivedIdentity,
+    pub plan: ReencryptionPlan,
+}
+
+/// Derive the old and new identities for a migration, so apps can read
+/// with the old one while writing with the new. The plan has one step per
+/// user topic suffix (e.g., `"files"`, `"settings"`).
+pub fn derive_identity_versioned(
+    master_seed: &[u8; 32],
+    migration: &ContextMigration,
+    topic_suffixes: &[&str],
+) -> Result<VersionedIdentities> {
+    let previous = derive_identity(master_seed, &migration.from)?;
+    let current = derive_identity(master_seed, &migration.to)?;
+    let steps = topic_suffixes
+        .iter()
+        .map(|suffix| ReencryptionStep {
+            from_topic: format_user_topic(&migration.from, &previous.user_id, suffix),
+            to_topic: format_user_topic(&migration.to, &current.user_id, suffix),
+        })
+        .collect();
+    let plan = ReencryptionPlan {
+        from_context: migration.from.hkdf_context.clone(),
+        to_context: migration.to.hkdf_context.clone(),
+        from_user_id: previous.user_id,
+        to_user_id: current.user_id,
+        steps,
+    };
+    Ok(VersionedIdentities { previous, current, plan })
+}
+
+// --- Key rotation ---
+//
+// Rotation itself (`derive_keys_epoch`, `rotate_identity`,
+// `prove_continuity`) runs only in polykit_identity.fl. The records it
+// produces are checked here because the attestation, delegation, and
+// session-endorsement verifiers below are still hand-written and must
+// accept a rotated key for its original user_id. `signed_bytes` is pinned
+// to `continuity_payload` by a shared golden digest in both test suites.
+
+/// One key rotation (`KeyContinuityProof` in polykit_identity.fl): the
+/// outgoing key's signature over the incoming key and its epoch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotationRecord {
+    pub epoch: u32,
+    pub old_public_key: Vec<u8>,
+    pub new_public_key: Vec<u8>,
+    /// Old key's ML-DSA-87 signature over the continuity payload
+    pub signature: Vec<u8>,
+}
+
+impl RotationRecord {
+    /// `continuity_payload` in polykit_identity.fl.
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let mut out = b"poly-key-continuity-v1:".to_vec();
+        out.extend_from_slice(&self.new_public_key);
+        out.extend_from_slice(&self.epoch.to_be_bytes());
+        out
+    }
+}
+
+/// user_id derived from a signing key: SHA3-256, truncated to 16 bytes
+/// (`derive_user_id` in polykit_identity.fl).
+pub fn user_id_for_key(signing_public_key: &[u8]) -> [u8; 16] {
+    crypto::sha3_256(signing_public_key)[..16].try_into().expect("sliced to 16 bytes")
+}
+
+/// Check `signing_public_key` speaks for `user_id`. With no rotations the
+/// user_id must be derived from the key itself. Otherwise it must be
+/// derived from the first record's old key, each record must be signed by
+/// the key it replaces and follow on from the previous one at a higher
+/// epoch, and the last record's new key must be `signing_public_key`.
+pub fn verify_key_chain(
+    user_id: &[u8; 16],
+    signing_public_key: &[u8],
+    rotations: &[RotationRecord],
+) -> Result<()> {
+    let genesis = rotations.first().map_or(signing_public_key, |r| &r.old_public_key[..]);
+    if user_id_for_key(genesis) != *user_id {
+        return Err(PolykitError::IdentityDerivation(
+            "user_id does not match its original signing key".to_string(),
+        ));
+    }
+    let mut current = genesis;
+    let mut last_epoch = None;
+    for record in rotations {
+        if record.old_public_key != current || last_epoch.is_some_and(|e| record.epoch <= e) {
+            return Err(PolykitError::IdentityDerivation(format!(
+                "rotation to epoch {} does not continue the chain",
+                record.epoch
+            )));
+        }
+        if !crypto::verify(&record.old_public_key, &record.signed_bytes(), &record.signature)? {
+            return Err(PolykitError::Crypto(format!(
+                "rotation to epoch {} signature invalid",
+                record.epoch
+            )));
+        }
+        current = &record.new_public_key;
+        last_epoch = Some(record.epoch);
+    }
+    if current != signing_public_key {
+        return Err(PolykitError::IdentityDerivation(
+            "rotation chain does not end at the signing key".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Stand-in for the `rotate_identity` circuit in tests: fresh keys under
+/// `old`'s user_id, and the old key's continuity signature over them.
+#[cfg(test)]
+pub(crate) fn test_rotation(
+    old: &DerivedIdentity,
+    epoch: u32,
+) -> (DerivedIdentity, RotationRecord) {
+    let mut seed_input = old.signing_public_key.clone();
+    seed_input.extend_from_slice(&epoch.to_be_bytes());
+    let seed = crypto::sha3_256(&seed_input);
+    let mut identity = host::derive_keys(&seed, b"test-rotation").expect("test keys derive");
+    identity.user_id = old.user_id;
+    let mut record = RotationRecord {
+        epoch,
+        old_public_key: old.signing_public_key.clone(),
+        new_public_key: identity.signing_public_key.clone(),
+        signature: Vec::new(),
+    };
+    record.signature =
+        crypto::sign(&old.signing_secret_key, &record.signed_bytes()).expect("test key signs");
+    (identity, record)
+}
+
+// --- Identity attestation ---
+
+/// Self-signed "identity card" for exchanging contacts across apps:
+/// public keys and app context, signed by the identity's own ML-DSA key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdentityAttestation {
+    pub user_id: [u8; 16],
+    pub signing_public_key: Vec<u8>,
+    pub encryption_public_key: Vec<u8>,
+    pub app_id: String,
+    pub lex_namespace: String,
+    pub issued_at_ms: u64,
+    /// ML-DSA-87 signature over `signed_bytes`
+    pub signature: Vec<u8>,
+    /// Rotations from the key the user_id was derived from to
+    /// `signing_public_key`; empty until the identity is first rotated.
+    /// Each record carries its own signature, so `signature` doesn't cover it.
+    #[serde(default)]
+    pub rotations: Vec<RotationRecord>,
+}
+
+impl IdentityAttestation {
+    /// Attach the identity's rotation history (oldest first).
+    pub fn with_rotations(mut self, rotations: Vec<RotationRecord>) -> Self {
+        self.rotations = rotations;
+        self
+    }
+
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let mut out = b"poly-attestation-v1".to_vec();
+        for field in [
+            &self.user_id[..],
+            &self.signing_public_key,
+            &self.encryption_public_key,
+            self.app_id.as_bytes(),
+            self.lex_namespace.as_bytes(),
+            &self.issued_at_ms.to_be_bytes(),
+        ] {
+            out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            out.extend_from_slice(field);
+        }
+        out
+    }
+}
+
+/// Sign an attestation of `identity` in `ctx`, timestamped from `clock`.
+pub fn export_attestation(
+    identity: &DerivedIdentity,
+    ctx: &AppContext,
+    clock: &dyn Clock,
+) -> Result<IdentityAttestation> {
+    let mut attestation = IdentityAttestation {
+        user_id: identity.user_id,
+        signing_public_key: identity.signing_public_key.clone(),
+        encryption_public_key: identity.encryption_public_key.clone(),
+        app_id: ctx.app_id.clone(),
+        lex_namespace: ctx.lex_namespace.clone(),
+        issued_at_ms: clock.now_ms(),
+        signature: Vec::new(),
+        rotations: Vec::new(),
+    };
+    let signed = attestation.signed_bytes();
+    attestation.signature = crypto::sign(&identity.signing_secret_key, &signed)?;
+    Ok(attestation)
+}
+
+/// Check the signing key speaks for the user_id (directly, or through the
+/// attached rotations) and the signature verifies under it. This proves
+/// the card is internally consistent and made by the key holder; whether
+/// to trust that key is up to the caller.
+pub fn verify_attestation(attestation: &IdentityAttestation) -> Result<()> {
+    verify_key_chain(
+        &attestation.user_id,
+        &attestation.signing_public_key,
+        &attestation.rotations,
+    )?;
+    let signed = attestation.signed_bytes();
+    if !crypto::verify(&attestation.signing_public_key, &signed, &attestation.signature)? {
+        return Err(PolykitError::Crypto("attestation signature invalid".to_string()));
+    }
+    Ok(())
+}
+
+// --- Challenge-response proofs ---
+
+/// Shortest challenge `prove` will sign; shorter ones are too easy to
+/// repeat for a replayed proof to be rejected.
+pub const MIN_CHALLENGE_LEN: usize = 16;
+
+/// Fresh random challenge for the verifier to send (e.g., in a QR code).
+pub fn new_challenge(rng: &mut dyn Rng) -> [u8; 32] {
+    let mut challenge = [0u8; 32];
+    rng.fill_bytes(&mut challenge);
+    challenge
+}
+
+/// Prove control of `identity` by signing the verifier's challenge. The
+/// domain tag keeps the proof from doubling as a signature in any other
+/// protocol, including the SPARK wire handshake.
+pub fn prove(challenge: &[u8], identity: &DerivedIdentity) -> Result<Vec<u8>> {
+    if challenge.len() < MIN_CHALLENGE_LEN {
+        return Err(PolykitError::IdentityDerivation(format!(
+            "challenge must be at least {} bytes, got {}",
+            MIN_CHALLENGE_LEN,
+            challenge.len()
+        )));
+    }
+    crypto::sign(&identity.signing_secret_key, &proof_bytes(challenge))
+}
+
+/// Check a proof from `prove` against the claimed ML-DSA-87 public key.
+/// The verifier must check `challenge` is one it issued and hasn't
+/// accepted before.
+pub fn verify_proof(challenge: &[u8], proof: &[u8], public_key: &[u8]) -> Result<bool> {
+    if challenge.len() < MIN_CHALLENGE_LEN {
+        return Ok(false);
+    }
+    crypto::verify(public_key, &proof_bytes(challenge), proof)
+}
+
+fn proof_bytes(challenge: &[u8]) -> Vec<u8> {
+    let mut out = b"poly-identity-proof-v1".to_vec();
+    out.extend_from_slice(&(challenge.len() as u32).to_be_bytes());
+    out.extend_from_slice(challenge);
+    out
+}
+
+// --- Session identities ---
+
+/// Public half of a session identity: short-lived keys endorsed by the
+/// long-term signing key for one wire session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionEndorsement {
+    /// Long-term user_id the session keys act for
+    pub user_id: [u8; 16],
+    pub signing_public_key: Vec<u8>,
+    pub encryption_public_key: Vec<u8>,
+    /// SHA3-256 of the session token the keys are bound to
+    pub session_binding: [u8; 32],
+    pub expires_at_ms: u64,
+    /// Long-term ML-DSA-87 signature over `signed_bytes`
+    pub signature: Vec<u8>,
+    /// Rotations from the key `user_id` was derived from to the endorsing
+    /// key; empty if the long-term identity never rotated
+    #[serde(default)]
+    pub rotations: Vec<RotationRecord>,
+}
+
+impl SessionEndorsement {
+    /// Attach the long-term identity's rotation history (oldest first).
+    pub fn with_rotations(mut self, rotations: Vec<RotationRecord>) -> Self {
+        self.rotations = rotations;
+        self
+    }
+
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let mut out = b"poly-session-identity-v1".to_vec();
+        for field in [
+            &self.user_id[..],
+            &self.signing_public_key,
+            &self.encryption_public_key,
+            &self.session_binding,
+            &self.expires_at_ms.to_be_bytes(),
+        ] {
+            out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            out.extend_from_slice(field);
+        }
+        out
+    }
+}
+
+/// Ephemeral ML-DSA/ML-KEM pairs for one wire session. They come from
+/// fresh randomness, not the seed, so discarding them at expiry gives
+/// forward secrecy.
+#[derive(Debug)]
+pub struct SessionIdentity {
+    pub endorsement: SessionEndorsement,
+    pub signing_secret_key: Secret<Vec<u8>>,
+    pub encryption_secret_key: Secret<Vec<u8>>,
+}
+
+impl SessionIdentity {
+    pub fn is_expired(&self, clock: &dyn Clock) -> bool {
+        clock.now_ms() >= self.endorsement.expires_at_ms
+    }
+}
+
+/// Create session keys bound to `session`, valid for `ttl_ms`, endorsed
+/// by the long-term identity.
+pub fn derive_session_identity(
+    long_term: &DerivedIdentity,
+    session: &WireSession,
+    ttl_ms: u64,
+    clock: &dyn Clock,
+    rng: &mut dyn Rng,
+) -> Result<SessionIdentity> {
+    let (signing_public_key, signing_secret_key) = crypto::signing_keypair(rng);
+    let (encryption_public_key, encryption_secret_key) = crypto::kem_keypair(rng);
+    let mut endorsement = SessionEndorsement {
+        user_id: long_term.user_id,
+        signing_public_key,
+        encryption_public_key,
+        session_binding: crypto::sha3_256(&session.session_token),
+        expires_at_ms: clock.now_ms().saturating_add(ttl_ms),
+        signature: Vec::new(),
+        rotations: Vec::new(),
+    };
+    let signed = endorsement.signed_bytes();
+    endorsement.signature = crypto::sign(&long_term.signing_secret_key, &signed)?;
+    Ok(SessionIdentity { endorsement, signing_secret_key, encryption_secret_key })
+}
+
+/// Peer side: accept session keys only if the long-term key speaks for
+/// the endorsed user_id, endorsed them for this session, and they haven't
+/// expired.
+pub fn verify_session_endorsement(
+    endorsement: &SessionEndorsement,
+    long_term_public_key: &[u8],
+    session: &WireSession,
+    clock: &dyn Clock,
+) -> Result<()> {
+    verify_key_chain(&endorsement.user_id, long_term_public_key, &endorsement.rotations)?;
+    if endorsement.session_binding != crypto::sha3_256(&session.session_token) {
+        return Err(PolykitError::Wire("session identity bound to a different session".to_string()));
+    }
+    if clock.now_ms() >= endorsement.expires_at_ms {
+        return Err(PolykitError::Wire("session identity expired".to_string()));
+    }
+    if !crypto::verify(long_term_public_key, &endorsement.signed_bytes(), &endorsement.signature)? {
+        return Err(PolykitError::Crypto("session identity endorsement invalid".to_string()));
+    }
+    Ok(())
+}
+
+// --- Key revocation ---
+
+/// Signed notice that a public key must no longer be trusted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationEntry {
+    /// Revoked ML-DSA-87 public key
+    pub public_key: Vec<u8>,
+    pub revoked_at_ms: u64,
+    pub reason: String,
+    /// Key that signed the entry: the revoked key itself or a list authority
+    pub signer_public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl RevocationEntry {
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let mut out = b"poly-revocation-v1".to_vec();
+        for field in [
+            &self.public_key[..],
+            &self.revoked_at_ms.to_be_bytes(),
+            self.reason.as_bytes(),
+        ] {
+            out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            out.extend_from_slice(field);
+        }
+        out
+    }
+}
+
+/// Sign a revocation of `public_key` with `signer_secret_key` (the
+/// compromised key itself, or an authority key).
+pub fn revoke_key(
+    public_key: &[u8],
+    reason: &str,
+    signer_secret_key: &[u8],
+    signer_public_key: &[u8],
+    clock: &dyn Clock,
+) -> Result<RevocationEntry> {
+    let mut entry = RevocationEntry {
+        public_key: public_key.to_vec(),
+        revoked_at_ms: clock.now_ms(),
+        reason: reason.to_string(),
+        signer_public_key: signer_public_key.to_vec(),
+        signature: Vec::new(),
+    };
+    entry.signature = crypto::sign(signer_secret_key, &entry.signed_bytes())?;
+    Ok(entry)
+}
+
+/// Revoked keys, keyed by the SHA3-256 of the public key. Only entries
+/// signed by the revoked key or by one of the list's authorities are
+/// accepted, so a third party can't revoke someone else's key.
+#[derive(Debug, Clone, Default)]
+pub struct RevocationList {
+    entries: BTreeMap<[u8; 32], RevocationEntry>,
+    authorities: Vec<Vec<u8>>,
+}
+
+impl RevocationList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also accept revocations signed by `public_key` (e.g., an org admin).
+    pub fn with_authority(mut self, public_key: &[u8]) -> Self {
+        self.authorities.push(public_key.to_vec());
+        self
+    }
+
+    /// Verify and add an entry. If the key is already revoked, the earlier
+    /// revocation time is kept.
+    pub fn add(&mut self, entry: RevocationEntry) -> Result<()> {
+        let authorized = entry.signer_public_key == entry.public_key
+            || self.authorities.contains(&entry.signer_public_key);
+        if !authorized {
+            return Err(PolykitError::Unauthorized {
+                required_role: "key holder or revocation authority".to_string(),
+                actual_roles: Vec::new(),
+            });
+        }
+        if !crypto::verify(&entry.signer_public_key, &entry.signed_bytes(), &entry.signature)? {
+            return Err(PolykitError::Crypto("revocation signature invalid".to_string()));
+        }
+        let key = crypto::sha3_256(&entry.public_key);
+        match self.entries.get(&key) {
+            Some(existing) if existing.revoked_at_ms <= entry.revoked_at_ms => {}
+            _ => {
+                self.entries.insert(key, entry);
+            }
+        }
+        Ok(())
+    }
+
+    /// The revocation for `public_key`, if it's revoked.
+    pub fn check(&self, public_key: &[u8]) -> Option<&RevocationEntry> {
+        self.entries.get(&crypto::sha3_256(public_key))
+    }
+
+    pub fn is_revoked(&self, public_key: &[u8]) -> bool {
+        self.check(public_key).is_some()
+    }
+
+    /// Add every entry from `other` that verifies under this list's rules.
+    /// Returns how many were rejected.
+    pub fn merge(&mut self, other: &RevocationList) -> usize {
+        other.entries.values().filter(|entry| self.add((*entry).clone()).is_err()).count()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &RevocationEntry> {
+        self.entries.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Topic revocations for an app are published on: `{namespace}.revocations`.
+pub fn revocation_topic(ctx: &AppContext) -> String {
+    format!("{}.revocations", ctx.lex_namespace)
+}
+
+// --- Device linking ---
+//
+// 1. The new (secondary) device calls `request_device_link`, shows the
+//    request to the primary (e.g., as a QR code), and keeps the secret.
+// 2. Both devices display `link_code(&request)`; the user checks they match.
+// 3. The primary calls `wrap_device_seed`, sealing a device-scoped sub-seed
+//    to the request's ephemeral ML-KEM key and signing it.
+// 4. The secondary calls `accept_device_link` with the primary's pinned
+//    public key, recovering the sub-seed, and sends back the confirmation.
+// 5. The primary calls `confirm_device_link` to check the device holds it.
+//
+// The master seed never leaves the primary; a device's sub-seed can't be
+// used to derive another device's.
+
+/// Sent by the secondary device to start linking.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceLinkRequest {
+    /// Random identifier for the new device
+    pub device_id: [u8; 16],
+    /// Ephemeral ML-KEM-1024 public key for this link only
+    pub ephemeral_public_key: Vec<u8>,
+}
+
+/// The secondary device's half of a pending link. Discard after use.
+pub struct DeviceLinkSecret {
+    ephemeral_secret_key: Secret<Vec<u8>>,
+}
+
+/// Sent by the primary: the sub-seed sealed to the request's ephemeral key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceLinkGrant {
+    pub device_id: [u8; 16],
+    pub sealed_seed: SealedMessage,
+}
+
+/// Secondary side: create a link request with a fresh ephemeral key.
+pub fn request_device_link(rng: &mut dyn Rng) -> (DeviceLinkRequest, DeviceLinkSecret) {
+    let mut device_id = [0u8; 16];
+    rng.fill_bytes(&mut device_id);
+    let (ephemeral_public_key, ephemeral_secret_key) = crypto::kem_keypair(rng);
+    (
+        DeviceLinkRequest { device_id, ephemeral_public_key },
+        DeviceLinkSecret { ephemeral_secret_key },
+    )
+}
+
+/// Six-digit code both devices display so the user can confirm the primary
+/// is answering the request the secondary made.
+pub fn link_code(request: &DeviceLinkRequest) -> String {
+    let digest = crypto::sha3_256(&link_aad(request));
+    let n = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) % 1_000_000;
+    format!("{:06}", n)
+}
+
+/// Device-scoped sub-seed: HKDF of the master seed bound to the device id.
+pub fn derive_device_seed(
+    master_seed: &[u8; 32],
+    device_id: &[u8; 16],
+) -> Result<Secret<[u8; 32]>> {
+    let mut info = b"poly-device-seed-v1".to_vec();
+    info.extend_from_slice(device_id);
+    crypto::hkdf_sha3_256(master_seed, &info)
+}
+
+/// Primary side: seal the device's sub-seed to the request's ephemeral
+/// key, signed with the primary's ML-DSA-87 key.
+pub fn wrap_device_seed(
+    master_seed: &[u8; 32],
+    request: &DeviceLinkRequest,
+    primary_secret_key: &[u8],
+    primary_public_key: &[u8],
+    rng: &mut dyn Rng,
+) -> Result<DeviceLinkGrant> {
+    let device_seed = derive_device_seed(master_seed, &request.device_id)?;
+    let sealed_seed = crypto::seal(
+        &request.ephemeral_public_key,
+        primary_secret_key,
+        primary_public_key,
+        &device_seed[..],
+        &link_aad(request),
+        rng,
+    )?;
+    Ok(DeviceLinkGrant { device_id: request.device_id, sealed_seed })
+}
+
+/// Secondary side: verify the grant came from the pinned primary key and
+/// answers this request, then recover the sub-seed. Returns it with the
+/// confirmation to send back.
+pub fn accept_device_link(
+    request: &DeviceLinkRequest,
+    secret: DeviceLinkSecret,
+    grant: &DeviceLinkGrant,
+    primary_public_key: &[u8],
+) -> Result<(Secret<[u8; 32]>, [u8; 32])> {
+    if grant.device_id != request.device_id {
+        return Err(PolykitError::IdentityDerivation("grant is for a different device".to_string()));
+    }
+    if grant.sealed_seed.sender_public_key != primary_public_key {
+        return Err(PolykitError::IdentityDerivation(
+            "grant not signed by the pinned primary key".to_string(),
+        ));
+    }
+    let aad = link_aad(request);
+    let seed = Secret::new(crypto::open(&secret.ephemeral_secret_key, &grant.sealed_seed, &aad)?);
+    if seed.len() != 32 {
+        return Err(PolykitError::IdentityDerivation("device seed must be 32 bytes".to_string()));
+    }
+    let mut device_seed = Secret::new([0u8; 32]);
+    device_seed.copy_from_slice(&seed);
+    let confirmation = link_confirmation(&device_seed, request);
+    Ok((device_seed, confirmation))
+}
+
+/// Primary side: check the secondary recovered the sub-seed, completing
+/// the link.
+pub fn confirm_device_link(
+    master_seed: &[u8; 32],
+    request: &DeviceLinkRequest,
+    confirmation: &[u8; 32],
+) -> Result<bool> {
+    let device_seed = derive_device_seed(master_seed, &request.device_id)?;
+    Ok(link_confirmation(&device_seed, request) == *confirmation)
+}
+
+/// Request binding used as AAD and for the link code.
+fn link_aad(request: &DeviceLinkRequest) -> Vec<u8> {
+    let mut aad = b"poly-device-link-v1".to_vec();
+    aad.extend_from_slice(&request.device_id);
+    aad.extend_from_slice(&request.ephemeral_public_key);
+    aad
+}
+
+fn link_confirmation(device_seed: &[u8; 32], request: &DeviceLinkRequest) -> [u8; 32] {
+    let mut input = b"poly-device-confirm-v1".to_vec();
+    input.extend_from_slice(device_seed);
+    input.extend_from_slice(&link_aad(request));
+    crypto::sha3_256(&input)
+}
+
+#[cfg(not(any(test, feature = "test-crypto")))]
+mod host {
+    use super::DerivedIdentity;
+    use crate::crypto;
+    use crate::error::Result;
+    use crate::secret::Secret;
+
+    // In production: host import estream::derive_keys, backed by the
+    // derive_keys circuit export (polykit_identity.fl).
+    pub fn derive_keys(_master_seed: &[u8; 32], _hkdf_context: &[u8]) -> Result<DerivedIdentity> {
+        let signing_public_key = vec![0u8; crypto::MLDSA87_PUBLIC_KEY_LEN];
+        Ok(DerivedIdentity {
+            user_id: crypto::sha3_256(&signing_public_key)[..16]
+                .try_into()
+                .expect("sliced to 16 bytes"),
+            signing_public_key,
+            signing_secret_key: Secret::new(vec![0u8; crypto::MLDSA87_SECRET_KEY_LEN]),
+            encryption_public_key: vec![0u8; crypto::MLKEM1024_PUBLIC_KEY_LEN],
+            encryption_secret_key: Secret::new(vec![0u8; crypto::MLKEM1024_SECRET_KEY_LEN]),
+        }) // Stub
+    }
+
+    // In production: host import estream::verify_platform_attestation,
+    // which checks the platform's certificate chain against pinned vendor
+    // roots and that the attested challenge is `seed_commitment`.
+    pub fn verify_platform_attestation(
+        _platform: u8,
+        _evidence: &[u8],
+        _seed_commitment: &[u8; 32],
+    ) -> bool {
+        false // Stub
+    }
+}
+
+/// Stand-ins for tests (`test-crypto`): keys come from a `SeededRng` keyed
+/// by the seed and context, so derivation stays deterministic.
+#[cfg(any(test, feature = "test-crypto"))]
+mod host {
+    use super::DerivedIdentity;
+    use crate::crypto::{self, SeededRng};
+    use crate::error::Result;
+
+    pub fn derive_keys(master_seed: &[u8; 32], hkdf_context: &[u8]) -> Result<DerivedIdentity> {
+        let seed = crypto::hkdf_sha3_256(master_seed, hkdf_context)?;
+        let mut rng = SeededRng::from_seed(*seed);
+        let (signing_public_key, signing_secret_key) = crypto::signing_keypair(&mut rng);
+        let (encryption_public_key, encryption_secret_key) = crypto::kem_keypair(&mut rng);
+        Ok(DerivedIdentity {
+            user_id: crypto::sha3_256(&signing_public_key)[..16]
+                .try_into()
+                .expect("sliced to 16 bytes"),
+            signing_public_key,
+            signing_secret_key,
+            encryption_public_key,
+            encryption_secret_key,
+        })
+    }
+
+    /// Accepts evidence that is exactly the commitment.
+    pub fn verify_platform_attestation(
+        _platform: u8,
+        evidence: &[u8],
+        seed_commitment: &[u8; 32],
+    ) -> bool {
+        evidence == seed_commitment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: [u8; 32] = [7; 32];
+
+    fn ctx() -> AppContext {
+        create_app_context("polydata", "poly-data-v1", "polydata")
+    }
+
+    fn attestation_of(identity: &DerivedIdentity) -> IdentityAttestation {
+        export_attestation(identity, &ctx(), &|| 1_000).unwrap()
+    }
+
+    #[test]
+    fn continuity_payload_matches_the_circuit_golden() {
+        // Same digest as the "continuity_payload_golden" test in
+        // polykit_identity.fl
+        let record = RotationRecord {
+            epoch: 1,
+            old_public_key: Vec::new(),
+            new_public_key: vec![0xab; crypto::MLDSA87_PUBLIC_KEY_LEN],
+            signature: Vec::new(),
+        };
+        let digest: String =
+            crypto::sha3_256(&record.signed_bytes()).iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(digest, "1c6be4fa6758991b8f5495b6d80c66c1bc699c41b5f76adbc9869be51082e5eb");
+    }
+
+    #[test]
+    fn rotated_attestation_verifies_through_its_chain() {
+        let original = derive_identity(&SEED, &ctx()).unwrap();
+        let (first, first_record) = test_rotation(&original, 1);
+        let (second, second_record) = test_rotation(&first, 2);
+
+        assert!(verify_attestation(&attestation_of(&original)).is_ok());
+        // Without its history the rotated key can't speak for the user_id
+        assert!(verify_attestation(&attestation_of(&second)).is_err());
+        let card = attestation_of(&second).with_rotations(vec![first_record, second_record]);
+        assert!(verify_attestation(&card).is_ok());
+    }
+
+    #[test]
+    fn broken_rotation_chains_are_rejected() {
+        let original = derive_identity(&SEED, &ctx()).unwrap();
+        let (first, first_record) = test_rotation(&original, 1);
+        let (second, second_record) = test_rotation(&first, 2);
+        let key = &second.signing_public_key;
+        let user_id = &original.user_id;
+        let chain = [first_record.clone(), second_record.clone()];
+        assert!(verify_key_chain(user_id, key, &chain).is_ok());
+
+        // A missing link
+        assert!(verify_key_chain(user_id, key, std::slice::from_ref(&second_record)).is_err());
+        // Out of order
+        let reordered = [second_record.clone(), first_record.clone()];
+        assert!(verify_key_chain(user_id, key, &reordered).is_err());
+        // Tampered signature
+        let mut forged = first_record.clone();
+        forged.signature[0] ^= 1;
+        assert!(verify_key_chain(user_id, key, &[forged, second_record]).is_err());
+        // Replayed epoch
+        let (stale, stale_record) = test_rotation(&first, 1);
+        let chain = [first_record, stale_record];
+        assert!(verify_key_chain(user_id, &stale.signing_public_key, &chain).is_err());
+    }
+
+    #[test]
+    fn chain_rooted_in_another_identity_is_rejected() {
+        let original = derive_identity(&SEED, &ctx()).unwrap();
+        let attacker = derive_identity(&[9; 32], &ctx()).unwrap();
+        let (hijack, hijack_record) = test_rotation(&attacker, 1);
+        let result =
+            verify_key_chain(&original.user_id, &hijack.signing_public_key, &[hijack_record]);
+        assert!(result.is_err());
+    }
+
+    fn primary() -> DerivedIdentity {
+        derive_identity(&SEED, &ctx()).unwrap()
+    }
+
+    fn link(
+        primary: &DerivedIdentity,
+        seed: u64,
+    ) -> (DeviceLinkRequest, DeviceLinkSecret, DeviceLinkGrant) {
+        let mut rng = crypto::SeededRng::seed_from_u64(seed);
+        let (request, secret) = request_device_link(&mut rng);
+        let grant = wrap_device_seed(
+            &SEED,
+            &request,
+            &primary.signing_secret_key,
+            &primary.signing_public_key,
+            &mut rng,
+        )
+        .unwrap();
+        (request, secret, grant)
+    }
+
+    #[test]
+    fn linked_device_recovers_its_sub_seed_and_confirms() {
+        let primary = primary();
+        let (request, secret, grant) = link(&primary, 1);
+        let (device_seed, confirmation) =
+            accept_device_link(&request, secret, &grant, &primary.signing_public_key).unwrap();
+        assert_eq!(*device_seed, *derive_device_seed(&SEED, &request.device_id).unwrap());
+        assert_ne!(*device_seed, SEED);
+        assert!(confirm_device_link(&SEED, &request, &confirmation).unwrap());
+        assert!(!confirm_device_link(&[9; 32], &request, &confirmation).unwrap());
+    }
+
+    #[test]
+    fn link_code_matches_on_both_devices_and_differs_per_request() {
+        let (request, _, _) = link(&primary(), 1);
+        let (other, _, _) = link(&primary(), 2);
+        let code = link_code(&request);
+        assert_eq!(code.len(), 6);
+        assert_eq!(code, link_code(&request.clone()));
+        assert_ne!(code, link_code(&other));
+    }
+
+    #[test]
+    fn grant_from_an_unpinned_key_is_rejected() {
+        let (request, secret, grant) = link(&primary(), 1);
+        let impostor = derive_identity(&[9; 32], &ctx()).unwrap();
+        let result = accept_device_link(&request, secret, &grant, &impostor.signing_public_key);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn grant_for_another_request_is_rejected() {
+        let primary = primary();
+        let (request, secret, _) = link(&primary, 1);
+        let (_, _, other_grant) = link(&primary, 2);
+        let pinned = &primary.signing_public_key;
+        assert!(accept_device_link(&request, secret, &other_grant, pinned).is_err());
+    }
+
+    #[test]
+    fn device_seeds_are_scoped_per_device() {
+        let a = derive_device_seed(&SEED, &[1; 16]).unwrap();
+        let b = derive_device_seed(&SEED, &[2; 16]).unwrap();
+        assert_ne!(*a, *b);
+    }
+
+    #[test]
+    fn attestation_carries_keys_and_context() {
+        let identity = primary();
+        let card = attestation_of(&identity);
+        assert_eq!(card.user_id, identity.user_id);
+        assert_eq!(card.encryption_public_key, identity.encryption_public_key);
+        assert_eq!((card.app_id.as_str(), card.issued_at_ms), ("polydata", 1_000));
+        let json = serde_json::to_string(&card).unwrap();
+        let parsed: IdentityAttestation = serde_json::from_str(&json).unwrap();
+        assert!(verify_attestation(&parsed).is_ok());
+    }
+
+    #[test]
+    fn tampered_attestation_fails() {
+        let card = attestation_of(&primary());
+
+        let mut swapped_key = card.clone();
+        swapped_key.encryption_public_key[0] ^= 1;
+        assert!(verify_attestation(&swapped_key).is_err());
+
+        let mut other_app = card.clone();
+        other_app.app_id = "polymessenger".to_string();
+        assert!(verify_attestation(&other_app).is_err());
+
+        let mut wrong_user = card;
+        wrong_user.user_id[0] ^= 1;
+        assert!(verify_attestation(&wrong_user).is_err());
+    }
+
+    fn revoked_by(key: &DerivedIdentity, signer: &DerivedIdentity, at: u64) -> RevocationEntry {
+        revoke_key(
+            &key.signing_public_key,
+            "device lost",
+            &signer.signing_secret_key,
+            &signer.signing_public_key,
+            &move || at,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn only_the_key_holder_or_an_authority_can_revoke() {
+        let victim = primary();
+        let admin = derive_identity(&[9; 32], &ctx()).unwrap();
+        let mut list = RevocationList::new();
+        assert!(list.add(revoked_by(&victim, &admin, 1_000)).is_err());
+        assert!(list.add(revoked_by(&victim, &victim, 1_000)).is_ok());
+        assert!(list.is_revoked(&victim.signing_public_key));
+
+        let mut managed = RevocationList::new().with_authority(&admin.signing_public_key);
+        assert!(managed.add(revoked_by(&victim, &admin, 1_000)).is_ok());
+    }
+
+    #[test]
+    fn merge_keeps_the_earliest_revocation_and_drops_forgeries() {
+        let victim = primary();
+        let admin = derive_identity(&[9; 32], &ctx()).unwrap();
+        let mut ours = RevocationList::new();
+        ours.add(revoked_by(&victim, &victim, 5_000)).unwrap();
+
+        let mut theirs = RevocationList::new().with_authority(&admin.signing_public_key);
+        theirs.add(revoked_by(&victim, &victim, 2_000)).unwrap();
+        theirs.add(revoked_by(&admin, &admin, 3_000)).unwrap();
+        let other = derive_identity(&[10; 32], &ctx()).unwrap();
+        theirs.add(revoked_by(&other, &admin, 3_000)).unwrap();
+
+        // The admin isn't an authority here, so its revocation of `other` is rejected
+        assert_eq!(ours.merge(&theirs), 1);
+        assert_eq!(ours.len(), 2);
+        assert_eq!(ours.check(&victim.signing_public_key).unwrap().revoked_at_ms, 2_000);
+        assert!(!ours.is_revoked(&other.signing_public_key));
+    }
+
+    #[test]
+    fn each_topic_gets_its_own_key() {
+        let app_key = derive_app_key(&SEED, &ctx()).unwrap();
+        let user_id = primary().user_id;
+        let files = format_user_topic(&ctx(), &user_id, "files");
+        let settings = format_user_topic(&ctx(), &user_id, "settings");
+        let key = |topic: &str| derive_topic_key(&app_key, &ctx(), &user_id, topic).unwrap();
+
+        assert_eq!(*key(&files), *key(&files));
+        assert_ne!(*key(&files), *key(&settings));
+        assert_ne!(*key(&files), *app_key);
+    }
+
+    #[test]
+    fn topic_keys_are_scoped_to_app_and_user() {
+        let user_id = primary().user_id;
+        let other_ctx = create_app_context("polymessenger", "poly-msg-v1", "polymsg");
+        let data_key = derive_app_key(&SEED, &ctx()).unwrap();
+        let msg_key = derive_app_key(&SEED, &other_ctx).unwrap();
+        assert_ne!(*data_key, *msg_key);
+
+        let topic = "polydata.shared.files";
+        let mine = derive_topic_key(&data_key, &ctx(), &user_id, topic).unwrap();
+        let theirs = derive_topic_key(&data_key, &ctx(), &[0; 16], topic).unwrap();
+        assert_ne!(*mine, *theirs);
+    }
+
+    #[test]
+    fn context_versions_parse_and_bump() {
+        assert_eq!(ctx().context_version(), Some(("poly-data", 1)));
+        assert_eq!(ctx().with_context_version(2).unwrap().hkdf_context, "poly-data-v2");
+        let unversioned = create_app_context("legacy", "legacy-data", "legacy");
+        assert_eq!(unversioned.context_version(), None);
+        assert!(unversioned.with_context_version(2).is_err());
+    }
+
+    #[test]
+    fn migration_yields_both_identities_and_a_plan() {
+        let migration = ContextMigration::new(&ctx(), 2).unwrap();
+        let both = derive_identity_versioned(&SEED, &migration, &["files", "settings"]).unwrap();
+        assert_eq!(both.previous.user_id, primary().user_id);
+        assert_ne!(both.current.user_id, both.previous.user_id);
+
+        let plan = &both.plan;
+        assert_eq!(plan.from_context, "poly-data-v1");
+        assert_eq!(plan.to_context, "poly-data-v2");
+        assert_eq!(plan.steps.len(), 2);
+        let files = format_user_topic(&ctx(), &plan.from_user_id, "files");
+        assert_eq!(plan.steps[0].from_topic, files);
+        let settings = format_user_topic(&migration.to, &plan.to_user_id, "settings");
+        assert_eq!(plan.steps[1].to_topic, settings);
+    }
+
+    #[test]
+    fn migration_must_move_forward() {
+        let v2 = ctx().with_context_version(2).unwrap();
+        assert!(ContextMigration::new(&v2, 2).is_err());
+        assert!(ContextMigration::new(&v2, 1).is_err());
+    }
+
+    #[test]
+    fn derivation_paths_parse_and_display() {
+        let path = DerivationPath::parse("m/polydata/upload/0").unwrap();
+        assert_eq!(path.segments(), ["polydata", "upload", "0"]);
+        assert_eq!(path.to_string(), "m/polydata/upload/0");
+        assert_eq!(DerivationPath::parse("m").unwrap(), DerivationPath::root());
+        for bad in ["", "x/app", "m//app", "m/App", "m/app/*"] {
+            assert!(DerivationPath::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn purpose_keys_are_deterministic_and_distinct() {
+        let at = |path: &str| {
+            let path = DerivationPath::parse(path).unwrap();
+            derive_identity_at(&SEED, &ctx(), &path).unwrap().signing_public_key
+        };
+        assert_eq!(at("m/polydata/upload/0"), at("m/polydata/upload/0"));
+        assert_ne!(at("m/polydata/upload/0"), at("m/polydata/upload/1"));
+        assert_ne!(at("m/polydata/upload/0"), at("m/polydata/backup/0"));
+        assert_eq!(at("m"), primary().signing_public_key);
+    }
+
+    #[test]
+    fn child_seed_follows_from_its_parent() {
+        let parent = DerivationPath::parse("m/polydata").unwrap();
+        let child = parent.clone().child("upload").unwrap();
+        let parent_seed = derive_path_seed(&SEED, &parent).unwrap();
+        let relative = DerivationPath::parse("m/upload").unwrap();
+        let via_parent = derive_path_seed(&parent_seed, &relative).unwrap();
+        assert_eq!(*derive_path_seed(&SEED, &child).unwrap(), *via_parent);
+    }
+
+    fn session(token: u8) -> WireSession {
+        WireSession {
+            session_token: vec![token; 32],
+            transport: crate::wire::Transport::WebTransport,
+            edge_node: "edge-1".to_string(),
+            mtu: crate::wire::DEFAULT_MTU,
+        }
+    }
+
+    fn session_identity(long_term: &DerivedIdentity) -> SessionIdentity {
+        let mut rng = crypto::SeededRng::seed_from_u64(5);
+        derive_session_identity(long_term, &session(1), 60_000, &|| 1_000, &mut rng).unwrap()
+    }
+
+    #[test]
+    fn session_keys_verify_for_their_session_until_expiry() {
+        let long_term = primary();
+        let keys = session_identity(&long_term);
+        let endorsement = &keys.endorsement;
+        let pk = &long_term.signing_public_key;
+        assert_ne!(endorsement.signing_public_key, long_term.signing_public_key);
+        assert!(verify_session_endorsement(endorsement, pk, &session(1), &|| 2_000).is_ok());
+        assert!(verify_session_endorsement(endorsement, pk, &session(2), &|| 2_000).is_err());
+        assert!(verify_session_endorsement(endorsement, pk, &session(1), &|| 61_000).is_err());
+        assert!(keys.is_expired(&|| 61_000));
+    }
+
+    #[test]
+    fn endorsement_must_come_from_the_user_ids_key() {
+        let victim = primary();
+        let attacker = derive_identity(&[9; 32], &ctx()).unwrap();
+        // Validly signed by the attacker, but claiming the victim's user_id
+        let mut forged = session_identity(&attacker).endorsement;
+        forged.user_id = victim.user_id;
+        let signed = forged.signed_bytes();
+        forged.signature = crypto::sign(&attacker.signing_secret_key, &signed).unwrap();
+        let pk = &attacker.signing_public_key;
+        assert!(verify_session_endorsement(&forged, pk, &session(1), &|| 2_000).is_err());
+    }
+
+    #[test]
+    fn rotated_long_term_key_endorses_with_its_chain() {
+        let original = primary();
+        let (rotated, record) = test_rotation(&original, 1);
+        let endorsement = session_identity(&rotated).endorsement;
+        let pk = &rotated.signing_public_key;
+        assert!(verify_session_endorsement(&endorsement, pk, &session(1), &|| 2_000).is_err());
+        let endorsement = endorsement.with_rotations(vec![record]);
+        assert!(verify_session_endorsement(&endorsement, pk, &session(1), &|| 2_000).is_ok());
+    }
+
+    #[test]
+    fn proof_verifies_for_its_challenge_only() {
+        let identity = primary();
+        let challenge = new_challenge(&mut crypto::SeededRng::seed_from_u64(1));
+        let proof = prove(&challenge, &identity).unwrap();
+        assert!(verify_proof(&challenge, &proof, &identity.signing_public_key).unwrap());
+
+        let other = new_challenge(&mut crypto::SeededRng::seed_from_u64(2));
+        assert!(!verify_proof(&other, &proof, &identity.signing_public_key).unwrap());
+        let stranger = derive_identity(&[9; 32], &ctx()).unwrap();
+        assert!(!verify_proof(&challenge, &proof, &stranger.signing_public_key).unwrap());
+    }
+
+    #[test]
+    fn proof_is_domain_separated_and_needs_a_real_challenge() {
+        let identity = primary();
+        let challenge = [3u8; 32];
+        // A plain signature over the challenge doesn't pass as a proof
+        let raw = crypto::sign(&identity.signing_secret_key, &challenge).unwrap();
+        assert!(!verify_proof(&challenge, &raw, &identity.signing_public_key).unwrap());
+
+        let short = [3u8; MIN_CHALLENGE_LEN - 1];
+        assert!(prove(&short, &identity).is_err());
+        let proof = crypto::sign(&identity.signing_secret_key, &proof_bytes(&short)).unwrap();
+        assert!(!verify_proof(&short, &proof, &identity.signing_public_key).unwrap());
+    }
+
+    #[test]
+    fn registry_rejects_a_shared_hkdf_context() {
+        let mut registry = AppContextRegistry::new();
+        registry.register(&ctx()).unwrap();
+        let err = registry.create("polymail", "poly-data-v1", "polymail").unwrap_err();
+        match err {
+            PolykitError::ContextCollision { field, app_id, existing_app_id, value } => {
+                assert_eq!(field, "hkdf_context");
+                assert_eq!(app_id, "polymail");
+                assert_eq!(existing_app_id, "polydata");
+                assert_eq!(value, "poly-data-v1");
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+        assert!(registry.get("polymail").is_none());
+    }
+
+    #[test]
+    fn registry_rejects_nested_namespaces_only() {
+        let mut registry = AppContextRegistry::new();
+        registry.register(&ctx()).unwrap();
+        for nested in ["polydata.mail", "polydata/mail"] {
+            let err = registry.create("polymail", "poly-mail-v1", nested).unwrap_err();
+            assert!(matches!(
+                err,
+                PolykitError::ContextCollision { ref field, .. } if field == "lex_namespace"
+            ));
+        }
+        // A shared prefix without a path boundary isn't nesting
+        registry.create("polymail", "poly-mail-v1", "polydatamail").unwrap();
+        assert_eq!(registry.contexts().count(), 2);
+    }
+
+    #[test]
+    fn registry_lets_an_app_re_register() {
+        let mut registry = AppContextRegistry::new();
+        registry.register(&ctx()).unwrap();
+        let bumped = create_app_context("polydata", "poly-data-v2", "polydata");
+        registry.register(&bumped).unwrap();
+        assert_eq!(registry.get("polydata").unwrap().hkdf_context, "poly-data-v2");
+        assert!(registry.remove("polydata").is_some());
+        assert_eq!(registry.contexts().count(), 0);
+    }
+
+    fn attestation_blob(
+        platform: AttestationPlatform,
+        commitment: &[u8],
+        evidence: &[u8],
+    ) -> Vec<u8> {
+        let mut blob = vec![SEED_ATTESTATION_VERSION, platform.tag()];
+        for field in [commitment, evidence] {
+            blob.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            blob.extend_from_slice(field);
+        }
+        blob
+    }
+
+    #[test]
+    fn seed_attestation_reports_provenance() {
+        let commitment = seed_commitment(&SEED);
+        let software = attestation_blob(AttestationPlatform::Software, &commitment, &[]);
+        let attestation = verify_seed_attestation(&software).unwrap();
+        assert_eq!(attestation.platform, AttestationPlatform::Software);
+        assert!(!attestation.is_hardware_backed());
+
+        let strongbox =
+            attestation_blob(AttestationPlatform::AndroidStrongBox, &commitment, &commitment);
+        let attestation = verify_seed_attestation(&strongbox).unwrap();
+        assert!(attestation.is_hardware_backed());
+        assert_eq!(attestation.seed_commitment, commitment);
+    }
+
+    #[test]
+    fn malformed_seed_attestations_are_rejected() {
+        let commitment = seed_commitment(&SEED);
+        let valid = attestation_blob(AttestationPlatform::AndroidTee, &commitment, &commitment);
+        let mut bad_version = valid.clone();
+        bad_version[0] = SEED_ATTESTATION_VERSION + 1;
+        let mut bad_platform = valid.clone();
+        bad_platform[1] = 9;
+        let mut trailing = valid.clone();
+        trailing.push(0);
+        let cases = [
+            Vec::new(),
+            bad_version,
+            bad_platform,
+            valid[..valid.len() - 1].to_vec(),
+            trailing,
+            attestation_blob(AttestationPlatform::Software, &commitment[..16], &[]),
+            attestation_blob(AttestationPlatform::Software, &commitment, &commitment),
+            attestation_blob(AttestationPlatform::AndroidTee, &commitment, &[1; 32]),
+        ];
+        for blob in cases {
+            assert!(verify_seed_attestation(&blob).is_err());
+        }
+    }
+
+    #[test]
+    fn attested_derivation_checks_seed_and_hardware() {
+        let commitment = seed_commitment(&SEED);
+        let hardware =
+            attestation_blob(AttestationPlatform::AppleSecureEnclave, &commitment, &commitment);
+        let (identity, attestation) =
+            derive_identity_attested(&SEED, &ctx(), &hardware, true).unwrap();
+        assert_eq!(identity.user_id, primary().user_id);
+        assert_eq!(attestation.platform, AttestationPlatform::AppleSecureEnclave);
+
+        let software = attestation_blob(AttestationPlatform::Software, &commitment, &[]);
+        assert!(derive_identity_attested(&SEED, &ctx(), &software, false).is_ok());
+        let err = derive_identity_attested(&SEED, &ctx(), &software, true).unwrap_err();
+        assert!(err.to_string().contains("not hardware-backed"));
+        let err = derive_identity_attested(&[8; 32], &ctx(), &hardware, false).unwrap_err();
+        assert!(err.to_string().contains("different seed"));
+    }
+}
@@ -0,0 +1,237 @@
+//! Delegated sub-identities
+//!
+//! A parent identity hands a guest (e.g., a share-link recipient) its own
+//! key pair plus a capability statement signed by the parent's ML-DSA-87
+//! key. The guest never holds the parent's seed or keys, and anything it
+//! signs is only honored within the capability's topics, tier, and expiry.
+
+use serde::{Deserialize, Serialize};
+use crate::classification::Classification;
+use crate::clock::Clock;
+use crate::crypto;
+use crate::error::{PolykitError, Result, ResultExt};
+use crate::secret::Secret;
+use super::{user_id_for_key, verify_key_chain, RotationRecord};
+
+/// What a delegate may do.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capability {
+    /// Lex topics the delegate may use; a trailing `*` matches any suffix
+    /// (e.g., `polydata.<user>.share.7f3a.*`)
+    pub allowed_topics: Vec<String>,
+    pub expires_at_ms: u64,
+    /// Highest classification the delegate may read or write
+    pub classification_ceiling: Classification,
+}
+
+impl Capability {
+    pub fn allows_topic(&self, topic: &str) -> bool {
+        self.allowed_topics.iter().any(|allowed| match allowed.strip_suffix('*') {
+            Some(prefix) => topic.starts_with(prefix),
+            None => topic == allowed,
+        })
+    }
+}
+
+/// Capability statement binding a delegate key to its parent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegationGrant {
+    pub parent_user_id: [u8; 16],
+    /// Delegate's ML-DSA-87 public key
+    pub delegate_public_key: Vec<u8>,
+    pub capability: Capability,
+    /// Parent's ML-DSA-87 signature over `signed_bytes`
+    pub signature: Vec<u8>,
+    /// Rotations from the key `parent_user_id` was derived from to the
+    /// key that signed this grant; empty if the parent never rotated
+    #[serde(default)]
+    pub parent_rotations: Vec<RotationRecord>,
+}
+
+impl DelegationGrant {
+    /// Delegate user_id: SHA3-256 of its public key, truncated to 16 bytes
+    /// (as `derive_user_id` in polykit_identity.fl).
+    pub fn delegate_user_id(&self) -> [u8; 16] {
+        user_id_for_key(&self.delegate_public_key)
+    }
+
+    /// Attach the parent's rotation history, for a parent signing with a
+    /// rotated key.
+    pub fn with_parent_rotations(mut self, rotations: Vec<RotationRecord>) -> Self {
+        self.parent_rotations = rotations;
+        self
+    }
+
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let capability = &self.capability;
+        let mut out = b"poly-delegation-v1".to_vec();
+        let fields = [
+            &self.parent_user_id[..],
+            &self.delegate_public_key,
+            &capability.expires_at_ms.to_be_bytes(),
+            capability.classification_ceiling.as_str().as_bytes(),
+        ];
+        let topics = capability.allowed_topics.iter().map(|t| t.as_bytes());
+        for field in fields.into_iter().chain(topics) {
+            out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            out.extend_from_slice(field);
+        }
+        out
+    }
+
+    /// Whether the grant covers an operation on `topic` at `classification`
+    /// right now. Call `verify_delegation` first.
+    pub fn permits(&self, topic: &str, classification: Classification, clock: &dyn Clock) -> bool {
+        clock.now_ms() < self.capability.expires_at_ms
+            && classification <= self.capability.classification_ceiling
+            && self.capability.allows_topic(topic)
+    }
+}
+
+/// Seed for a delegate's key pair (fed to `derive_keys`), one-way derived
+/// from the parent seed and a per-delegation label so the delegate can't
+/// recover the parent seed or another delegate's.
+pub fn derive_delegate_seed(
+    parent_master_seed: &[u8; 32],
+    label: &[u8],
+) -> Result<Secret<[u8; 32]>> {
+    let mut info = b"poly-delegate-seed-v1".to_vec();
+    info.extend_from_slice(label);
+    crypto::hkdf_sha3_256(parent_master_seed, &info)
+}
+
+/// Parent side: sign a capability statement for a delegate key.
+pub fn issue_delegation(
+    parent_user_id: [u8; 16],
+    parent_secret_key: &[u8],
+    delegate_public_key: &[u8],
+    capability: Capability,
+) -> Result<DelegationGrant> {
+    let mut grant = DelegationGrant {
+        parent_user_id,
+        delegate_public_key: delegate_public_key.to_vec(),
+        capability,
+        signature: Vec::new(),
+        parent_rotations: Vec::new(),
+    };
+    grant.signature = crypto::sign(parent_secret_key, &grant.signed_bytes())?;
+    Ok(grant)
+}
+
+/// Check a grant was signed by the parent and hasn't expired. A rotated
+/// parent's key must chain back to `parent_user_id` through the grant's
+/// `parent_rotations`.
+pub fn verify_delegation(
+    grant: &DelegationGrant,
+    parent_public_key: &[u8],
+    clock: &dyn Clock,
+) -> Result<()> {
+    verify_key_chain(&grant.parent_user_id, parent_public_key, &grant.parent_rotations)
+        .context("delegation parent key does not match parent_user_id")?;
+    if !crypto::verify(parent_public_key, &grant.signed_bytes(), &grant.signature)? {
+        return Err(PolykitError::Crypto("delegation signature invalid".to_string()));
+    }
+    if clock.now_ms() >= grant.capability.expires_at_ms {
+        return Err(PolykitError::Unauthorized {
+            required_role: "unexpired delegation".to_string(),
+            actual_roles: vec!["delegation expired".to_string()],
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::{create_app_context, derive_identity, test_rotation};
+
+    fn capability() -> Capability {
+        Capability {
+            allowed_topics: vec!["polydata.share.*".to_string()],
+            expires_at_ms: 10_000,
+            classification_ceiling: Classification::Internal,
+        }
+    }
+
+    #[test]
+    fn rotated_parent_grant_verifies_with_its_rotations() {
+        let ctx = create_app_context("polydata", "poly-data-v1", "polydata");
+        let parent = derive_identity(&[7; 32], &ctx).unwrap();
+        let (rotated, record) = test_rotation(&parent, 1);
+        let delegate = derive_identity(&[8; 32], &ctx).unwrap();
+        let grant = issue_delegation(
+            parent.user_id,
+            &rotated.signing_secret_key,
+            &delegate.signing_public_key,
+            capability(),
+        )
+        .unwrap();
+        let signer = &rotated.signing_public_key;
+
+        assert!(verify_delegation(&grant, signer, &|| 1_000).is_err());
+        let grant = grant.with_parent_rotations(vec![record]);
+        assert!(verify_delegation(&grant, signer, &|| 1_000).is_ok());
+        assert!(verify_delegation(&grant, signer, &|| 10_000).is_err());
+    }
+
+    #[test]
+    fn grant_from_an_unrelated_key_is_rejected() {
+        let ctx = create_app_context("polydata", "poly-data-v1", "polydata");
+        let parent = derive_identity(&[7; 32], &ctx).unwrap();
+        let other = derive_identity(&[9; 32], &ctx).unwrap();
+        let grant =
+            issue_delegation(parent.user_id, &other.signing_secret_key, b"delegate", capability())
+                .unwrap();
+        let err = verify_delegation(&grant, &other.signing_public_key, &|| 1_000).unwrap_err();
+        assert_eq!(err.context_chain(), ["delegation parent key does not match parent_user_id"]);
+    }
+
+    fn signed_grant() -> (DelegationGrant, Vec<u8>) {
+        let ctx = create_app_context("polydata", "poly-data-v1", "polydata");
+        let parent = derive_identity(&[7; 32], &ctx).unwrap();
+        let delegate_seed = derive_delegate_seed(&[7; 32], b"share-7f3a").unwrap();
+        let delegate = derive_identity(&delegate_seed, &ctx).unwrap();
+        let grant = issue_delegation(
+            parent.user_id,
+            &parent.signing_secret_key,
+            &delegate.signing_public_key,
+            capability(),
+        )
+        .unwrap();
+        (grant, parent.signing_public_key.clone())
+    }
+
+    #[test]
+    fn delegate_keys_are_distinct_from_the_parent() {
+        let a = derive_delegate_seed(&[7; 32], b"share-7f3a").unwrap();
+        let b = derive_delegate_seed(&[7; 32], b"share-9c01").unwrap();
+        assert_ne!(*a, [7; 32]);
+        assert_ne!(*a, *b);
+        let (grant, parent_public_key) = signed_grant();
+        assert_ne!(grant.delegate_public_key, parent_public_key);
+        assert_ne!(grant.delegate_user_id(), grant.parent_user_id);
+    }
+
+    #[test]
+    fn grant_permits_only_its_topics_tier_and_lifetime() {
+        let (grant, parent_public_key) = signed_grant();
+        assert!(verify_delegation(&grant, &parent_public_key, &|| 1_000).is_ok());
+        let now = || 1_000;
+        assert!(grant.permits("polydata.share.doc1", Classification::Internal, &now));
+        assert!(!grant.permits("polydata.share.doc1", Classification::Confidential, &now));
+        assert!(!grant.permits("polydata.files", Classification::Public, &now));
+        assert!(!grant.permits("polydata.share.doc1", Classification::Public, &|| 10_000));
+    }
+
+    #[test]
+    fn widened_capability_breaks_the_signature() {
+        let (mut grant, parent_public_key) = signed_grant();
+        grant.capability.classification_ceiling = Classification::Restricted;
+        assert!(verify_delegation(&grant, &parent_public_key, &|| 1_000).is_err());
+
+        let (mut grant, parent_public_key) = signed_grant();
+        grant.capability.allowed_topics.push("polydata.*".to_string());
+        assert!(verify_delegation(&grant, &parent_public_key, &|| 1_000).is_err());
+    }
+}
+
@@ -0,0 +1,292 @@
+//! Group identities
+//!
+//! A group has one ML-KEM-1024 key pair per epoch. Anyone can encrypt to
+//! the group public key; the secret key is sealed to each member's own
+//! ML-KEM-1024 key and signed by the admin who re-keyed. Adding or
+//! removing a member starts a new epoch with a fresh pair, so a removed
+//! member can't read new traffic and a new member can't read old traffic.
+
+use serde::{Deserialize, Serialize};
+use super::DerivedIdentity;
+use crate::crypto::{self, Rng, SealedMessage};
+use crate::error::{PolykitError, Result};
+use crate::secret::Secret;
+
+/// A member's public identity within a group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupMember {
+    pub user_id: [u8; 16],
+    /// ML-KEM-1024 public key the group secret key is sealed to
+    pub encryption_public_key: Vec<u8>,
+}
+
+/// The group secret key for one epoch, sealed to one member.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedGroupKey {
+    pub user_id: [u8; 16],
+    pub sealed: SealedMessage,
+}
+
+/// Public state of a group, safe to distribute to every member.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupIdentity {
+    pub group_id: [u8; 16],
+    /// Starts at 1 and increases on every re-key
+    pub epoch: u64,
+    /// ML-KEM-1024 group public key for this epoch
+    pub public_key: Vec<u8>,
+    pub members: Vec<GroupMember>,
+    pub wrapped_keys: Vec<WrappedGroupKey>,
+    /// ML-DSA-87 key of the admin that produced this epoch
+    pub admin_public_key: Vec<u8>,
+}
+
+impl GroupIdentity {
+    /// New group at epoch 1. Include the admin in `members` if they should
+    /// be able to read group traffic.
+    pub fn create(
+        group_id: [u8; 16],
+        members: Vec<GroupMember>,
+        admin: &DerivedIdentity,
+        rng: &mut dyn Rng,
+    ) -> Result<Self> {
+        let mut group = GroupIdentity {
+            group_id,
+            epoch: 0,
+            public_key: Vec::new(),
+            members: Vec::new(),
+            wrapped_keys: Vec::new(),
+            admin_public_key: Vec::new(),
+        };
+        for member in members {
+            group.check_new_member(&member)?;
+            group.members.push(member);
+        }
+        group.rekey(admin, rng)?;
+        Ok(group)
+    }
+
+    pub fn is_member(&self, user_id: &[u8; 16]) -> bool {
+        self.members.iter().any(|m| m.user_id == *user_id)
+    }
+
+    /// Add a member and re-key.
+    pub fn add_member(
+        &mut self,
+        member: GroupMember,
+        admin: &DerivedIdentity,
+        rng: &mut dyn Rng,
+    ) -> Result<()> {
+        self.check_new_member(&member)?;
+        self.members.push(member);
+        self.rekey(admin, rng)
+    }
+
+    /// Remove a member and re-key. The last member can't be removed.
+    pub fn remove_member(
+        &mut self,
+        user_id: &[u8; 16],
+        admin: &DerivedIdentity,
+        rng: &mut dyn Rng,
+    ) -> Result<()> {
+        if !self.is_member(user_id) {
+            return Err(invalid("not a member of this group".to_string()));
+        }
+        if self.members.len() == 1 {
+            return Err(invalid("cannot remove the last member".to_string()));
+        }
+        self.members.retain(|m| m.user_id != *user_id);
+        self.rekey(admin, rng)
+    }
+
+    /// Start a new epoch: fresh group key pair, sealed to every current
+    /// member. Also used on its own, e.g. after a member's device is lost.
+    pub fn rekey(&mut self, admin: &DerivedIdentity, rng: &mut dyn Rng) -> Result<()> {
+        if self.members.is_empty() {
+            return Err(invalid("a group needs at least one member".to_string()));
+        }
+        let epoch = self.epoch + 1;
+        let (public_key, secret_key) = crypto::kem_keypair(rng);
+        let wrapped_keys = self
+            .members
+            .iter()
+            .map(|member| {
+                let sealed = crypto::seal(
+                    &member.encryption_public_key,
+                    &admin.signing_secret_key,
+                    &admin.signing_public_key,
+                    &secret_key,
+                    &group_aad(&self.group_id, epoch, &public_key, &member.user_id),
+                    rng,
+                )?;
+                Ok(WrappedGroupKey { user_id: member.user_id, sealed })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.epoch = epoch;
+        self.public_key = public_key;
+        self.wrapped_keys = wrapped_keys;
+        self.admin_public_key = admin.signing_public_key.clone();
+        Ok(())
+    }
+
+    /// Recover this epoch's group secret key as `member`. `admin_public_key`
+    /// is the admin key the member already trusts; a group re-keyed by
+    /// anyone else is rejected.
+    pub fn unwrap_key(
+        &self,
+        member: &DerivedIdentity,
+        admin_public_key: &[u8],
+    ) -> Result<Secret<Vec<u8>>> {
+        if self.admin_public_key != admin_public_key {
+            return Err(invalid("group was re-keyed by an untrusted admin".to_string()));
+        }
+        let wrapped = self
+            .wrapped_keys
+            .iter()
+            .find(|w| w.user_id == member.user_id)
+            .ok_or_else(|| {
+                invalid(format!("no group key for this member at epoch {}", self.epoch))
+            })?;
+        if wrapped.sealed.sender_public_key != admin_public_key {
+            return Err(invalid("group key not sealed by the admin".to_string()));
+        }
+        let aad = group_aad(&self.group_id, self.epoch, &self.public_key, &member.user_id);
+        let secret_key = crypto::open(&member.encryption_secret_key, &wrapped.sealed, &aad)?;
+        Ok(Secret::new(secret_key))
+    }
+
+    fn check_new_member(&self, member: &GroupMember) -> Result<()> {
+        if self.is_member(&member.user_id) {
+            return Err(invalid("already a member of this group".to_string()));
+        }
+        if member.encryption_public_key.len() != crypto::MLKEM1024_PUBLIC_KEY_LEN {
+            return Err(invalid(format!(
+                "member key must be {} bytes, got {}",
+                crypto::MLKEM1024_PUBLIC_KEY_LEN,
+                member.encryption_public_key.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Binds a wrapped key to its group, epoch, group public key, and member,
+/// so it can't be replayed into another epoch or to another member.
+fn group_aad(group_id: &[u8; 16], epoch: u64, public_key: &[u8], user_id: &[u8; 16]) -> Vec<u8> {
+    let mut out = b"poly-group-key-v1".to_vec();
+    for field in [&group_id[..], &epoch.to_be_bytes(), public_key, user_id] {
+        out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        out.extend_from_slice(field);
+    }
+    out
+}
+
+fn invalid(message: String) -> PolykitError {
+    PolykitError::IdentityDerivation(format!("group: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::SeededRng;
+    use crate::identity::{create_app_context, derive_identity};
+
+    fn identity(seed: u8) -> DerivedIdentity {
+        let ctx = create_app_context("polymessenger", "poly-messenger-v1", "polymessenger");
+        derive_identity(&[seed; 32], &ctx).unwrap()
+    }
+
+    fn member(identity: &DerivedIdentity) -> GroupMember {
+        GroupMember {
+            user_id: identity.user_id,
+            encryption_public_key: identity.encryption_public_key.clone(),
+        }
+    }
+
+    /// Whether `secret_key` opens a message sealed to `group`'s public key.
+    fn opens_group_traffic(group: &GroupIdentity, secret_key: &[u8], rng: &mut SeededRng) -> bool {
+        let sender = identity(99);
+        let sealed = crypto::seal(
+            &group.public_key,
+            &sender.signing_secret_key,
+            &sender.signing_public_key,
+            b"hello group",
+            b"",
+            rng,
+        )
+        .unwrap();
+        crypto::open(secret_key, &sealed, b"").is_ok()
+    }
+
+    #[test]
+    fn members_unwrap_the_group_key() {
+        let mut rng = SeededRng::seed_from_u64(1);
+        let (admin, bob) = (identity(1), identity(2));
+        let group =
+            GroupIdentity::create([5; 16], vec![member(&admin), member(&bob)], &admin, &mut rng)
+                .unwrap();
+        assert_eq!(group.epoch, 1);
+        for reader in [&admin, &bob] {
+            let key = group.unwrap_key(reader, &admin.signing_public_key).unwrap();
+            assert!(opens_group_traffic(&group, &key, &mut rng));
+        }
+        assert!(group.unwrap_key(&identity(3), &admin.signing_public_key).is_err());
+    }
+
+    #[test]
+    fn membership_changes_rekey() {
+        let mut rng = SeededRng::seed_from_u64(2);
+        let (admin, bob, carol) = (identity(1), identity(2), identity(3));
+        let mut group =
+            GroupIdentity::create([5; 16], vec![member(&admin), member(&bob)], &admin, &mut rng)
+                .unwrap();
+        let bob_epoch_1 = group.unwrap_key(&bob, &admin.signing_public_key).unwrap();
+
+        group.add_member(member(&carol), &admin, &mut rng).unwrap();
+        assert_eq!(group.epoch, 2);
+        let carol_key = group.unwrap_key(&carol, &admin.signing_public_key).unwrap();
+        assert!(opens_group_traffic(&group, &carol_key, &mut rng));
+        assert!(!opens_group_traffic(&group, &bob_epoch_1, &mut rng));
+
+        group.remove_member(&bob.user_id, &admin, &mut rng).unwrap();
+        assert_eq!(group.epoch, 3);
+        assert!(!group.is_member(&bob.user_id));
+        assert!(group.unwrap_key(&bob, &admin.signing_public_key).is_err());
+        assert!(!opens_group_traffic(&group, &carol_key, &mut rng));
+    }
+
+    #[test]
+    fn invalid_membership_changes_are_rejected() {
+        let mut rng = SeededRng::seed_from_u64(3);
+        let admin = identity(1);
+        let mut group =
+            GroupIdentity::create([5; 16], vec![member(&admin)], &admin, &mut rng).unwrap();
+        assert!(group.add_member(member(&admin), &admin, &mut rng).is_err());
+        let short = GroupMember { user_id: [9; 16], encryption_public_key: vec![0; 8] };
+        assert!(group.add_member(short, &admin, &mut rng).is_err());
+        assert!(group.remove_member(&[9; 16], &admin, &mut rng).is_err());
+        assert!(group.remove_member(&admin.user_id, &admin, &mut rng).is_err());
+        assert_eq!(group.epoch, 1);
+        assert!(GroupIdentity::create([6; 16], Vec::new(), &admin, &mut rng).is_err());
+    }
+
+    #[test]
+    fn untrusted_admin_and_replayed_keys_are_rejected() {
+        let mut rng = SeededRng::seed_from_u64(4);
+        let (admin, bob, mallory) = (identity(1), identity(2), identity(4));
+        let mut group =
+            GroupIdentity::create([5; 16], vec![member(&admin), member(&bob)], &admin, &mut rng)
+                .unwrap();
+        let old_wrapped = group.wrapped_keys.clone();
+
+        let mut hijacked = group.clone();
+        hijacked.rekey(&mallory, &mut rng).unwrap();
+        assert!(hijacked.unwrap_key(&bob, &admin.signing_public_key).is_err());
+
+        // An old epoch's wrapped keys don't open under the new epoch
+        group.rekey(&admin, &mut rng).unwrap();
+        group.wrapped_keys = old_wrapped;
+        assert!(group.unwrap_key(&bob, &admin.signing_public_key).is_err());
+    }
+}
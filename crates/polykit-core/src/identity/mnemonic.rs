@@ -0,0 +1,174 @@
+//! Mnemonic encoding for master seeds
+//!
+//! Renders the 32-byte SPARK seed as 24 words from the BIP-39 English
+//! wordlist: 256 seed bits plus an 8-bit checksum, 11 bits per word. The
+//! checksum is the first byte of SHA3-256 rather than BIP-39's SHA-256,
+//! so a phrase can't be mistaken for (or imported as) a wallet seed.
+
+use crate::crypto;
+use crate::error::{PolykitError, Result};
+use crate::secret::Secret;
+
+/// Words in a master seed phrase.
+pub const WORD_COUNT: usize = 24;
+
+const WORDLIST: &str = include_str!("bip39_english.txt");
+const BITS_PER_WORD: usize = 11;
+
+fn wordlist() -> Vec<&'static str> {
+    WORDLIST.lines().collect()
+}
+
+/// Encode a master seed as 24 space-separated words.
+pub fn encode(master_seed: &[u8; 32]) -> Secret<String> {
+    let words = wordlist();
+    let checksum = checksum(master_seed);
+    let mut phrase = Secret::new(String::new());
+
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &byte in master_seed.iter().chain(std::iter::once(&checksum)) {
+        acc = (acc << 8) | byte as u32;
+        bits += 8;
+        if bits >= BITS_PER_WORD {
+            bits -= BITS_PER_WORD;
+            let index = (acc >> bits) as usize & 0x7ff;
+            if !phrase.is_empty() {
+                phrase.push(' ');
+            }
+            phrase.push_str(words[index]);
+        }
+    }
+    phrase
+}
+
+/// Decode a 24-word phrase back to the master seed. Case and extra
+/// whitespace are ignored, and any word may be shortened to its first
+/// four letters (unique in the wordlist). Errors name the offending word
+/// by position only, so the phrase never reaches logs.
+pub fn decode(phrase: &str) -> Result<Secret<[u8; 32]>> {
+    let words = wordlist();
+    let lowered = Secret::new(phrase.to_lowercase());
+    let input: Vec<&str> = lowered.split_whitespace().collect();
+    if input.len() != WORD_COUNT {
+        return Err(invalid(format!("expected {} words, got {}", WORD_COUNT, input.len())));
+    }
+
+    let mut bytes = Secret::new(Vec::with_capacity(33));
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for (position, word) in input.iter().enumerate() {
+        let index = lookup(&words, word)
+            .ok_or_else(|| invalid(format!("word {} is not in the wordlist", position + 1)))?;
+        acc = (acc << BITS_PER_WORD) | index as u32;
+        bits += BITS_PER_WORD;
+        while bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+
+    let mut seed = Secret::new([0u8; 32]);
+    seed.copy_from_slice(&bytes[..32]);
+    if checksum(&seed) != bytes[32] {
+        return Err(invalid("checksum mismatch: check the words and their order".to_string()));
+    }
+    Ok(seed)
+}
+
+/// Whether `phrase` decodes to a seed with a valid checksum.
+pub fn validate(phrase: &str) -> bool {
+    decode(phrase).is_ok()
+}
+
+/// Exact match, or a unique prefix of at least four letters.
+fn lookup(words: &[&str], word: &str) -> Option<usize> {
+    match words.binary_search(&word) {
+        Ok(index) => Some(index),
+        Err(index) if word.len() >= 4 => {
+            words.get(index).filter(|w| w.starts_with(word)).map(|_| index)
+        }
+        Err(_) => None,
+    }
+}
+
+fn checksum(master_seed: &[u8; 32]) -> u8 {
+    crypto::sha3_256(master_seed)[0]
+}
+
+fn invalid(message: String) -> PolykitError {
+    PolykitError::IdentityDerivation(format!("mnemonic: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: [u8; 32] = [
+        0x3a, 0x91, 0x0c, 0xe7, 0x55, 0x12, 0xfe, 0x08, 0x6b, 0xd4, 0x27, 0x9e, 0x40, 0xb3, 0x1f,
+        0xc8, 0x72, 0x05, 0xaa, 0x39, 0xe1, 0x5d, 0x86, 0x0f, 0xcb, 0x64, 0x17, 0xf2, 0x98, 0x2e,
+        0x4b, 0xd0,
+    ];
+
+    #[test]
+    fn wordlist_is_sorted_bip39_english() {
+        let words = wordlist();
+        assert_eq!(words.len(), 1 << BITS_PER_WORD);
+        assert!(words.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn phrase_round_trips() {
+        for seed in [SEED, [0; 32], [0xff; 32]] {
+            let phrase = encode(&seed);
+            assert_eq!(phrase.split(' ').count(), WORD_COUNT);
+            assert_eq!(*decode(&phrase).unwrap(), seed);
+        }
+    }
+
+    #[test]
+    fn case_whitespace_and_prefixes_are_accepted() {
+        let phrase = encode(&SEED);
+        let loose: Vec<String> = phrase
+            .split(' ')
+            .map(|word| word.chars().take(4).collect::<String>().to_uppercase())
+            .collect();
+        assert_eq!(*decode(&format!("  {}\n", loose.join("   "))).unwrap(), SEED);
+    }
+
+    #[test]
+    fn checksum_mismatch_is_rejected() {
+        // The last word's low 8 bits are the checksum; flipping one leaves
+        // the seed bits intact and the checksum wrong
+        let phrase = encode(&SEED);
+        let list = wordlist();
+        let mut words: Vec<&str> = phrase.split(' ').collect();
+        let last = lookup(&list, words[WORD_COUNT - 1]).unwrap();
+        words[WORD_COUNT - 1] = list[last ^ 1];
+        let tampered = words.join(" ");
+        assert!(!validate(&tampered));
+        let err = decode(&tampered).unwrap_err().to_string();
+        assert!(err.contains("checksum mismatch"), "{}", err);
+    }
+
+    #[test]
+    fn errors_name_positions_not_words() {
+        let phrase = encode(&SEED);
+        let mut words: Vec<&str> = phrase.split(' ').collect();
+        words[2] = "notaword";
+        let err = decode(&words.join(" ")).unwrap_err().to_string();
+        assert!(err.contains("word 3 is not in the wordlist"), "{}", err);
+        assert!(!err.contains("notaword"));
+
+        let err = decode(&words[..12].join(" ")).unwrap_err().to_string();
+        assert!(err.contains("expected 24 words, got 12"), "{}", err);
+    }
+
+    #[test]
+    fn short_or_ambiguous_prefixes_are_rejected() {
+        let words = wordlist();
+        assert_eq!(lookup(&words, "aba"), None);
+        assert_eq!(lookup(&words, "aban"), Some(0));
+        assert_eq!(lookup(&words, "zzzz"), None);
+    }
+}
@@ -0,0 +1,158 @@
+//! Per-recipient pseudonymous identities
+//!
+//! Each recipient (a mailbox, a workspace) sees its own key pair and
+//! user_id, derived from the master seed with the recipient as HKDF salt.
+//! Without the seed, two pseudonyms can't be linked to each other or to
+//! the canonical identity. The resolver keeps the mapping back locally.
+
+use serde::{Deserialize, Serialize};
+use super::{derive_identity, AppContext, DerivedIdentity};
+use crate::crypto;
+use crate::error::{PolykitError, Result};
+use crate::secret::Secret;
+
+/// Seed for the pseudonym shown to `recipient` in `ctx`. Callers should
+/// normalize the recipient first (e.g., lowercase an email address), or
+/// two spellings yield two pseudonyms.
+pub fn derive_pseudonym_seed(
+    master_seed: &[u8; 32],
+    ctx: &AppContext,
+    recipient: &str,
+) -> Result<Secret<[u8; 32]>> {
+    if recipient.is_empty() {
+        return Err(PolykitError::IdentityDerivation("pseudonym recipient is empty".to_string()));
+    }
+    let mut info = b"poly-pseudonym-v1".to_vec();
+    for field in [ctx.hkdf_context.as_bytes(), recipient.as_bytes()] {
+        info.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        info.extend_from_slice(field);
+    }
+    crypto::hkdf_sha3_256(master_seed, &info)
+}
+
+/// Key pairs and user_id for the pseudonym shown to `recipient`.
+pub fn derive_pseudonym(
+    master_seed: &[u8; 32],
+    ctx: &AppContext,
+    recipient: &str,
+) -> Result<DerivedIdentity> {
+    let seed = derive_pseudonym_seed(master_seed, ctx, recipient)?;
+    derive_identity(&seed, ctx)
+}
+
+/// One pseudonym the local user has handed out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PseudonymEntry {
+    pub user_id: [u8; 16],
+    pub recipient: String,
+}
+
+/// Local map from pseudonym user_ids back to the canonical identity.
+/// Never published: it is exactly the linkage pseudonyms hide.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PseudonymResolver {
+    pub canonical_user_id: [u8; 16],
+    entries: Vec<PseudonymEntry>,
+}
+
+impl PseudonymResolver {
+    pub fn new(canonical_user_id: [u8; 16]) -> Self {
+        Self { canonical_user_id, entries: Vec::new() }
+    }
+
+    /// Derive the pseudonym for `recipient` and remember it.
+    pub fn derive(
+        &mut self,
+        master_seed: &[u8; 32],
+        ctx: &AppContext,
+        recipient: &str,
+    ) -> Result<DerivedIdentity> {
+        let identity = derive_pseudonym(master_seed, ctx, recipient)?;
+        self.record(identity.user_id, recipient);
+        Ok(identity)
+    }
+
+    /// Remember a pseudonym derived elsewhere (e.g., on another device).
+    pub fn record(&mut self, user_id: [u8; 16], recipient: &str) {
+        if !self.entries.iter().any(|e| e.user_id == user_id) {
+            self.entries.push(PseudonymEntry { user_id, recipient: recipient.to_string() });
+        }
+    }
+
+    /// The recipient a pseudonym was made for, or `None` if `user_id` isn't
+    /// one of ours.
+    pub fn resolve(&self, user_id: &[u8; 16]) -> Option<&str> {
+        self.entries.iter().find(|e| e.user_id == *user_id).map(|e| e.recipient.as_str())
+    }
+
+    /// Pseudonym user_id already used with `recipient`.
+    pub fn pseudonym_for(&self, recipient: &str) -> Option<[u8; 16]> {
+        self.entries.iter().find(|e| e.recipient == recipient).map(|e| e.user_id)
+    }
+
+    /// Whether `user_id` is the canonical identity or one of its pseudonyms.
+    pub fn is_own(&self, user_id: &[u8; 16]) -> bool {
+        *user_id == self.canonical_user_id || self.resolve(user_id).is_some()
+    }
+
+    pub fn entries(&self) -> &[PseudonymEntry] {
+        &self.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::create_app_context;
+
+    const SEED: [u8; 32] = [7; 32];
+
+    fn ctx() -> AppContext {
+        create_app_context("polymail", "poly-mail-v1", "polymail")
+    }
+
+    #[test]
+    fn pseudonyms_are_stable_and_distinct_per_recipient() {
+        let canonical = derive_identity(&SEED, &ctx()).unwrap();
+        let alice = derive_pseudonym(&SEED, &ctx(), "alice@example.com").unwrap();
+        let bob = derive_pseudonym(&SEED, &ctx(), "bob@example.com").unwrap();
+        assert_eq!(
+            alice.user_id,
+            derive_pseudonym(&SEED, &ctx(), "alice@example.com").unwrap().user_id
+        );
+        for (a, b) in [(&alice, &bob), (&alice, &canonical), (&bob, &canonical)] {
+            assert_ne!(a.user_id, b.user_id);
+            assert_ne!(a.signing_public_key, b.signing_public_key);
+        }
+    }
+
+    #[test]
+    fn pseudonyms_are_scoped_to_seed_and_context() {
+        let alice = derive_pseudonym(&SEED, &ctx(), "alice@example.com").unwrap();
+        let other_seed = derive_pseudonym(&[8; 32], &ctx(), "alice@example.com").unwrap();
+        let other_app = create_app_context("polydata", "poly-data-v1", "polydata");
+        let other_ctx = derive_pseudonym(&SEED, &other_app, "alice@example.com").unwrap();
+        assert_ne!(alice.user_id, other_seed.user_id);
+        assert_ne!(alice.user_id, other_ctx.user_id);
+        assert!(derive_pseudonym(&SEED, &ctx(), "").is_err());
+    }
+
+    #[test]
+    fn resolver_maps_pseudonyms_back_locally() {
+        let canonical = derive_identity(&SEED, &ctx()).unwrap();
+        let mut resolver = PseudonymResolver::new(canonical.user_id);
+        let alice = resolver.derive(&SEED, &ctx(), "alice@example.com").unwrap();
+        resolver.derive(&SEED, &ctx(), "alice@example.com").unwrap();
+        assert_eq!(resolver.entries().len(), 1);
+
+        assert_eq!(resolver.resolve(&alice.user_id), Some("alice@example.com"));
+        assert_eq!(resolver.pseudonym_for("alice@example.com"), Some(alice.user_id));
+        assert!(resolver.is_own(&alice.user_id));
+        assert!(resolver.is_own(&canonical.user_id));
+
+        let stranger = derive_identity(&[8; 32], &ctx()).unwrap();
+        assert_eq!(resolver.resolve(&stranger.user_id), None);
+        assert!(!resolver.is_own(&stranger.user_id));
+        assert_eq!(resolver.pseudonym_for("bob@example.com"), None);
+    }
+}
@@ -0,0 +1,325 @@
+//! Social recovery of the SPARK master seed
+//!
+//! The seed is split into k-of-n Shamir shares over GF(256), and each
+//! share is sealed to one recovery contact's ML-KEM-1024 key. Any k
+//! contacts can return their shares to rebuild the seed; fewer learn
+//! nothing about it. Shares carry no function of the seed: a rebuilt seed
+//! is checked by deriving the identity it belongs to and comparing
+//! user_ids.
+
+use serde::{Deserialize, Serialize};
+use crate::crypto::{self, Rng, SealedMessage};
+use crate::error::{PolykitError, Result};
+use crate::secret::Secret;
+use super::{derive_identity, AppContext};
+
+/// Serialized share format version.
+pub const SHARE_VERSION: u8 = 1;
+/// Serialized share length: version, threshold, index, value, split id, checksum.
+pub const SHARE_LEN: usize = 3 + 32 + 8 + 4;
+
+/// One Shamir share of a master seed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryShare {
+    /// Shares needed to reconstruct
+    pub threshold: u8,
+    /// x-coordinate, 1..=n
+    pub index: u8,
+    pub value: [u8; 32],
+    /// Random tag shared by the shares of one split, so mixed sets are
+    /// caught before interpolating. Independent of the seed.
+    pub split_id: [u8; 8],
+}
+
+impl RecoveryShare {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![SHARE_VERSION, self.threshold, self.index];
+        out.extend_from_slice(&self.value);
+        out.extend_from_slice(&self.split_id);
+        let checksum = crypto::sha3_256(&out);
+        out.extend_from_slice(&checksum[..4]);
+        out
+    }
+
+    /// Parse and validate a serialized share (length, version, checksum,
+    /// index and threshold ranges).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != SHARE_LEN {
+            return Err(invalid(format!("share must be {} bytes, got {}", SHARE_LEN, bytes.len())));
+        }
+        let (body, checksum) = bytes.split_at(SHARE_LEN - 4);
+        if crypto::sha3_256(body)[..4] != *checksum {
+            return Err(invalid("share checksum mismatch".to_string()));
+        }
+        if body[0] != SHARE_VERSION {
+            return Err(invalid(format!("unsupported share version {}", body[0])));
+        }
+        let share = RecoveryShare {
+            threshold: body[1],
+            index: body[2],
+            value: body[3..35].try_into().expect("sliced to 32 bytes"),
+            split_id: body[35..43].try_into().expect("sliced to 8 bytes"),
+        };
+        if share.threshold < 2 || share.index == 0 {
+            return Err(invalid("share has an invalid threshold or index".to_string()));
+        }
+        Ok(share)
+    }
+}
+
+/// Split `seed` into `n` shares, any `threshold` of which reconstruct it.
+pub fn split(
+    seed: &[u8; 32],
+    threshold: u8,
+    n: u8,
+    rng: &mut dyn Rng,
+) -> Result<Vec<RecoveryShare>> {
+    if threshold < 2 || threshold > n {
+        return Err(invalid(format!("need 2 <= threshold <= n, got {} of {}", threshold, n)));
+    }
+    let mut split_id = [0u8; 8];
+    rng.fill_bytes(&mut split_id);
+    // One random polynomial per seed byte, with the byte as its constant term
+    let mut coefficients = vec![Secret::new([0u8; 32]); threshold as usize - 1];
+    for c in coefficients.iter_mut() {
+        rng.fill_bytes(&mut c[..]);
+    }
+
+    Ok((1..=n)
+        .map(|x| {
+            let mut value = [0u8; 32];
+            for (i, byte) in value.iter_mut().enumerate() {
+                // Horner's rule, highest coefficient first
+                let mut y = 0u8;
+                for c in coefficients.iter().rev() {
+                    y = gf_mul(y, x) ^ c[i];
+                }
+                *byte = gf_mul(y, x) ^ seed[i];
+            }
+            RecoveryShare { threshold, index: x, value, split_id }
+        })
+        .collect())
+}
+
+/// Rebuild the seed from at least `threshold` shares and check it derives
+/// the identity with `user_id` in `ctx` (the one being recovered). Rejects
+/// mixed share sets, duplicate indices, too few shares, and a seed that
+/// derives a different user_id, e.g. from a corrupted share.
+pub fn reconstruct(
+    shares: &[RecoveryShare],
+    ctx: &AppContext,
+    user_id: &[u8; 16],
+) -> Result<Secret<[u8; 32]>> {
+    let first = shares.first().ok_or_else(|| invalid("no shares".to_string()))?;
+    if shares.iter().any(|s| s.threshold != first.threshold || s.split_id != first.split_id) {
+        return Err(invalid("shares are from different splits".to_string()));
+    }
+    let mut indices: Vec<u8> = shares.iter().map(|s| s.index).collect();
+    indices.sort_unstable();
+    indices.dedup();
+    if indices.len() != shares.len() || indices[0] == 0 {
+        return Err(invalid("duplicate or zero share index".to_string()));
+    }
+    if shares.len() < first.threshold as usize {
+        return Err(invalid(format!("need {} shares, got {}", first.threshold, shares.len())));
+    }
+
+    // Lagrange interpolation at x = 0 over the first `threshold` shares
+    let used = &shares[..first.threshold as usize];
+    let mut seed = Secret::new([0u8; 32]);
+    for (j, share) in used.iter().enumerate() {
+        let mut basis = 1u8;
+        for (m, other) in used.iter().enumerate() {
+            if m != j {
+                basis = gf_mul(basis, gf_mul(other.index, gf_inv(other.index ^ share.index)));
+            }
+        }
+        for (byte, value) in seed.iter_mut().zip(share.value.iter()) {
+            *byte ^= gf_mul(basis, *value);
+        }
+    }
+
+    if derive_identity(&seed, ctx)?.user_id != *user_id {
+        return Err(invalid("reconstructed seed does not derive the expected user_id".to_string()));
+    }
+    Ok(seed)
+}
+
+/// A share sealed to one recovery contact.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedShare {
+    pub index: u8,
+    pub sealed: SealedMessage,
+}
+
+/// Seal a share to a contact's ML-KEM-1024 public key, signed by the
+/// owner's ML-DSA-87 key.
+pub fn encrypt_share(
+    share: &RecoveryShare,
+    contact_public_key: &[u8],
+    owner_secret_key: &[u8],
+    owner_public_key: &[u8],
+    rng: &mut dyn Rng,
+) -> Result<EncryptedShare> {
+    let sealed = crypto::seal(
+        contact_public_key,
+        owner_secret_key,
+        owner_public_key,
+        &share.to_bytes(),
+        &share_aad(share.index),
+        rng,
+    )?;
+    Ok(EncryptedShare { index: share.index, sealed })
+}
+
+/// Open a share with the contact's secret key, checking it was sealed by
+/// the expected owner.
+pub fn decrypt_share(
+    encrypted: &EncryptedShare,
+    contact_secret_key: &[u8],
+    owner_public_key: &[u8],
+) -> Result<RecoveryShare> {
+    if encrypted.sealed.sender_public_key != owner_public_key {
+        return Err(invalid("share not sealed by the expected owner".to_string()));
+    }
+    let bytes = crypto::open(contact_secret_key, &encrypted.sealed, &share_aad(encrypted.index))?;
+    let share = RecoveryShare::from_bytes(&bytes)?;
+    if share.index != encrypted.index {
+        return Err(invalid("share index does not match its envelope".to_string()));
+    }
+    Ok(share)
+}
+
+fn share_aad(index: u8) -> Vec<u8> {
+    let mut aad = b"poly-recovery-share-v1".to_vec();
+    aad.push(index);
+    aad
+}
+
+fn invalid(message: String) -> PolykitError {
+    PolykitError::IdentityDerivation(format!("recovery: {}", message))
+}
+
+/// Multiplication in GF(2^8) with the AES polynomial x^8 + x^4 + x^3 + x + 1.
+/// Runs on secret share bytes, so it is branch-free: a fixed eight rounds,
+/// with masks in place of the bit tests.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse in GF(2^8): a^254, by a fixed run of `gf_mul`s.
+/// Callers never pass 0.
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    for _ in 0..254 {
+        result = gf_mul(result, a);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::SeededRng;
+    use crate::identity::create_app_context;
+
+    const SEED: [u8; 32] = [7; 32];
+
+    fn ctx() -> AppContext {
+        create_app_context("polydata", "poly-data-v1", "polydata")
+    }
+
+    fn user_id() -> [u8; 16] {
+        derive_identity(&SEED, &ctx()).unwrap().user_id
+    }
+
+    fn shares(seed: u64) -> Vec<RecoveryShare> {
+        split(&SEED, 3, 5, &mut SeededRng::seed_from_u64(seed)).unwrap()
+    }
+
+    #[test]
+    fn any_threshold_subset_reconstructs() {
+        let shares = shares(1);
+        for subset in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let picked: Vec<_> = subset.iter().map(|&i| shares[i].clone()).collect();
+            assert_eq!(*reconstruct(&picked, &ctx(), &user_id()).unwrap(), SEED);
+        }
+    }
+
+    #[test]
+    fn too_few_mixed_or_duplicate_shares_are_rejected() {
+        let first = shares(1);
+        let second = shares(2);
+        assert!(reconstruct(&first[..2], &ctx(), &user_id()).is_err());
+        let mixed = [first[0].clone(), first[1].clone(), second[2].clone()];
+        assert!(reconstruct(&mixed, &ctx(), &user_id()).is_err());
+        let duplicate = [first[0].clone(), first[0].clone(), first[1].clone()];
+        assert!(reconstruct(&duplicate, &ctx(), &user_id()).is_err());
+    }
+
+    #[test]
+    fn corrupted_share_fails_the_user_id_check() {
+        let mut shares = shares(1);
+        shares[1].value[0] ^= 1;
+        let err = reconstruct(&shares[..3], &ctx(), &user_id()).unwrap_err();
+        assert!(err.to_string().contains("expected user_id"));
+    }
+
+    #[test]
+    fn shares_carry_nothing_derived_from_the_seed() {
+        // Same seed, different randomness: nothing in common but the shape
+        let a = shares(1);
+        let b = shares(2);
+        assert_ne!(a[0].split_id, b[0].split_id);
+        assert_ne!(a[0].value, b[0].value);
+        let other_seed = split(&[8; 32], 3, 5, &mut SeededRng::seed_from_u64(1)).unwrap();
+        assert_eq!(a[0].split_id, other_seed[0].split_id);
+    }
+
+    #[test]
+    fn serialized_share_round_trips_and_is_validated() {
+        let share = shares(1).remove(0);
+        let bytes = share.to_bytes();
+        assert_eq!(bytes.len(), SHARE_LEN);
+        assert_eq!(RecoveryShare::from_bytes(&bytes).unwrap(), share);
+
+        let mut flipped = bytes.clone();
+        flipped[10] ^= 1;
+        assert!(RecoveryShare::from_bytes(&flipped).is_err());
+        assert!(RecoveryShare::from_bytes(&bytes[..SHARE_LEN - 1]).is_err());
+    }
+
+    #[test]
+    fn sealed_share_opens_only_for_its_contact_and_owner() {
+        let mut rng = SeededRng::seed_from_u64(3);
+        let (owner_pk, owner_sk) = crypto::signing_keypair(&mut rng);
+        let (contact_pk, contact_sk) = crypto::kem_keypair(&mut rng);
+        let share = shares(1).remove(2);
+        let sealed = encrypt_share(&share, &contact_pk, &owner_sk, &owner_pk, &mut rng).unwrap();
+
+        assert_eq!(decrypt_share(&sealed, &contact_sk, &owner_pk).unwrap(), share);
+        let (other_pk, _) = crypto::signing_keypair(&mut rng);
+        assert!(decrypt_share(&sealed, &contact_sk, &other_pk).is_err());
+        let relabeled = EncryptedShare { index: 1, ..sealed };
+        assert!(decrypt_share(&relabeled, &contact_sk, &owner_pk).is_err());
+    }
+
+    #[test]
+    fn field_arithmetic_matches_aes() {
+        // FIPS-197 section 4.2 example
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1, "inverse of {:#04x}", a);
+            assert_eq!(gf_mul(a, 0), 0);
+            assert_eq!(gf_mul(a, 1), a);
+        }
+    }
+}
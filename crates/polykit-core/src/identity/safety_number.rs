@@ -0,0 +1,128 @@
+//! Contact verification safety numbers
+//!
+//! Two users compare a 60-digit number (read aloud or scanned as a QR code)
+//! derived from both ML-DSA-87 public keys. A match means neither side is
+//! talking to a substituted key. Each key contributes 30 digits, and the
+//! halves are ordered so both users see the same number.
+
+use serde::{Deserialize, Serialize};
+use crate::crypto;
+use crate::error::{PolykitError, Result};
+
+/// Digits contributed by each public key.
+pub const FINGERPRINT_DIGITS: usize = 30;
+
+/// Digits per display group.
+pub const GROUP_LEN: usize = 5;
+
+/// Shared safety number for a pair of contacts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SafetyNumber {
+    digits: String,
+}
+
+impl SafetyNumber {
+    /// Compute from the local and remote ML-DSA-87 public keys. The
+    /// argument order doesn't matter.
+    pub fn compute(local_public_key: &[u8], remote_public_key: &[u8]) -> Result<Self> {
+        let local = fingerprint(local_public_key)?;
+        let remote = fingerprint(remote_public_key)?;
+        let digits = if local <= remote { local + &remote } else { remote + &local };
+        Ok(Self { digits })
+    }
+
+    /// All 60 digits, ungrouped.
+    pub fn as_str(&self) -> &str {
+        &self.digits
+    }
+
+    /// Five-digit groups in display order.
+    pub fn groups(&self) -> Vec<&str> {
+        (0..self.digits.len())
+            .step_by(GROUP_LEN)
+            .map(|i| &self.digits[i..i + GROUP_LEN])
+            .collect()
+    }
+
+    /// Compare against a number the user typed or scanned. Whitespace is
+    /// ignored, so grouped and ungrouped input both work.
+    pub fn matches(&self, candidate: &str) -> bool {
+        let candidate: String = candidate.chars().filter(|c| !c.is_whitespace()).collect();
+        candidate == self.digits
+    }
+}
+
+impl std::fmt::Display for SafetyNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.groups().join(" "))
+    }
+}
+
+/// Check a candidate number against the one computed for these keys.
+pub fn verify_safety_number(
+    local_public_key: &[u8],
+    remote_public_key: &[u8],
+    candidate: &str,
+) -> Result<bool> {
+    Ok(SafetyNumber::compute(local_public_key, remote_public_key)?.matches(candidate))
+}
+
+/// One key's 30 digits: six 5-byte chunks of its SHA3-256 fingerprint,
+/// each reduced to five decimal digits.
+fn fingerprint(public_key: &[u8]) -> Result<String> {
+    if public_key.len() != crypto::MLDSA87_PUBLIC_KEY_LEN {
+        return Err(PolykitError::Crypto(format!(
+            "safety number needs an ML-DSA-87 public key ({} bytes), got {} bytes",
+            crypto::MLDSA87_PUBLIC_KEY_LEN,
+            public_key.len()
+        )));
+    }
+    let mut input = b"poly-safety-number-v1".to_vec();
+    input.extend_from_slice(&(public_key.len() as u32).to_be_bytes());
+    input.extend_from_slice(public_key);
+    let hash = crypto::sha3_256(&input);
+
+    Ok(hash
+        .chunks_exact(5)
+        .take(FINGERPRINT_DIGITS / GROUP_LEN)
+        .map(|chunk| {
+            let value = chunk.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+            format!("{:05}", value % 100_000)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::SeededRng;
+
+    fn key(seed: u64) -> Vec<u8> {
+        crypto::signing_keypair(&mut SeededRng::seed_from_u64(seed)).0
+    }
+
+    #[test]
+    fn both_sides_see_the_same_grouped_number() {
+        let alice_view = SafetyNumber::compute(&key(1), &key(2)).unwrap();
+        let bob_view = SafetyNumber::compute(&key(2), &key(1)).unwrap();
+        assert_eq!(alice_view, bob_view);
+        assert_eq!(alice_view.as_str().len(), 2 * FINGERPRINT_DIGITS);
+        assert!(alice_view.as_str().bytes().all(|b| b.is_ascii_digit()));
+        assert_eq!(alice_view.groups().len(), 12);
+        assert_eq!(alice_view.to_string().len(), 60 + 11);
+    }
+
+    #[test]
+    fn grouped_or_ungrouped_input_verifies() {
+        let number = SafetyNumber::compute(&key(1), &key(2)).unwrap();
+        assert!(verify_safety_number(&key(2), &key(1), &number.to_string()).unwrap());
+        assert!(verify_safety_number(&key(1), &key(2), number.as_str()).unwrap());
+    }
+
+    #[test]
+    fn substituted_key_changes_the_number() {
+        let number = SafetyNumber::compute(&key(1), &key(2)).unwrap();
+        assert!(!verify_safety_number(&key(1), &key(3), number.as_str()).unwrap());
+        assert!(SafetyNumber::compute(&key(1), b"short").is_err());
+    }
+}
@@ -3,13 +3,20 @@
 //! Identity, crypto, metering, and classification logic now lives in
 //! FastLang circuits (circuits/fl/*.fl). This crate provides only the
 //! runtime helpers that can't be expressed as circuits: AppContext,
-//! topic formatting, and error types.
+//! topic formatting, error types, and the host bindings that let the
+//! runtime crates call into the crypto circuits.
 //!
-//! See: circuits/fl/polykit_identity.fl (replaces identity.rs + crypto.rs)
+//! See: circuits/fl/polykit_identity.fl (replaces identity.rs; crypto.rs only binds to it)
 //!      circuits/fl/polykit_metering.fl (replaces metering.rs)
 //!      docs/FASTLANG_REFACTOR_PLAN.md
 
 pub mod identity;
 pub mod classification;
 pub mod wire;
+pub mod crypto;
+pub mod chunking;
+pub mod cancel;
+pub mod clock;
+pub mod versioned;
+pub mod secret;
 pub mod error;
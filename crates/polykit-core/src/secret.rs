@@ -0,0 +1,116 @@
+//! Zeroizing containers for key material
+//!
+//! Secret keys, derived seeds, and AES keys live in `Secret<T>`, which
+//! overwrites its contents when dropped, so they don't linger in WASM
+//! linear memory after use.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{compiler_fence, Ordering};
+
+/// Types whose contents can be overwritten in place.
+pub trait Zeroize {
+    fn zeroize(&mut self);
+}
+
+impl Zeroize for [u8] {
+    fn zeroize(&mut self) {
+        for byte in self.iter_mut() {
+            // SAFETY: `byte` is a valid, aligned &mut u8. The volatile write
+            // keeps the compiler from eliding stores to memory about to be freed.
+            unsafe { std::ptr::write_volatile(byte, 0) };
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl<const N: usize> Zeroize for [u8; N] {
+    fn zeroize(&mut self) {
+        self.as_mut_slice().zeroize();
+    }
+}
+
+impl Zeroize for Vec<u8> {
+    fn zeroize(&mut self) {
+        self.as_mut_slice().zeroize();
+        self.clear();
+    }
+}
+
+impl Zeroize for String {
+    fn zeroize(&mut self) {
+        // SAFETY: all-zero bytes are valid UTF-8, and the string is then cleared.
+        unsafe { self.as_bytes_mut() }.zeroize();
+        self.clear();
+    }
+}
+
+/// Owns a secret and zeroizes it on drop. Derefs to the inner value;
+/// `Debug` never prints it.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Zeroize now rather than at drop.
+    pub fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Zeroize> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> DerefMut for Secret<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize> std::fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret([REDACTED])")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zeroize_overwrites_fixed_and_growable_secrets() {
+        let mut key = Secret::new([0xAB; 32]);
+        key.zeroize();
+        assert_eq!(*key, [0; 32]);
+
+        let mut seed = Secret::new(vec![0xCD; 64]);
+        seed.zeroize();
+        assert!(seed.is_empty());
+    }
+
+    #[test]
+    fn debug_never_prints_the_secret() {
+        let key = Secret::new(b"hunter2".to_vec());
+        assert_eq!(format!("{:?}", key), "Secret([REDACTED])");
+        assert_eq!(format!("{:?}", Some(key)), "Some(Secret([REDACTED]))");
+    }
+}
@@ -0,0 +1,98 @@
+//! Versioned envelope for serde types that cross the WASM/server boundary
+//!
+//! Payloads are wrapped as `{ "v": <schema version>, "data": <T> }` so an
+//! old client and a newer server can tell when they disagree, instead of
+//! failing on (or silently dropping) a changed field. Additive changes
+//! should rely on `#[serde(default)]` and keep the version; bump it only
+//! for breaking changes. Older versions are accepted, newer ones rejected.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{PolykitError, Result};
+
+/// Current schema version of a wire-crossing type.
+pub trait WireVersion {
+    const VERSION: u16;
+}
+
+/// A payload stamped with its schema version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub v: u16,
+    pub data: T,
+}
+
+/// Just the version, read before decoding `data` so a newer payload
+/// reports a version error rather than a field error.
+#[derive(Deserialize)]
+struct VersionHeader {
+    v: u16,
+}
+
+impl<T: WireVersion> Versioned<T> {
+    /// Stamp `data` with its type's current version.
+    pub fn new(data: T) -> Self {
+        Self { v: T::VERSION, data }
+    }
+}
+
+/// Serialize `data` inside a versioned envelope.
+pub fn encode<T: Serialize + WireVersion>(data: &T) -> Result<String> {
+    let envelope = Versioned { v: T::VERSION, data };
+    serde_json::to_string(&envelope)
+        .map_err(|e| PolykitError::Wire(format!("versioned encode failed: {}", e)))
+}
+
+/// Decode a versioned envelope, rejecting versions newer than this build
+/// understands.
+pub fn decode<T: DeserializeOwned + WireVersion>(encoded: &str) -> Result<T> {
+    let header: VersionHeader = serde_json::from_str(encoded)
+        .map_err(|e| PolykitError::Wire(format!("missing schema version: {}", e)))?;
+    if header.v > T::VERSION {
+        return Err(PolykitError::Wire(format!(
+            "payload is schema v{} but this build supports up to v{}",
+            header.v,
+            T::VERSION
+        )));
+    }
+    let envelope: Versioned<T> = serde_json::from_str(encoded).map_err(|e| {
+        PolykitError::Wire(format!("versioned decode failed (v{}): {}", header.v, e))
+    })?;
+    Ok(envelope.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Receipt {
+        id: u32,
+        #[serde(default)]
+        read: bool,
+    }
+
+    impl WireVersion for Receipt {
+        const VERSION: u16 = 2;
+    }
+
+    #[test]
+    fn older_versions_are_accepted() {
+        let receipt: Receipt = decode(r#"{"v":1,"data":{"id":9}}"#).unwrap();
+        assert_eq!(receipt, Receipt { id: 9, read: false });
+    }
+
+    #[test]
+    fn missing_version_is_rejected() {
+        assert!(decode::<Receipt>(r#"{"data":{"id":9}}"#).is_err());
+    }
+
+    #[test]
+    fn envelope_carries_current_version() {
+        let envelope = Versioned::new(Receipt { id: 1, read: true });
+        assert_eq!(envelope.v, 2);
+        let encoded = encode(&envelope.data).unwrap();
+        assert_eq!(encoded, r#"{"v":2,"data":{"id":1,"read":true}}"#);
+    }
+}
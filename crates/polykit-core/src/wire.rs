@@ -4,9 +4,18 @@
 //! from WASM. All wire operations happen in WASM — TypeScript never
 //! frames, signs, or encrypts wire messages.
 
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
 use serde::{Deserialize, Serialize};
-use crate::error::Result;
+use crate::clock::Clock;
+use crate::crypto;
+use crate::error::{PolykitError, Result};
 use crate::identity::AppContext;
+use crate::secret::Secret;
+
+/// Default UDP datagram budget (bytes). Conservative enough to avoid
+/// IP fragmentation across typical paths.
+pub const DEFAULT_MTU: usize = 1200;
 
 /// Wire protocol transport preference.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -17,6 +26,14 @@ pub enum Transport {
     WebTransport,
 }
 
+impl Transport {
+    /// Datagram transports must fragment payloads above the MTU;
+    /// stream transports (WebTransport/QUIC) carry them whole.
+    pub fn needs_fragmentation(&self) -> bool {
+        matches!(self, Transport::Udp)
+    }
+}
+
 /// Wire session state.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WireSession {
@@ -26,6 +43,13 @@ pub struct WireSession {
     pub transport: Transport,
     /// Connected edge node
     pub edge_node: String,
+    /// Maximum datagram size for fragmenting transports
+    #[serde(default = "default_mtu")]
+    pub mtu: usize,
+}
+
+fn default_mtu() -> usize {
+    DEFAULT_MTU
 }
 
 /// SPARK authentication message types (wire protocol opcodes)
@@ -34,6 +58,194 @@ pub mod opcodes {
     pub const SPARK_CHALLENGE: u8 = 0x51;
     pub const SPARK_AUTH_REQUEST: u8 = 0x52;
     pub const SPARK_SESSION_GRANT: u8 = 0x53;
+
+    /// Opcodes below this are reserved for the core protocol.
+    pub const APP_RANGE_START: u8 = 0x80;
+
+    /// Core opcodes currently defined.
+    pub const CORE: &[u8] = &[
+        SPARK_CHALLENGE_REQUEST,
+        SPARK_CHALLENGE,
+        SPARK_AUTH_REQUEST,
+        SPARK_SESSION_GRANT,
+    ];
+
+    pub fn is_reserved(opcode: u8) -> bool {
+        opcode < APP_RANGE_START
+    }
+}
+
+/// App-specific opcodes allocated from the app range (0x80..=0xFF).
+#[derive(Debug, Clone, Default)]
+pub struct OpcodeRegistry {
+    app_opcodes: BTreeMap<u8, String>,
+}
+
+impl OpcodeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a specific app opcode. Reserved or taken values are rejected.
+    pub fn register(&mut self, name: &str, opcode: u8) -> Result<u8> {
+        if opcodes::is_reserved(opcode) {
+            return Err(PolykitError::Wire(format!(
+                "opcode {:#04x} is reserved for the core protocol",
+                opcode
+            )));
+        }
+        if let Some(existing) = self.app_opcodes.get(&opcode) {
+            return Err(PolykitError::Wire(format!(
+                "opcode {:#04x} already registered to {}",
+                opcode, existing
+            )));
+        }
+        self.app_opcodes.insert(opcode, name.to_string());
+        Ok(opcode)
+    }
+
+    /// Allocate the lowest free app opcode.
+    pub fn allocate(&mut self, name: &str) -> Result<u8> {
+        let opcode = (opcodes::APP_RANGE_START..=u8::MAX)
+            .find(|op| !self.app_opcodes.contains_key(op))
+            .ok_or_else(|| PolykitError::Wire("app opcode range exhausted".to_string()))?;
+        self.register(name, opcode)
+    }
+
+    /// Name of a registered app opcode.
+    pub fn name(&self, opcode: u8) -> Option<&str> {
+        self.app_opcodes.get(&opcode).map(|s| s.as_str())
+    }
+
+    /// Core opcodes and registered app opcodes are valid on the wire.
+    pub fn is_known(&self, opcode: u8) -> bool {
+        opcodes::CORE.contains(&opcode) || self.app_opcodes.contains_key(&opcode)
+    }
+}
+
+/// Frame header: opcode (u8) | payload length (u32), big-endian.
+pub const FRAME_HEADER_LEN: usize = 5;
+
+/// A decoded wire frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub opcode: u8,
+    pub payload: Vec<u8>,
+}
+
+/// Frame a message, rejecting opcodes the registry doesn't know.
+pub fn encode_frame(registry: &OpcodeRegistry, opcode: u8, payload: &[u8]) -> Result<Vec<u8>> {
+    if !registry.is_known(opcode) {
+        return Err(PolykitError::Wire(format!("unknown opcode {:#04x}", opcode)));
+    }
+    let len = u32::try_from(payload.len())
+        .map_err(|_| PolykitError::Wire("frame payload too large".to_string()))?;
+    let mut out = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+    out.push(opcode);
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(payload);
+    Ok(out)
+}
+
+/// Parse a frame, rejecting unknown opcodes and length mismatches.
+pub fn decode_frame(registry: &OpcodeRegistry, bytes: &[u8]) -> Result<Frame> {
+    if bytes.len() < FRAME_HEADER_LEN {
+        return Err(PolykitError::Wire("frame shorter than header".to_string()));
+    }
+    let opcode = bytes[0];
+    if !registry.is_known(opcode) {
+        return Err(PolykitError::Wire(format!("unknown opcode {:#04x}", opcode)));
+    }
+    let len = u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]) as usize;
+    let payload = &bytes[FRAME_HEADER_LEN..];
+    if payload.len() != len {
+        return Err(PolykitError::Wire(format!(
+            "frame length {} does not match payload {}",
+            len,
+            payload.len()
+        )));
+    }
+    Ok(Frame { opcode, payload: payload.to_vec() })
+}
+
+/// Where a frame goes on a WebTransport session. Browsers expose reliable
+/// streams and unreliable, unfragmented datagrams rather than raw UDP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebTransportChannel {
+    /// Bidirectional stream opened with the session: core-range opcodes
+    /// (SPARK handshake, subscription control), which must arrive in order
+    ControlStream,
+    /// App frames that fit in one datagram
+    Datagram,
+    /// A fresh unidirectional stream per frame, for app frames too large
+    /// for a datagram (browsers don't fragment them)
+    UniStream,
+}
+
+/// Browser WebTransport session primitives. Frames are written in the
+/// `encode_frame` format on every channel; the length prefix delimits
+/// consecutive frames on the control stream.
+pub trait WebTransportIo {
+    fn write_control(&mut self, frame: &[u8]) -> Result<()>;
+    fn send_datagram(&mut self, frame: &[u8]) -> Result<()>;
+    fn write_uni_stream(&mut self, frame: &[u8]) -> Result<()>;
+    /// Largest datagram the browser session accepts
+    fn max_datagram_size(&self) -> usize;
+}
+
+/// Channel for a frame: control opcodes always ride the reliable stream.
+pub fn webtransport_channel(
+    opcode: u8,
+    frame_len: usize,
+    max_datagram: usize,
+) -> WebTransportChannel {
+    if opcodes::is_reserved(opcode) {
+        WebTransportChannel::ControlStream
+    } else if frame_len <= max_datagram {
+        WebTransportChannel::Datagram
+    } else {
+        WebTransportChannel::UniStream
+    }
+}
+
+/// Frame a message and send it on the matching WebTransport channel.
+pub fn send_webtransport(
+    io: &mut dyn WebTransportIo,
+    registry: &OpcodeRegistry,
+    opcode: u8,
+    payload: &[u8],
+) -> Result<WebTransportChannel> {
+    let frame = encode_frame(registry, opcode, payload)?;
+    let channel = webtransport_channel(opcode, frame.len(), io.max_datagram_size());
+    match channel {
+        WebTransportChannel::ControlStream => io.write_control(&frame)?,
+        WebTransportChannel::Datagram => io.send_datagram(&frame)?,
+        WebTransportChannel::UniStream => io.write_uni_stream(&frame)?,
+    }
+    Ok(channel)
+}
+
+/// WebTransport via the browser's session object.
+pub struct HostWebTransport;
+
+impl WebTransportIo for HostWebTransport {
+    // In production: host imports wire::wt_write_control / wt_send_datagram /
+    // wt_write_uni, backed by the JS WebTransport session on :4433
+    fn write_control(&mut self, _frame: &[u8]) -> Result<()> {
+        Ok(()) // Stub
+    }
+
+    fn send_datagram(&mut self, _frame: &[u8]) -> Result<()> {
+        Ok(()) // Stub
+    }
+
+    fn write_uni_stream(&mut self, _frame: &[u8]) -> Result<()> {
+        Ok(()) // Stub
+    }
+
+    fn max_datagram_size(&self) -> usize {
+        DEFAULT_MTU // Stub — in production: datagrams.maxDatagramSize
+    }
 }
 
 /// Perform SPARK authentication over wire protocol.
@@ -44,22 +256,344 @@ pub fn authenticate(
     _transport: Transport,
 ) -> Result<WireSession> {
     // In production: sends SparkChallengeRequest (0x50), receives challenge (0x51),
-    // signs with ML-DSA-87, sends SparkAuthRequest (0x52), receives session grant (0x53)
+    // absorbing both into a HandshakeTranscript; signs it with sign_auth_request,
+    // sends SparkAuthRequest (0x52), receives session grant (0x53)
     Ok(WireSession {
         session_token: vec![0u8; 32],
         transport: Transport::WebTransport,
         edge_node: String::new(),
+        mtu: DEFAULT_MTU,
     }) // Stub
 }
 
+/// Running hash over every SPARK handshake message, in order. Signatures
+/// and key derivation cover the transcript hash, so a message spliced in
+/// from another handshake breaks both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandshakeTranscript {
+    hash: [u8; 32],
+}
+
+impl HandshakeTranscript {
+    pub fn new() -> Self {
+        Self { hash: crypto::sha3_256(b"spark-transcript-v1") }
+    }
+
+    /// Fold in one handshake message: SHA3-256(prev || opcode || len || payload).
+    pub fn absorb(&mut self, opcode: u8, payload: &[u8]) {
+        let mut input = self.hash.to_vec();
+        input.push(opcode);
+        input.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        input.extend_from_slice(payload);
+        self.hash = crypto::sha3_256(&input);
+    }
+
+    pub fn hash(&self) -> &[u8; 32] {
+        &self.hash
+    }
+}
+
+impl Default for HandshakeTranscript {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Bytes the client signs in SparkAuthRequest (0x52): domain tag and the
+/// transcript hash after the challenge request (0x50) and challenge (0x51).
+pub fn auth_signed_bytes(transcript: &HandshakeTranscript) -> Vec<u8> {
+    let mut out = b"spark-auth-v1".to_vec();
+    out.extend_from_slice(transcript.hash());
+    out
+}
+
+/// Client side: sign the transcript so far for SparkAuthRequest (0x52).
+pub fn sign_auth_request(signing_key: &[u8], transcript: &HandshakeTranscript) -> Result<Vec<u8>> {
+    crypto::sign(signing_key, &auth_signed_bytes(transcript))
+}
+
+/// Server side: check the client's auth signature against the server's
+/// own transcript. Fails if either side saw a different message.
+pub fn verify_auth_request(
+    client_public_key: &[u8],
+    transcript: &HandshakeTranscript,
+    signature: &[u8],
+) -> Result<()> {
+    if !crypto::verify(client_public_key, &auth_signed_bytes(transcript), signature)? {
+        return Err(PolykitError::Wire(
+            "auth signature does not match handshake transcript".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Session grant (0x53). For mutual auth the server signs the client's
+/// challenge nonce together with the grant it issues.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SparkSessionGrant {
+    pub session_token: Vec<u8>,
+    pub edge_node: String,
+    /// ML-DSA-87 signature by the server over `grant_signed_bytes`
+    pub server_signature: Vec<u8>,
+}
+
+/// Bytes the server signs: domain tag, challenge nonce, session token, edge node.
+pub fn grant_signed_bytes(challenge_nonce: &[u8], session_token: &[u8], edge_node: &str) -> Vec<u8> {
+    let mut out = b"spark-grant-v1".to_vec();
+    for field in [challenge_nonce, session_token, edge_node.as_bytes()] {
+        out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        out.extend_from_slice(field);
+    }
+    out
+}
+
+/// Server side: issue a grant signed over the client's challenge nonce.
+pub fn sign_session_grant(
+    server_secret_key: &[u8],
+    challenge_nonce: &[u8],
+    session_token: Vec<u8>,
+    edge_node: &str,
+) -> Result<SparkSessionGrant> {
+    let signed = grant_signed_bytes(challenge_nonce, &session_token, edge_node);
+    Ok(SparkSessionGrant {
+        server_signature: crypto::sign(server_secret_key, &signed)?,
+        session_token,
+        edge_node: edge_node.to_string(),
+    })
+}
+
+/// Client side: verify the server's signature against the pinned server
+/// key before accepting the grant. No pinned key, a malformed key, or a
+/// signature that doesn't verify aborts the handshake.
+pub fn accept_session_grant(
+    grant: SparkSessionGrant,
+    challenge_nonce: &[u8],
+    pinned_server_key: Option<&[u8]>,
+    transport: Transport,
+) -> Result<WireSession> {
+    let server_key = pinned_server_key
+        .ok_or_else(|| PolykitError::Wire("no pinned server key; refusing session grant".to_string()))?;
+    let signed = grant_signed_bytes(challenge_nonce, &grant.session_token, &grant.edge_node);
+    if !crypto::verify(server_key, &signed, &grant.server_signature)? {
+        return Err(PolykitError::Wire("session grant not signed by pinned server key".to_string()));
+    }
+    Ok(WireSession {
+        session_token: grant.session_token,
+        transport,
+        edge_node: grant.edge_node,
+        mtu: DEFAULT_MTU,
+    })
+}
+
+/// Perform mutually authenticated SPARK over wire protocol: the client
+/// proves its identity as in `authenticate`, then verifies the server's
+/// grant signature with `pinned_server_key`.
+pub fn authenticate_mutual(
+    _ctx: &AppContext,
+    _signing_key: &[u8],
+    pinned_server_key: &[u8],
+    transport: Transport,
+) -> Result<WireSession> {
+    // In production: challenge nonce is generated via crypto::HostRng and sent
+    // in SparkChallengeRequest (0x50); the grant arrives as 0x53.
+    let challenge_nonce = [0u8; 32];
+    let grant = SparkSessionGrant {
+        session_token: vec![0u8; 32],
+        edge_node: String::new(),
+        server_signature: Vec::new(),
+    }; // Stub
+    accept_session_grant(grant, &challenge_nonce, Some(pinned_server_key), transport)
+}
+
+/// Derive the application-layer session key from the ML-KEM shared secret,
+/// bound to both parties' user_ids, the session nonce, and the handshake
+/// transcript. The user_ids are put in canonical order, so initiator and
+/// responder derive the same key.
+pub fn derive_session_key(
+    shared_secret: &[u8; 32],
+    my_user_id: &[u8; 16],
+    peer_user_id: &[u8; 16],
+    nonce: &[u8],
+    transcript: &HandshakeTranscript,
+) -> Result<Secret<[u8; 32]>> {
+    let (low, high) = if my_user_id <= peer_user_id {
+        (my_user_id, peer_user_id)
+    } else {
+        (peer_user_id, my_user_id)
+    };
+    let mut info = b"spark-session-key-v1".to_vec();
+    info.extend_from_slice(low);
+    info.extend_from_slice(high);
+    info.extend_from_slice(nonce);
+    info.extend_from_slice(transcript.hash());
+    crypto::hkdf_sha3_256(shared_secret, &info)
+}
+
+/// Opaque ticket the server issues at handshake time. The session token
+/// and edge node are sealed under a server-only key; the expiry is in the
+/// clear (and bound as AAD) so clients can skip tickets that are already
+/// stale.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResumptionTicket {
+    pub expires_at_ms: u64,
+    /// nonce || AES-256-GCM(session_token_len | session_token | edge_node)
+    pub sealed: Vec<u8>,
+}
+
+/// How a session was established.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionOrigin {
+    /// One round trip via a resumption ticket
+    Resumed,
+    /// Full SPARK challenge-response
+    FullHandshake,
+}
+
+/// Server side: seal a session into a resumption ticket valid for `ttl_ms`.
+pub fn issue_resumption_ticket(
+    ticket_key: &[u8; 32],
+    session: &WireSession,
+    clock: &dyn Clock,
+    ttl_ms: u64,
+    rng: &mut dyn crypto::Rng,
+) -> Result<ResumptionTicket> {
+    let expires_at_ms = clock.now_ms().saturating_add(ttl_ms);
+    let mut plaintext = (session.session_token.len() as u32).to_be_bytes().to_vec();
+    plaintext.extend_from_slice(&session.session_token);
+    plaintext.extend_from_slice(session.edge_node.as_bytes());
+
+    let mut nonce = [0u8; crypto::AEAD_NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+    let mut sealed = nonce.to_vec();
+    sealed.extend(crypto::aead_seal(ticket_key, &nonce, &expires_at_ms.to_be_bytes(), &plaintext)?);
+
+    Ok(ResumptionTicket { expires_at_ms, sealed })
+}
+
+/// Server side: open a ticket and re-establish its session. Expired,
+/// tampered, or foreign tickets are rejected.
+pub fn redeem_resumption_ticket(
+    ticket_key: &[u8; 32],
+    ticket: &ResumptionTicket,
+    transport: Transport,
+    clock: &dyn Clock,
+) -> Result<WireSession> {
+    if clock.now_ms() >= ticket.expires_at_ms {
+        return Err(PolykitError::Wire("resumption ticket expired".to_string()));
+    }
+    if ticket.sealed.len() < crypto::AEAD_NONCE_LEN {
+        return Err(PolykitError::Wire("resumption ticket truncated".to_string()));
+    }
+    let (nonce, ciphertext) = ticket.sealed.split_at(crypto::AEAD_NONCE_LEN);
+    let nonce: [u8; crypto::AEAD_NONCE_LEN] = nonce.try_into().expect("split at nonce length");
+    let aad = ticket.expires_at_ms.to_be_bytes();
+    let plaintext = crypto::aead_open(ticket_key, &nonce, &aad, ciphertext)
+        .map_err(|_| PolykitError::Wire("resumption ticket invalid".to_string()))?;
+
+    let invalid = || PolykitError::Wire("resumption ticket malformed".to_string());
+    let len_bytes: [u8; 4] = plaintext.get(..4).ok_or_else(invalid)?.try_into().unwrap();
+    let token_len = u32::from_be_bytes(len_bytes) as usize;
+    let session_token = plaintext.get(4..4 + token_len).ok_or_else(invalid)?.to_vec();
+    let edge_node = String::from_utf8(plaintext[4 + token_len..].to_vec()).map_err(|_| invalid())?;
+
+    Ok(WireSession {
+        session_token,
+        transport,
+        edge_node,
+        mtu: DEFAULT_MTU,
+    })
+}
+
+/// Client side: resume with a ticket in one round trip, falling back to
+/// the full SPARK handshake if the ticket is expired or refused.
+pub fn resume_with_ticket(
+    ctx: &AppContext,
+    signing_key: &[u8],
+    ticket: &ResumptionTicket,
+    transport: Transport,
+    clock: &dyn Clock,
+) -> Result<(WireSession, SessionOrigin)> {
+    resume_with(&mut host_resume, ctx, signing_key, ticket, transport, clock)
+}
+
+/// `resume_with_ticket` over an explicit resume round trip.
+pub fn resume_with(
+    resume: &mut dyn FnMut(&ResumptionTicket, Transport) -> Result<WireSession>,
+    ctx: &AppContext,
+    signing_key: &[u8],
+    ticket: &ResumptionTicket,
+    transport: Transport,
+    clock: &dyn Clock,
+) -> Result<(WireSession, SessionOrigin)> {
+    if clock.now_ms() < ticket.expires_at_ms {
+        if let Ok(session) = resume(ticket, transport) {
+            return Ok((session, SessionOrigin::Resumed));
+        }
+    }
+    let session = authenticate(ctx, signing_key, transport)?;
+    Ok((session, SessionOrigin::FullHandshake))
+}
+
+fn host_resume(_ticket: &ResumptionTicket, _transport: Transport) -> Result<WireSession> {
+    // In production: sends the ticket via host import wire::resume; the edge
+    // runs redeem_resumption_ticket and answers with the session grant.
+    Err(PolykitError::Wire("resumption not available".to_string())) // Stub
+}
+
 /// Subscribe to a lex stream topic.
 pub fn subscribe(_session: &WireSession, _topic: &str) -> Result<SubscriptionHandle> {
     Ok(SubscriptionHandle { id: 0 }) // Stub
 }
 
 /// Emit a message to a lex stream topic.
-pub fn emit(_session: &WireSession, _topic: &str, _payload: &[u8]) -> Result<()> {
-    Ok(()) // Stub
+/// Payloads larger than the session MTU are fragmented on UDP.
+pub fn emit(session: &WireSession, topic: &str, payload: &[u8]) -> Result<()> {
+    emit_with(&mut HostWire, session, topic, payload)
+}
+
+/// Publish a signed revocation on the app's `{namespace}.revocations`
+/// topic, for peers to merge into their `RevocationList`.
+pub fn publish_revocation(
+    session: &WireSession,
+    ctx: &AppContext,
+    entry: &crate::identity::RevocationEntry,
+) -> Result<()> {
+    let payload = serde_json::to_vec(entry)
+        .map_err(|e| PolykitError::Wire(format!("encode revocation: {}", e)))?;
+    emit(session, &crate::identity::revocation_topic(ctx), &payload)
+}
+
+/// `emit` over an explicit transport. On UDP each fragment goes out as its
+/// own datagram; stream transports get the payload in one send.
+pub fn emit_with(
+    io: &mut dyn WireIo,
+    session: &WireSession,
+    topic: &str,
+    payload: &[u8],
+) -> Result<()> {
+    if !session.transport.needs_fragmentation() {
+        return io.send(topic, payload);
+    }
+    let message_id = NEXT_MESSAGE_ID.fetch_add(1, Ordering::Relaxed);
+    for fragment in fragment(message_id, payload, session.mtu)? {
+        io.send(topic, &fragment.encode())?;
+    }
+    Ok(())
+}
+
+/// Sends one message (or one datagram, when fragmenting) to a topic.
+pub trait WireIo {
+    fn send(&mut self, topic: &str, bytes: &[u8]) -> Result<()>;
+}
+
+/// Sends via the host's wire session.
+pub struct HostWire;
+
+impl WireIo for HostWire {
+    // In production: host import wire::send
+    fn send(&mut self, _topic: &str, _bytes: &[u8]) -> Result<()> {
+        Ok(()) // Stub
+    }
 }
 
 /// Handle for an active stream subscription.
@@ -67,3 +601,598 @@ pub fn emit(_session: &WireSession, _topic: &str, _payload: &[u8]) -> Result<()>
 pub struct SubscriptionHandle {
     pub id: u64,
 }
+
+static NEXT_MESSAGE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Fragment header: message_id (u64) | index (u16) | total (u16), big-endian.
+pub const FRAGMENT_HEADER_LEN: usize = 12;
+
+/// One numbered piece of a payload that exceeded the MTU.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fragment {
+    pub message_id: u64,
+    pub index: u16,
+    pub total: u16,
+    pub payload: Vec<u8>,
+}
+
+impl Fragment {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(FRAGMENT_HEADER_LEN + self.payload.len());
+        out.extend_from_slice(&self.message_id.to_be_bytes());
+        out.extend_from_slice(&self.index.to_be_bytes());
+        out.extend_from_slice(&self.total.to_be_bytes());
+        out.extend_from_slice(&self.payload);
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < FRAGMENT_HEADER_LEN {
+            return Err(PolykitError::Wire(format!(
+                "fragment too short: {} bytes",
+                bytes.len()
+            )));
+        }
+        let fragment = Fragment {
+            message_id: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            index: u16::from_be_bytes([bytes[8], bytes[9]]),
+            total: u16::from_be_bytes([bytes[10], bytes[11]]),
+            payload: bytes[FRAGMENT_HEADER_LEN..].to_vec(),
+        };
+        if fragment.total == 0 || fragment.index >= fragment.total {
+            return Err(PolykitError::Wire(format!(
+                "fragment index {} out of range for total {}",
+                fragment.index, fragment.total
+            )));
+        }
+        Ok(fragment)
+    }
+}
+
+/// Split a payload into fragments whose encoded size fits within `mtu`.
+pub fn fragment(message_id: u64, payload: &[u8], mtu: usize) -> Result<Vec<Fragment>> {
+    if mtu <= FRAGMENT_HEADER_LEN {
+        return Err(PolykitError::Wire(format!(
+            "mtu {} leaves no room for fragment payload",
+            mtu
+        )));
+    }
+    let chunk_size = mtu - FRAGMENT_HEADER_LEN;
+    let chunks: Vec<&[u8]> = if payload.is_empty() {
+        vec![payload]
+    } else {
+        payload.chunks(chunk_size).collect()
+    };
+    let total = u16::try_from(chunks.len()).map_err(|_| {
+        PolykitError::Wire(format!(
+            "payload of {} bytes needs more than {} fragments",
+            payload.len(),
+            u16::MAX
+        ))
+    })?;
+
+    Ok(chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| Fragment {
+            message_id,
+            index: i as u16,
+            total,
+            payload: chunk.to_vec(),
+        })
+        .collect())
+}
+
+/// Default number of incomplete messages a `Reassembler` holds.
+pub const DEFAULT_MAX_PENDING: usize = 64;
+
+/// Reassembles fragmented messages. Fragments may arrive in any order;
+/// a message is only delivered once every fragment is present. At most
+/// `max_pending` messages are held: a fragment of a new message evicts
+/// the incomplete message that started longest ago, so lost datagrams
+/// can't grow memory without bound.
+pub struct Reassembler {
+    pending: HashMap<u64, PartialMessage>,
+    max_pending: usize,
+    arrivals: u64,
+}
+
+struct PartialMessage {
+    total: u16,
+    fragments: BTreeMap<u16, Vec<u8>>,
+    /// Order in which the message's first fragment arrived
+    arrival: u64,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self::with_max_pending(DEFAULT_MAX_PENDING)
+    }
+
+    pub fn with_max_pending(max_pending: usize) -> Self {
+        Self {
+            pending: HashMap::new(),
+            max_pending: max_pending.max(1),
+            arrivals: 0,
+        }
+    }
+
+    /// Accept a fragment. Returns the full payload once the message is complete.
+    pub fn push(&mut self, fragment: Fragment) -> Result<Option<Vec<u8>>> {
+        if fragment.total == 0 || fragment.index >= fragment.total {
+            return Err(PolykitError::Wire(format!(
+                "fragment index {} out of range for total {}",
+                fragment.index, fragment.total
+            )));
+        }
+
+        if !self.pending.contains_key(&fragment.message_id) {
+            if self.pending.len() >= self.max_pending {
+                self.evict_oldest();
+            }
+            self.arrivals += 1;
+        }
+        let arrival = self.arrivals;
+        let partial = self
+            .pending
+            .entry(fragment.message_id)
+            .or_insert_with(|| PartialMessage {
+                total: fragment.total,
+                fragments: BTreeMap::new(),
+                arrival,
+            });
+        if partial.total != fragment.total {
+            return Err(PolykitError::Wire(format!(
+                "message {}: fragment total changed from {} to {}",
+                fragment.message_id, partial.total, fragment.total
+            )));
+        }
+        partial.fragments.insert(fragment.index, fragment.payload);
+
+        if partial.fragments.len() == partial.total as usize {
+            let complete = self.pending.remove(&fragment.message_id).unwrap();
+            return Ok(Some(complete.fragments.into_values().flatten().collect()));
+        }
+        Ok(None)
+    }
+
+    /// Give up on a message (e.g., after a receive timeout). Errors with the
+    /// missing fragment indices rather than delivering partial data.
+    pub fn finish(&mut self, message_id: u64) -> Result<Vec<u8>> {
+        let partial = self.pending.remove(&message_id).ok_or_else(|| {
+            PolykitError::Wire(format!("message {}: no fragments received", message_id))
+        })?;
+        let missing: Vec<u16> = (0..partial.total)
+            .filter(|i| !partial.fragments.contains_key(i))
+            .collect();
+        if !missing.is_empty() {
+            return Err(PolykitError::Wire(format!(
+                "message {}: missing fragments {:?} of {}",
+                message_id, missing, partial.total
+            )));
+        }
+        Ok(partial.fragments.into_values().flatten().collect())
+    }
+
+    /// Messages still waiting on fragments.
+    pub fn pending_messages(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self.pending.iter().min_by_key(|(_, p)| p.arrival).map(|(id, _)| *id);
+        if let Some(message_id) = oldest {
+            self.pending.remove(&message_id);
+        }
+    }
+}
+
+impl Default for Reassembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FakeClock;
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn udp_session(mtu: usize) -> WireSession {
+        WireSession {
+            session_token: vec![1; 32],
+            transport: Transport::Udp,
+            edge_node: "edge-1".to_string(),
+            mtu,
+        }
+    }
+
+    #[test]
+    fn fragments_reassemble_in_order() {
+        let data = payload(1000);
+        let fragments = fragment(7, &data, 300).unwrap();
+        assert_eq!(fragments.len(), 4);
+        assert!(fragments.iter().all(|f| f.encode().len() <= 300));
+
+        let mut reassembler = Reassembler::new();
+        let mut delivered = None;
+        for f in fragments {
+            let decoded = Fragment::decode(&f.encode()).unwrap();
+            delivered = reassembler.push(decoded).unwrap();
+        }
+        assert_eq!(delivered, Some(data));
+        assert_eq!(reassembler.pending_messages(), 0);
+    }
+
+    #[test]
+    fn fragments_reassemble_out_of_order() {
+        let data = payload(1000);
+        let mut fragments = fragment(7, &data, 300).unwrap();
+        fragments.reverse();
+        fragments.swap(1, 2);
+
+        let mut reassembler = Reassembler::new();
+        let last = fragments.pop().unwrap();
+        for f in fragments {
+            assert_eq!(reassembler.push(f).unwrap(), None);
+        }
+        assert_eq!(reassembler.push(last).unwrap(), Some(data));
+    }
+
+    #[test]
+    fn dropped_fragment_is_reported() {
+        let mut fragments = fragment(9, &payload(1000), 300).unwrap();
+        fragments.remove(2);
+
+        let mut reassembler = Reassembler::new();
+        for f in fragments {
+            assert_eq!(reassembler.push(f).unwrap(), None);
+        }
+        let err = reassembler.finish(9).unwrap_err().to_string();
+        assert!(err.contains("missing fragments [2] of 4"), "{}", err);
+        assert_eq!(reassembler.pending_messages(), 0);
+    }
+
+    #[test]
+    fn reassembler_evicts_oldest_incomplete_message() {
+        let mut reassembler = Reassembler::with_max_pending(2);
+        for message_id in 1..=3 {
+            let first = fragment(message_id, &payload(600), 300).unwrap().remove(0);
+            reassembler.push(first).unwrap();
+        }
+        assert_eq!(reassembler.pending_messages(), 2);
+        assert!(reassembler.finish(1).unwrap_err().to_string().contains("no fragments"));
+        assert!(reassembler.finish(3).unwrap_err().to_string().contains("missing"));
+    }
+
+    #[derive(Default)]
+    struct RecordingIo {
+        sent: Vec<(String, Vec<u8>)>,
+    }
+
+    impl WireIo for RecordingIo {
+        fn send(&mut self, topic: &str, bytes: &[u8]) -> Result<()> {
+            self.sent.push((topic.to_string(), bytes.to_vec()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn emit_sends_each_fragment_on_udp() {
+        let data = payload(2500);
+        let mut io = RecordingIo::default();
+        emit_with(&mut io, &udp_session(1200), "data.events", &data).unwrap();
+        assert_eq!(io.sent.len(), 3);
+
+        let mut reassembler = Reassembler::new();
+        let mut delivered = None;
+        for (topic, datagram) in &io.sent {
+            assert_eq!(topic, "data.events");
+            assert!(datagram.len() <= 1200);
+            delivered = reassembler.push(Fragment::decode(datagram).unwrap()).unwrap();
+        }
+        assert_eq!(delivered, Some(data));
+    }
+
+    #[test]
+    fn emit_sends_whole_payload_on_streams() {
+        let data = payload(2500);
+        let session = WireSession { transport: Transport::WebTransport, ..udp_session(1200) };
+        let mut io = RecordingIo::default();
+        emit_with(&mut io, &session, "data.events", &data).unwrap();
+        assert_eq!(io.sent, vec![("data.events".to_string(), data)]);
+    }
+
+    #[test]
+    fn session_without_mtu_uses_default() {
+        let json = r#"{"session_token":[],"transport":"Udp","edge_node":"edge-1"}"#;
+        let session: WireSession = serde_json::from_str(json).unwrap();
+        assert_eq!(session.mtu, DEFAULT_MTU);
+    }
+
+    fn server_keys(seed: u64) -> (Vec<u8>, Vec<u8>) {
+        let (public_key, secret_key) =
+            crypto::signing_keypair(&mut crypto::SeededRng::seed_from_u64(seed));
+        (public_key, secret_key.to_vec())
+    }
+
+    const NONCE: [u8; 32] = [9; 32];
+
+    #[test]
+    fn mutual_handshake_accepts_pinned_server() {
+        let (public_key, secret_key) = server_keys(1);
+        let grant = sign_session_grant(&secret_key, &NONCE, vec![7; 32], "edge-1").unwrap();
+        let session =
+            accept_session_grant(grant, &NONCE, Some(&public_key), Transport::Udp).unwrap();
+        assert_eq!(session.session_token, vec![7; 32]);
+        assert_eq!(session.edge_node, "edge-1");
+    }
+
+    #[test]
+    fn grant_from_other_server_is_rejected() {
+        let (pinned, _) = server_keys(1);
+        let (_, impostor) = server_keys(2);
+        let grant = sign_session_grant(&impostor, &NONCE, vec![7; 32], "edge-1").unwrap();
+        assert!(accept_session_grant(grant, &NONCE, Some(&pinned), Transport::Udp).is_err());
+    }
+
+    #[test]
+    fn grant_for_other_challenge_is_rejected() {
+        let (public_key, secret_key) = server_keys(1);
+        let grant = sign_session_grant(&secret_key, &[1; 32], vec![7; 32], "edge-1").unwrap();
+        assert!(accept_session_grant(grant, &NONCE, Some(&public_key), Transport::Udp).is_err());
+    }
+
+    #[test]
+    fn unpinned_server_is_rejected() {
+        let (_, secret_key) = server_keys(1);
+        let grant = sign_session_grant(&secret_key, &NONCE, vec![7; 32], "edge-1").unwrap();
+        assert!(accept_session_grant(grant, &NONCE, None, Transport::Udp).is_err());
+    }
+
+    const TICKET_KEY: [u8; 32] = [3; 32];
+
+    /// Ticket issued at t=1s, valid for a minute.
+    fn ticket() -> ResumptionTicket {
+        let mut rng = crypto::SeededRng::seed_from_u64(11);
+        let issued_at = FakeClock::new(1_000);
+        issue_resumption_ticket(&TICKET_KEY, &udp_session(1200), &issued_at, 60_000, &mut rng)
+            .unwrap()
+    }
+
+    fn redeem(ticket: &ResumptionTicket, key: &[u8; 32], now_ms: u64) -> Result<WireSession> {
+        redeem_resumption_ticket(key, ticket, Transport::Udp, &FakeClock::new(now_ms))
+    }
+
+    #[test]
+    fn valid_ticket_redeems_session() {
+        let session = redeem(&ticket(), &TICKET_KEY, 2_000).unwrap();
+        assert_eq!(session.session_token, vec![1; 32]);
+        assert_eq!(session.edge_node, "edge-1");
+    }
+
+    #[test]
+    fn expired_tampered_or_foreign_tickets_are_refused() {
+        assert!(redeem(&ticket(), &TICKET_KEY, 61_000).is_err());
+        assert!(redeem(&ticket(), &[4; 32], 2_000).is_err());
+
+        // The expiry is bound as AAD, so it can't be extended
+        let extended = ResumptionTicket { expires_at_ms: 120_000, ..ticket() };
+        assert!(redeem(&extended, &TICKET_KEY, 2_000).is_err());
+    }
+
+    fn resume_at(
+        ticket: &ResumptionTicket,
+        now_ms: u64,
+        resume: &mut dyn FnMut(&ResumptionTicket, Transport) -> Result<WireSession>,
+    ) -> (WireSession, SessionOrigin) {
+        let ctx = crate::identity::create_app_context("polydata", "polydata-v1", "polydata");
+        resume_with(resume, &ctx, &[], ticket, Transport::Udp, &FakeClock::new(now_ms)).unwrap()
+    }
+
+    #[test]
+    fn client_resumes_with_valid_ticket() {
+        let mut edge = |ticket: &ResumptionTicket, _: Transport| redeem(ticket, &TICKET_KEY, 2_000);
+        let (session, origin) = resume_at(&ticket(), 2_000, &mut edge);
+        assert_eq!(origin, SessionOrigin::Resumed);
+        assert_eq!(session.edge_node, "edge-1");
+    }
+
+    #[test]
+    fn client_falls_back_on_expired_ticket() {
+        let mut edge = |_: &ResumptionTicket, _: Transport| -> Result<WireSession> {
+            panic!("expired tickets must not be sent")
+        };
+        let (_, origin) = resume_at(&ticket(), 90_000, &mut edge);
+        assert_eq!(origin, SessionOrigin::FullHandshake);
+    }
+
+    #[test]
+    fn client_falls_back_when_edge_refuses_ticket() {
+        let mut edge = |ticket: &ResumptionTicket, _: Transport| redeem(ticket, &TICKET_KEY, 2_000);
+        let refused = ResumptionTicket { sealed: vec![0; 40], ..ticket() };
+        let (_, origin) = resume_at(&refused, 2_000, &mut edge);
+        assert_eq!(origin, SessionOrigin::FullHandshake);
+    }
+
+    const ALICE: [u8; 16] = [1; 16];
+    const BOB: [u8; 16] = [2; 16];
+
+    fn handshake() -> HandshakeTranscript {
+        let mut transcript = HandshakeTranscript::new();
+        transcript.absorb(0x50, b"challenge request");
+        transcript.absorb(0x51, b"challenge");
+        transcript
+    }
+
+    #[test]
+    fn both_parties_derive_the_same_session_key() {
+        let secret = [5u8; 32];
+        let alice = derive_session_key(&secret, &ALICE, &BOB, b"nonce-1", &handshake()).unwrap();
+        let bob = derive_session_key(&secret, &BOB, &ALICE, b"nonce-1", &handshake()).unwrap();
+        assert_eq!(*alice, *bob);
+    }
+
+    #[test]
+    fn session_key_depends_on_nonce_and_transcript() {
+        let secret = [5u8; 32];
+        let key = derive_session_key(&secret, &ALICE, &BOB, b"nonce-1", &handshake()).unwrap();
+        let other_nonce =
+            derive_session_key(&secret, &ALICE, &BOB, b"nonce-2", &handshake()).unwrap();
+        assert_ne!(*key, *other_nonce);
+
+        let mut spliced = handshake();
+        spliced.absorb(0x51, b"another challenge");
+        let other_transcript =
+            derive_session_key(&secret, &ALICE, &BOB, b"nonce-1", &spliced).unwrap();
+        assert_ne!(*key, *other_transcript);
+    }
+
+    #[test]
+    fn session_key_is_bound_to_both_identities() {
+        let secret = [5u8; 32];
+        let key = derive_session_key(&secret, &ALICE, &BOB, b"nonce-1", &handshake()).unwrap();
+        let other_peer =
+            derive_session_key(&secret, &ALICE, &[3; 16], b"nonce-1", &handshake()).unwrap();
+        assert_ne!(*key, *other_peer);
+    }
+
+    #[test]
+    fn app_opcodes_are_registered_in_the_app_range() {
+        let mut registry = OpcodeRegistry::new();
+        assert_eq!(registry.register("read_receipt", 0x90).unwrap(), 0x90);
+        assert_eq!(registry.allocate("typing").unwrap(), opcodes::APP_RANGE_START);
+        assert_eq!(registry.name(0x90), Some("read_receipt"));
+        assert!(registry.register("duplicate", 0x90).is_err());
+    }
+
+    #[test]
+    fn reserved_opcodes_cannot_be_registered() {
+        let mut registry = OpcodeRegistry::new();
+        assert!(registry.register("spoof", opcodes::SPARK_CHALLENGE).is_err());
+        assert!(registry.register("unused_core", 0x10).is_err());
+    }
+
+    #[test]
+    fn app_frame_round_trips() {
+        let mut registry = OpcodeRegistry::new();
+        let opcode = registry.allocate("read_receipt").unwrap();
+        let bytes = encode_frame(&registry, opcode, b"msg-42").unwrap();
+        let frame = decode_frame(&registry, &bytes).unwrap();
+        assert_eq!(frame, Frame { opcode, payload: b"msg-42".to_vec() });
+
+        assert!(encode_frame(&registry, 0xF0, b"x").is_err());
+        assert!(decode_frame(&OpcodeRegistry::new(), &bytes).is_err());
+        assert!(decode_frame(&registry, &bytes[..bytes.len() - 1]).is_err());
+    }
+
+    /// Records which WebTransport channel each frame was written to.
+    struct MockWebTransport {
+        max_datagram: usize,
+        sent: Vec<(WebTransportChannel, Vec<u8>)>,
+    }
+
+    impl MockWebTransport {
+        fn new(max_datagram: usize) -> Self {
+            Self { max_datagram, sent: Vec::new() }
+        }
+    }
+
+    impl WebTransportIo for MockWebTransport {
+        fn write_control(&mut self, frame: &[u8]) -> Result<()> {
+            self.sent.push((WebTransportChannel::ControlStream, frame.to_vec()));
+            Ok(())
+        }
+
+        fn send_datagram(&mut self, frame: &[u8]) -> Result<()> {
+            self.sent.push((WebTransportChannel::Datagram, frame.to_vec()));
+            Ok(())
+        }
+
+        fn write_uni_stream(&mut self, frame: &[u8]) -> Result<()> {
+            self.sent.push((WebTransportChannel::UniStream, frame.to_vec()));
+            Ok(())
+        }
+
+        fn max_datagram_size(&self) -> usize {
+            self.max_datagram
+        }
+    }
+
+    #[test]
+    fn control_frames_use_the_reliable_stream() {
+        let registry = OpcodeRegistry::new();
+        let mut io = MockWebTransport::new(1_200);
+        for opcode in opcodes::CORE {
+            let channel = send_webtransport(&mut io, &registry, *opcode, b"hs").unwrap();
+            assert_eq!(channel, WebTransportChannel::ControlStream);
+        }
+        assert_eq!(io.sent.len(), opcodes::CORE.len());
+        assert!(io.sent.iter().all(|(c, _)| *c == WebTransportChannel::ControlStream));
+
+        // Same frame format as encode_frame, even for large handshake payloads
+        let big = vec![7u8; 4_000];
+        send_webtransport(&mut io, &registry, opcodes::SPARK_AUTH_REQUEST, &big).unwrap();
+        let (channel, frame) = io.sent.last().unwrap();
+        assert_eq!(*channel, WebTransportChannel::ControlStream);
+        assert_eq!(decode_frame(&registry, frame).unwrap().payload, big);
+    }
+
+    #[test]
+    fn app_frames_use_datagrams_until_too_large() {
+        let mut registry = OpcodeRegistry::new();
+        let opcode = registry.allocate("presence").unwrap();
+        let mut io = MockWebTransport::new(64);
+
+        let small = send_webtransport(&mut io, &registry, opcode, &[1; 10]).unwrap();
+        assert_eq!(small, WebTransportChannel::Datagram);
+        let large = send_webtransport(&mut io, &registry, opcode, &[1; 200]).unwrap();
+        assert_eq!(large, WebTransportChannel::UniStream);
+        assert_eq!(decode_frame(&registry, &io.sent[1].1).unwrap().payload.len(), 200);
+    }
+
+    #[test]
+    fn unknown_opcode_is_not_sent() {
+        let mut io = MockWebTransport::new(1_200);
+        assert!(send_webtransport(&mut io, &OpcodeRegistry::new(), 0xF0, b"x").is_err());
+        assert!(io.sent.is_empty());
+    }
+
+    fn transcript_of(challenge: &[u8]) -> HandshakeTranscript {
+        let mut transcript = HandshakeTranscript::new();
+        transcript.absorb(opcodes::SPARK_CHALLENGE_REQUEST, b"challenge request");
+        transcript.absorb(opcodes::SPARK_CHALLENGE, challenge);
+        transcript
+    }
+
+    #[test]
+    fn auth_over_a_matching_transcript_verifies() {
+        let (public_key, secret_key) =
+            crypto::signing_keypair(&mut crypto::SeededRng::seed_from_u64(11));
+        let client = transcript_of(b"challenge-1");
+        let signature = sign_auth_request(&secret_key, &client).unwrap();
+        let server = transcript_of(b"challenge-1");
+        assert!(verify_auth_request(&public_key, &server, &signature).is_ok());
+    }
+
+    #[test]
+    fn message_from_another_handshake_breaks_auth() {
+        let (public_key, secret_key) =
+            crypto::signing_keypair(&mut crypto::SeededRng::seed_from_u64(11));
+        // The client saw a challenge spliced in from a different handshake
+        let client = transcript_of(b"challenge-2");
+        let signature = sign_auth_request(&secret_key, &client).unwrap();
+        let err = verify_auth_request(&public_key, &transcript_of(b"challenge-1"), &signature);
+        assert!(err.is_err());
+
+        // Same bytes under a different opcode are a different transcript
+        let mut relabeled = HandshakeTranscript::new();
+        relabeled.absorb(opcodes::SPARK_AUTH_REQUEST, b"challenge request");
+        relabeled.absorb(opcodes::SPARK_CHALLENGE, b"challenge-1");
+        assert_ne!(relabeled, transcript_of(b"challenge-1"));
+    }
+}
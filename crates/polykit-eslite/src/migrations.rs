@@ -2,7 +2,11 @@
 //!
 //! Versioned, forward-only migrations with automatic schema_version tracking.
 //! Each app registers its migrations at init time; the runner applies
-//! any unapplied migrations in order.
+//! any unapplied migrations in order. Each migration is all-or-nothing:
+//! if one of its operations fails, the ones already applied are undone
+//! with their inverse operations and the version stays put. Drops have no
+//! inverse, so a drop may only be a migration's last operation; anything
+//! else is rejected before the migration runs.
 
 use serde::{Deserialize, Serialize};
 use crate::schema::TableDef;
@@ -40,6 +44,37 @@ pub enum MigrationOp {
     },
     /// Drop a table
     DropTable(String),
+    /// Drop a column (inverse of AddColumn)
+    DropColumn {
+        table: String,
+        name: String,
+    },
+    /// Drop an index (inverse of CreateIndex)
+    DropIndex {
+        table: String,
+        columns: Vec<String>,
+    },
+}
+
+impl MigrationOp {
+    /// The operation that undoes this one, if it can be undone without
+    /// data that isn't in the op (dropped tables and columns can't be).
+    pub fn inverse(&self) -> Option<MigrationOp> {
+        match self {
+            MigrationOp::CreateTable(def) => Some(MigrationOp::DropTable(def.name.clone())),
+            MigrationOp::AddColumn { table, name, .. } => Some(MigrationOp::DropColumn {
+                table: table.clone(),
+                name: name.clone(),
+            }),
+            MigrationOp::CreateIndex { table, columns, .. } => Some(MigrationOp::DropIndex {
+                table: table.clone(),
+                columns: columns.clone(),
+            }),
+            MigrationOp::DropTable(_)
+            | MigrationOp::DropColumn { .. }
+            | MigrationOp::DropIndex { .. } => None,
+        }
+    }
 }
 
 /// Migration runner. Tracks applied versions per table namespace.
@@ -60,20 +95,43 @@ impl MigrationRunner {
         &mut self,
         namespace: &str,
         migrations: &[Migration],
+    ) -> Result<u32, String> {
+        self.migrate_with(namespace, migrations, &mut execute_ddl)
+    }
+
+    /// Run unapplied migrations through `execute`. Each migration is a
+    /// transaction: on failure its applied ops are rolled back in reverse
+    /// order, the version stays at the last fully applied migration, and
+    /// the error names the failing op. A migration with an irreversible op
+    /// anywhere but last is rejected before any of its ops run.
+    pub fn migrate_with(
+        &mut self,
+        namespace: &str,
+        migrations: &[Migration],
+        execute: &mut dyn FnMut(&MigrationOp) -> Result<(), String>,
     ) -> Result<u32, String> {
         let current = self.applied_versions.get(namespace).copied().unwrap_or(0);
 
         let mut applied = 0;
         for migration in migrations {
-            if migration.version > current {
-                // In production: execute operations against ESLite store
-                // via host import eslite::execute_ddl
-                applied += 1;
+            if migration.version <= current {
+                continue;
+            }
+            check_reversible(migration)?;
+
+            for (i, op) in migration.operations.iter().enumerate() {
+                if let Err(e) = execute(op) {
+                    let rollback = rollback(&migration.operations[..i], execute);
+                    return Err(format!(
+                        "migration {} op {} failed: {}{}",
+                        migration.version, i, e, rollback
+                    ));
+                }
             }
-        }
 
-        let new_version = migrations.last().map(|m| m.version).unwrap_or(current);
-        self.applied_versions.insert(namespace.to_string(), new_version);
+            self.applied_versions.insert(namespace.to_string(), migration.version);
+            applied += 1;
+        }
 
         Ok(applied)
     }
@@ -83,3 +141,144 @@ impl MigrationRunner {
         self.applied_versions.get(namespace).copied().unwrap_or(0)
     }
 }
+
+/// Every op that could need undoing (all but the last) must have an inverse.
+fn check_reversible(migration: &Migration) -> Result<(), String> {
+    let Some((_, undoable)) = migration.operations.split_last() else {
+        return Ok(());
+    };
+    match undoable.iter().position(|op| op.inverse().is_none()) {
+        Some(i) => Err(format!(
+            "migration {} op {} is not reversible and must be the last op",
+            migration.version, i
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Undo `applied` ops in reverse order. Returns a suffix for the error
+/// message when rollback was incomplete.
+fn rollback(
+    applied: &[MigrationOp],
+    execute: &mut dyn FnMut(&MigrationOp) -> Result<(), String>,
+) -> String {
+    for (i, op) in applied.iter().enumerate().rev() {
+        let result = match op.inverse() {
+            Some(inverse) => execute(&inverse),
+            None => Err("operation is not reversible".to_string()),
+        };
+        if let Err(e) = result {
+            return format!("; rollback of op {} failed: {}", i, e);
+        }
+    }
+    String::new()
+}
+
+fn execute_ddl(_op: &MigrationOp) -> Result<(), String> {
+    // In production: host import eslite::execute_ddl
+    Ok(()) // Stub
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::TableBuilder;
+
+    fn add_column(name: &str) -> MigrationOp {
+        MigrationOp::AddColumn {
+            table: "contacts".into(),
+            name: name.into(),
+            column_type: "TEXT".into(),
+            default: None,
+            nullable: true,
+            indexed: false,
+        }
+    }
+
+    fn migration(version: u32, operations: Vec<MigrationOp>) -> Migration {
+        Migration { version, description: format!("v{}", version), operations }
+    }
+
+    fn label(op: &MigrationOp) -> String {
+        match op {
+            MigrationOp::CreateTable(def) => format!("create {}", def.name),
+            MigrationOp::AddColumn { name, .. } => format!("add {}", name),
+            MigrationOp::DropColumn { name, .. } => format!("drop {}", name),
+            MigrationOp::DropTable(name) => format!("drop table {}", name),
+            MigrationOp::CreateIndex { .. } => "create index".into(),
+            MigrationOp::DropIndex { .. } => "drop index".into(),
+        }
+    }
+
+    #[test]
+    fn failed_op_rolls_back_earlier_ops() {
+        let contacts = TableBuilder::new("contacts").build();
+        let migrations = [migration(
+            1,
+            vec![MigrationOp::CreateTable(contacts), add_column("email"), add_column("phone")],
+        )];
+        let mut executed = Vec::new();
+        let mut runner = MigrationRunner::new();
+        let err = runner
+            .migrate_with("app", &migrations, &mut |op| {
+                executed.push(label(op));
+                match op {
+                    MigrationOp::AddColumn { name, .. } if name == "phone" => {
+                        Err("disk full".into())
+                    }
+                    _ => Ok(()),
+                }
+            })
+            .unwrap_err();
+
+        assert!(err.contains("migration 1 op 2 failed"), "{}", err);
+        assert_eq!(
+            executed,
+            ["create contacts", "add email", "add phone", "drop email", "drop table contacts"]
+        );
+        assert_eq!(runner.current_version("app"), 0);
+    }
+
+    #[test]
+    fn earlier_migrations_stay_applied() {
+        let migrations = [
+            migration(1, vec![add_column("email")]),
+            migration(2, vec![add_column("bad")]),
+        ];
+        let mut runner = MigrationRunner::new();
+        let err = runner
+            .migrate_with("app", &migrations, &mut |op| match op {
+                MigrationOp::AddColumn { name, .. } if name == "bad" => Err("boom".into()),
+                _ => Ok(()),
+            })
+            .unwrap_err();
+        assert!(err.contains("migration 2 op 0 failed"), "{}", err);
+        assert_eq!(runner.current_version("app"), 1);
+    }
+
+    #[test]
+    fn irreversible_op_before_others_is_rejected_up_front() {
+        let drop = MigrationOp::DropColumn { table: "contacts".into(), name: "fax".into() };
+        let migrations = [migration(1, vec![drop, add_column("email")])];
+        let mut executed = 0;
+        let mut runner = MigrationRunner::new();
+        let err = runner
+            .migrate_with("app", &migrations, &mut |_| {
+                executed += 1;
+                Ok(())
+            })
+            .unwrap_err();
+        assert!(err.contains("op 0 is not reversible"), "{}", err);
+        assert_eq!(executed, 0);
+        assert_eq!(runner.current_version("app"), 0);
+    }
+
+    #[test]
+    fn irreversible_last_op_is_allowed() {
+        let drop = MigrationOp::DropTable("legacy".into());
+        let migrations = [migration(1, vec![add_column("email"), drop])];
+        let mut runner = MigrationRunner::new();
+        assert_eq!(runner.migrate_with("app", &migrations, &mut |_| Ok(())), Ok(1));
+        assert_eq!(runner.current_version("app"), 1);
+    }
+}
@@ -2,7 +2,11 @@
 //!
 //! Runs queries in WASM and returns serialized results to the TS layer.
 
+use std::collections::HashMap;
+
+use polykit_core::classification::Classification;
 use serde::{Deserialize, Serialize};
+use crate::schema::{ColumnType, TableDef};
 
 /// Query result returned from WASM to the TS binding layer.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,6 +14,184 @@ pub struct QueryResult {
     pub columns: Vec<String>,
     pub rows: Vec<Vec<serde_json::Value>>,
     pub row_count: usize,
+    /// Columns removed because they exceed the reader's clearance
+    #[serde(default)]
+    pub redacted: Vec<String>,
+}
+
+/// A column value checked against its schema type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedValue {
+    Null,
+    Text(String),
+    Integer(i64),
+    Real(f64),
+    Blob(Vec<u8>),
+    Boolean(bool),
+}
+
+impl TypedValue {
+    fn from_json(column: &str, value: &serde_json::Value, column_type: &ColumnType) -> Result<Self, String> {
+        let mismatch = || format!("column {}: expected {:?}, got {}", column, column_type, value);
+        if value.is_null() {
+            return Ok(TypedValue::Null);
+        }
+        match column_type {
+            ColumnType::Text => value
+                .as_str()
+                .map(|s| TypedValue::Text(s.to_string()))
+                .ok_or_else(mismatch),
+            ColumnType::Integer => value.as_i64().map(TypedValue::Integer).ok_or_else(mismatch),
+            ColumnType::Real => value.as_f64().map(TypedValue::Real).ok_or_else(mismatch),
+            ColumnType::Boolean => value.as_bool().map(TypedValue::Boolean).ok_or_else(mismatch),
+            ColumnType::Blob => value
+                .as_array()
+                .and_then(|bytes| {
+                    bytes
+                        .iter()
+                        .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+                        .collect()
+                })
+                .map(TypedValue::Blob)
+                .ok_or_else(mismatch),
+        }
+    }
+
+    /// Type for columns outside the schema (e.g., aggregates).
+    fn infer(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Bool(b) => TypedValue::Boolean(*b),
+            serde_json::Value::Number(n) => n
+                .as_i64()
+                .map(TypedValue::Integer)
+                .unwrap_or_else(|| TypedValue::Real(n.as_f64().unwrap_or_default())),
+            serde_json::Value::String(s) => TypedValue::Text(s.clone()),
+            serde_json::Value::Null => TypedValue::Null,
+            other => TypedValue::Text(other.to_string()),
+        }
+    }
+}
+
+/// A result row keyed by column name, with typed accessors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedRow {
+    values: HashMap<String, TypedValue>,
+}
+
+impl TypedRow {
+    pub fn get(&self, column: &str) -> Result<&TypedValue, String> {
+        self.values.get(column).ok_or_else(|| format!("no column {}", column))
+    }
+
+    pub fn get_str(&self, column: &str) -> Result<&str, String> {
+        match self.get(column)? {
+            TypedValue::Text(s) => Ok(s),
+            other => Err(format!("column {} is {:?}, not Text", column, other)),
+        }
+    }
+
+    pub fn get_i64(&self, column: &str) -> Result<i64, String> {
+        match self.get(column)? {
+            TypedValue::Integer(n) => Ok(*n),
+            other => Err(format!("column {} is {:?}, not Integer", column, other)),
+        }
+    }
+
+    pub fn get_f64(&self, column: &str) -> Result<f64, String> {
+        match self.get(column)? {
+            TypedValue::Real(n) => Ok(*n),
+            TypedValue::Integer(n) => Ok(*n as f64),
+            other => Err(format!("column {} is {:?}, not Real", column, other)),
+        }
+    }
+
+    pub fn get_bool(&self, column: &str) -> Result<bool, String> {
+        match self.get(column)? {
+            TypedValue::Boolean(b) => Ok(*b),
+            other => Err(format!("column {} is {:?}, not Boolean", column, other)),
+        }
+    }
+
+    pub fn get_blob(&self, column: &str) -> Result<&[u8], String> {
+        match self.get(column)? {
+            TypedValue::Blob(b) => Ok(b),
+            other => Err(format!("column {} is {:?}, not Blob", column, other)),
+        }
+    }
+
+    pub fn is_null(&self, column: &str) -> Result<bool, String> {
+        Ok(matches!(self.get(column)?, TypedValue::Null))
+    }
+}
+
+impl QueryResult {
+    /// Drop columns classified above `clearance`, listing them in
+    /// `redacted`. Columns not in the schema (e.g., aggregates) are kept.
+    pub fn redact_for(&self, table: &TableDef, clearance: Classification) -> QueryResult {
+        let hidden: Vec<bool> = self
+            .columns
+            .iter()
+            .map(|name| {
+                table
+                    .columns
+                    .iter()
+                    .find(|c| &c.name == name)
+                    .and_then(|c| c.classification)
+                    .is_some_and(|required| required > clearance)
+            })
+            .collect();
+        let visible = |values: &[serde_json::Value]| -> Vec<serde_json::Value> {
+            values.iter().zip(&hidden).filter(|(_, h)| !**h).map(|(v, _)| v.clone()).collect()
+        };
+
+        let mut redacted = self.redacted.clone();
+        let mut columns = Vec::new();
+        for (name, hide) in self.columns.iter().zip(&hidden) {
+            if *hide {
+                redacted.push(name.clone());
+            } else {
+                columns.push(name.clone());
+            }
+        }
+
+        QueryResult {
+            columns,
+            rows: self.rows.iter().map(|row| visible(row)).collect(),
+            row_count: self.row_count,
+            redacted,
+        }
+    }
+
+    /// Map rows to typed, name-keyed rows using the table schema. Fails if
+    /// a value doesn't match its column type or a non-nullable column is null.
+    pub fn typed_rows(&self, table: &TableDef) -> Result<Vec<TypedRow>, String> {
+        self.rows
+            .iter()
+            .map(|row| {
+                if row.len() != self.columns.len() {
+                    return Err(format!(
+                        "row has {} values for {} columns",
+                        row.len(),
+                        self.columns.len()
+                    ));
+                }
+                let mut values = HashMap::new();
+                for (name, value) in self.columns.iter().zip(row) {
+                    let typed = match table.columns.iter().find(|c| &c.name == name) {
+                        Some(col) => {
+                            if value.is_null() && !col.nullable {
+                                return Err(format!("column {} is not nullable", name));
+                            }
+                            TypedValue::from_json(name, value, &col.column_type)?
+                        }
+                        None => TypedValue::infer(value),
+                    };
+                    values.insert(name.clone(), typed);
+                }
+                Ok(TypedRow { values })
+            })
+            .collect()
+    }
 }
 
 /// Query builder for ESLite.
@@ -18,6 +200,10 @@ pub struct Query {
     pub table: String,
     pub select: Vec<String>,
     pub where_clauses: Vec<WhereClause>,
+    pub group_by: Vec<String>,
+    pub aggregates: Vec<Aggregate>,
+    /// Filters on aggregate results, applied after grouping
+    pub having_clauses: Vec<HavingClause>,
     pub order_by: Option<(String, Order)>,
     pub limit: Option<usize>,
     pub offset: Option<usize>,
@@ -30,10 +216,52 @@ pub enum WhereClause {
     Gt(String, serde_json::Value),
     Like(String, String),
     In(String, Vec<serde_json::Value>),
+    /// `column IN (SELECT ...)`; the subquery must yield exactly one column
+    InSubquery(String, Box<Query>),
     IsNull(String),
     IsNotNull(String),
 }
 
+/// Aggregate column. `Count(None)` is `COUNT(*)`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Aggregate {
+    Count(Option<String>),
+    Sum(String),
+    Avg(String),
+    Min(String),
+    Max(String),
+}
+
+impl Aggregate {
+    /// Result column name, e.g. `count(*)` or `sum(bytes)`.
+    pub fn alias(&self) -> String {
+        match self {
+            Aggregate::Count(None) => "count(*)".to_string(),
+            Aggregate::Count(Some(col)) => format!("count({})", col),
+            Aggregate::Sum(col) => format!("sum({})", col),
+            Aggregate::Avg(col) => format!("avg({})", col),
+            Aggregate::Min(col) => format!("min({})", col),
+            Aggregate::Max(col) => format!("max({})", col),
+        }
+    }
+}
+
+/// Post-aggregation filter. Mirrors `WhereClause` but references aggregates.
+#[derive(Debug, Clone)]
+pub enum HavingClause {
+    Eq(Aggregate, serde_json::Value),
+    Lt(Aggregate, serde_json::Value),
+    Gt(Aggregate, serde_json::Value),
+}
+
+impl HavingClause {
+    pub fn aggregate(&self) -> &Aggregate {
+        match self {
+            HavingClause::Eq(agg, _) | HavingClause::Lt(agg, _) | HavingClause::Gt(agg, _) => agg,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Order {
     Asc,
@@ -46,6 +274,9 @@ impl Query {
             table: table.to_string(),
             select: vec!["*".to_string()],
             where_clauses: Vec::new(),
+            group_by: Vec::new(),
+            aggregates: Vec::new(),
+            having_clauses: Vec::new(),
             order_by: None,
             limit: None,
             offset: None,
@@ -62,6 +293,32 @@ impl Query {
         self
     }
 
+    /// `column IN (subquery)`, executed by the host as a single statement.
+    pub fn where_in_subquery(mut self, column: &str, subquery: Query) -> Self {
+        self.where_clauses.push(WhereClause::InSubquery(column.to_string(), Box::new(subquery)));
+        self
+    }
+
+    pub fn group_by(mut self, columns: &[&str]) -> Self {
+        self.group_by = columns.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn aggregate(mut self, aggregate: Aggregate) -> Self {
+        self.aggregates.push(aggregate);
+        self
+    }
+
+    /// Filter groups on an aggregate. The aggregate is added to the
+    /// result columns if it isn't already selected.
+    pub fn having(mut self, clause: HavingClause) -> Self {
+        if !self.aggregates.contains(clause.aggregate()) {
+            self.aggregates.push(clause.aggregate().clone());
+        }
+        self.having_clauses.push(clause);
+        self
+    }
+
     pub fn order_by(mut self, column: &str, order: Order) -> Self {
         self.order_by = Some((column.to_string(), order));
         self
@@ -72,13 +329,245 @@ impl Query {
         self
     }
 
+    /// Columns the query yields: the selection, or group keys followed by
+    /// aggregate aliases when grouping/aggregating.
+    pub fn result_columns(&self) -> Vec<String> {
+        if self.group_by.is_empty() && self.aggregates.is_empty() {
+            self.select.clone()
+        } else {
+            self.group_by
+                .iter()
+                .cloned()
+                .chain(self.aggregates.iter().map(Aggregate::alias))
+                .collect()
+        }
+    }
+
+    /// Check the query's structure, including any subqueries.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.having_clauses.is_empty() && self.group_by.is_empty() {
+            return Err("HAVING requires GROUP BY".to_string());
+        }
+        for clause in &self.where_clauses {
+            if let WhereClause::InSubquery(column, subquery) = clause {
+                let columns = subquery.result_columns();
+                if columns.len() != 1 || columns[0] == "*" {
+                    return Err(format!(
+                        "subquery for {} IN must select exactly one column, got {:?}",
+                        column, columns
+                    ));
+                }
+                subquery.validate()?;
+            }
+        }
+        Ok(())
+    }
+
     /// Execute query against ESLite store (in WASM).
     pub fn execute(&self) -> Result<QueryResult, String> {
+        self.validate()?;
+
         // In production: executes against ESLite via host imports
         Ok(QueryResult {
-            columns: self.select.clone(),
+            columns: self.result_columns(),
             rows: Vec::new(),
             row_count: 0,
+            redacted: Vec::new(),
         })
     }
 }
+
+/// Insert `row` under `key`, or update it if the key already exists.
+/// One round trip instead of query-then-insert/update.
+pub fn upsert(table: &str, key: &[u8], row: serde_json::Value) -> Result<(), String> {
+    if table.is_empty() {
+        return Err("upsert requires a table name".to_string());
+    }
+    if key.is_empty() {
+        return Err("upsert requires a non-empty key".to_string());
+    }
+    // In production: host import eslite::upsert (INSERT ... ON CONFLICT DO UPDATE)
+    let _ = row;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn busy_operations() -> Query {
+        Query::from("audit_log")
+            .where_eq("org", serde_json::json!("polylabs"))
+            .group_by(&["operation"])
+            .having(HavingClause::Gt(Aggregate::Count(None), serde_json::json!(100)))
+    }
+
+    #[test]
+    fn having_is_kept_apart_from_where() {
+        let query = busy_operations();
+        assert!(matches!(
+            query.where_clauses.as_slice(),
+            [WhereClause::Eq(column, _)] if column == "org"
+        ));
+        assert!(matches!(
+            query.having_clauses.as_slice(),
+            [HavingClause::Gt(Aggregate::Count(None), limit)] if *limit == 100
+        ));
+    }
+
+    #[test]
+    fn having_selects_its_aggregate_once() {
+        let query = Query::from("audit_log")
+            .group_by(&["operation"])
+            .aggregate(Aggregate::Count(None))
+            .having(HavingClause::Gt(Aggregate::Count(None), serde_json::json!(100)));
+        assert_eq!(query.aggregates.len(), 1);
+        assert_eq!(query.execute().unwrap().columns, vec!["operation", "count(*)"]);
+        assert_eq!(busy_operations().result_columns(), vec!["operation", "count(*)"]);
+    }
+
+    #[test]
+    fn having_requires_group_by() {
+        let query = Query::from("audit_log")
+            .having(HavingClause::Gt(Aggregate::Sum("bytes".to_string()), serde_json::json!(0)));
+        assert!(query.execute().is_err());
+    }
+
+    fn people() -> TableDef {
+        crate::schema::TableBuilder::new("people")
+            .column("name", ColumnType::Text)
+            .done()
+            .column("age", ColumnType::Integer)
+            .done()
+            .column("nickname", ColumnType::Text)
+            .nullable()
+            .done()
+            .build()
+    }
+
+    fn result(columns: &[&str], rows: Vec<Vec<serde_json::Value>>) -> QueryResult {
+        QueryResult {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            row_count: rows.len(),
+            rows,
+            redacted: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn typed_rows_follow_column_names() {
+        // Column order differs from the schema
+        let result = result(
+            &["age", "nickname", "name"],
+            vec![vec![serde_json::json!(41), serde_json::Value::Null, serde_json::json!("Ada")]],
+        );
+        let rows = result.typed_rows(&people()).unwrap();
+        assert_eq!(rows[0].get_str("name").unwrap(), "Ada");
+        assert_eq!(rows[0].get_i64("age").unwrap(), 41);
+        assert!(rows[0].is_null("nickname").unwrap());
+    }
+
+    #[test]
+    fn mismatched_accessor_is_an_error() {
+        let row = vec![serde_json::json!("Ada"), serde_json::json!(41)];
+        let result = result(&["name", "age"], vec![row]);
+        let rows = result.typed_rows(&people()).unwrap();
+        assert!(rows[0].get_i64("name").is_err());
+        assert!(rows[0].get_str("missing").is_err());
+    }
+
+    #[test]
+    fn values_are_checked_against_the_schema() {
+        let wrong_type = result(&["age"], vec![vec![serde_json::json!("forty")]]);
+        assert!(wrong_type.typed_rows(&people()).is_err());
+
+        let null_name = result(&["name"], vec![vec![serde_json::Value::Null]]);
+        assert!(null_name.typed_rows(&people()).is_err());
+    }
+
+    #[test]
+    fn aggregate_columns_are_inferred() {
+        let result = result(&["count(*)"], vec![vec![serde_json::json!(12)]]);
+        let rows = result.typed_rows(&people()).unwrap();
+        assert_eq!(rows[0].get_i64("count(*)").unwrap(), 12);
+    }
+
+    #[test]
+    fn in_subquery_keeps_its_structure() {
+        let banned = Query::from("banned").select(&["user_id"]);
+        let query = Query::from("users").select(&["id", "name"]).where_in_subquery("id", banned);
+        match query.where_clauses.as_slice() {
+            [WhereClause::InSubquery(column, subquery)] => {
+                assert_eq!(column, "id");
+                assert_eq!(subquery.table, "banned");
+                assert_eq!(subquery.result_columns(), ["user_id"]);
+            }
+            other => panic!("unexpected clauses: {:?}", other),
+        }
+        assert_eq!(query.execute().unwrap().columns, ["id", "name"]);
+    }
+
+    #[test]
+    fn multi_column_subquery_is_rejected() {
+        let banned = Query::from("banned").select(&["user_id", "reason"]);
+        let query = Query::from("users").where_in_subquery("id", banned);
+        assert!(query.execute().unwrap_err().contains("exactly one column"));
+
+        let wildcard = Query::from("users").where_in_subquery("id", Query::from("banned"));
+        assert!(wildcard.validate().is_err());
+    }
+
+    #[test]
+    fn aggregate_subquery_counts_as_one_column() {
+        let top = Query::from("orders").aggregate(Aggregate::Max("user_id".to_string()));
+        assert!(Query::from("users").where_in_subquery("id", top).validate().is_ok());
+    }
+
+    fn patients() -> TableDef {
+        crate::schema::TableBuilder::new("patients")
+            .column("id", ColumnType::Integer)
+            .done()
+            .column("ward", ColumnType::Text)
+            .classified(Classification::Internal)
+            .done()
+            .column("diagnosis", ColumnType::Text)
+            .classified(Classification::Confidential)
+            .done()
+            .build()
+    }
+
+    fn rows(values: serde_json::Value) -> Vec<Vec<serde_json::Value>> {
+        serde_json::from_value(values).unwrap()
+    }
+
+    #[test]
+    fn columns_above_clearance_are_redacted() {
+        let full = result(
+            &["id", "ward", "diagnosis"],
+            rows(serde_json::json!([[1, "north", "asthma"], [2, "south", "flu"]])),
+        );
+        let redacted = full.redact_for(&patients(), Classification::Internal);
+
+        assert_eq!(redacted.columns, ["id", "ward"]);
+        assert_eq!(redacted.rows, rows(serde_json::json!([[1, "north"], [2, "south"]])));
+        assert_eq!(redacted.redacted, ["diagnosis"]);
+        assert_eq!(redacted.row_count, 2);
+    }
+
+    #[test]
+    fn sufficient_clearance_sees_every_column() {
+        let full = result(&["id", "diagnosis"], rows(serde_json::json!([[1, "asthma"]])));
+        let visible = full.redact_for(&patients(), Classification::Confidential);
+        assert_eq!(visible.columns, full.columns);
+        assert_eq!(visible.rows, full.rows);
+        assert!(visible.redacted.is_empty());
+    }
+
+    #[test]
+    fn columns_outside_the_schema_are_kept() {
+        let counts = result(&["ward", "count(*)"], rows(serde_json::json!([["north", 3]])));
+        let visible = counts.redact_for(&patients(), Classification::Public);
+        assert_eq!(visible.columns, ["count(*)"]);
+        assert_eq!(visible.redacted, ["ward"]);
+    }
+}
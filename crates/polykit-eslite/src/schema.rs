@@ -2,6 +2,7 @@
 //!
 //! Provides a builder API for defining ESLite table schemas.
 
+use polykit_core::classification::Classification;
 use serde::{Deserialize, Serialize};
 
 /// Table definition.
@@ -22,6 +23,10 @@ pub struct ColumnDef {
     pub indexed: bool,
     pub nullable: bool,
     pub default: Option<String>,
+    /// Minimum clearance to read this column; unclassified columns are
+    /// visible to everyone
+    #[serde(default)]
+    pub classification: Option<Classification>,
 }
 
 /// Column data types.
@@ -69,6 +74,7 @@ impl TableBuilder {
                 indexed: false,
                 nullable: false,
                 default: None,
+                classification: None,
             },
         }
     }
@@ -116,6 +122,11 @@ impl ColumnBuilder {
         self
     }
 
+    pub fn classified(mut self, classification: Classification) -> Self {
+        self.def.classification = Some(classification);
+        self
+    }
+
     pub fn done(mut self) -> TableBuilder {
         self.table.columns.push(self.def);
         self.table
@@ -4,8 +4,20 @@
 //! lex stream state. Uses the snapshot+delta pattern:
 //! 1. Initial load: subscribe to {topic}.snapshot → full state
 //! 2. Ongoing: subscribe to {topic}.delta → incremental updates
+//!
+//! Tables are assigned to one or more regions of the lex hierarchy
+//! (global, or a regional lex that rolls up to it). A table assigned to
+//! several regions fans their streams into one local table, with each
+//! region's sequence tracked independently.
 
 use serde::{Deserialize, Serialize};
+use polykit_core::clock::{Clock, HostClock};
+use polykit_core::crypto;
+use polykit_core::error::MeteringDimension;
+use polykit_core::versioned::WireVersion;
+
+/// Current snapshot format version.
+pub const SNAPSHOT_VERSION: u16 = 1;
 
 /// Sync state for a table.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,9 +32,50 @@ pub enum SyncState {
     Error(String),
 }
 
+/// Lex region a table syncs from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Region {
+    /// esn/global — org-wide aggregates
+    #[default]
+    Global,
+    /// esn/region/us — US data residency
+    Us,
+    /// esn/region/eu — EU data residency
+    Eu,
+}
+
+impl Region {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Region::Global => "global",
+            Region::Us => "us",
+            Region::Eu => "eu",
+        }
+    }
+
+    /// Lex path for an org in this region (e.g., `esn/region/us/org/polylabs`).
+    pub fn lex_path(&self, org: &str) -> String {
+        match self {
+            Region::Global => format!("esn/global/org/{}", org),
+            _ => format!("esn/region/{}/org/{}", self.as_str(), org),
+        }
+    }
+}
+
+/// Snapshot and delta topics for one region of a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionTopics {
+    pub region: Region,
+    pub snapshot: String,
+    pub delta: String,
+}
+
 /// A delta update from a lex stream.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Delta {
+    /// Region stream this delta came from
+    #[serde(default)]
+    pub region: Region,
     pub sequence: u64,
     pub operation: DeltaOp,
     pub table: String,
@@ -30,39 +83,462 @@ pub struct Delta {
     pub data: Option<Vec<u8>>,
 }
 
+/// A signed, versioned full-state snapshot from a lex stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u16,
+    pub region: Region,
+    pub table: String,
+    pub sequence: u64,
+    pub data: Vec<u8>,
+    /// SHA3-256 of `data`
+    pub content_hash: [u8; 32],
+    /// ML-DSA-87 signature by the source over `signed_bytes()`
+    pub signature: Vec<u8>,
+}
+
+impl Snapshot {
+    /// Build and sign a global snapshot (source side).
+    pub fn sign(table: &str, data: Vec<u8>, sequence: u64, secret_key: &[u8]) -> Result<Self, String> {
+        Self::sign_in_region(Region::Global, table, data, sequence, secret_key)
+    }
+
+    /// Build and sign a snapshot for a regional lex (source side).
+    pub fn sign_in_region(
+        region: Region,
+        table: &str,
+        data: Vec<u8>,
+        sequence: u64,
+        secret_key: &[u8],
+    ) -> Result<Self, String> {
+        require_backend()?;
+        let mut snapshot = Snapshot {
+            version: SNAPSHOT_VERSION,
+            region,
+            table: table.to_string(),
+            sequence,
+            content_hash: crypto::sha3_256(&data),
+            data,
+            signature: Vec::new(),
+        };
+        snapshot.signature = crypto::sign(secret_key, &snapshot.signed_bytes())
+            .map_err(|e| format!("snapshot signing failed: {:?}", e))?;
+        Ok(snapshot)
+    }
+
+    /// Bytes covered by the signature: version, region, table, sequence,
+    /// content hash.
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&self.version.to_be_bytes());
+        out.push(self.region.as_str().len() as u8);
+        out.extend_from_slice(self.region.as_str().as_bytes());
+        out.extend_from_slice(&(self.table.len() as u32).to_be_bytes());
+        out.extend_from_slice(self.table.as_bytes());
+        out.extend_from_slice(&self.sequence.to_be_bytes());
+        out.extend_from_slice(&self.content_hash);
+        out
+    }
+
+    /// Check version, content hash, and source signature. Without the host
+    /// crypto backend no signature can be checked, so every snapshot is
+    /// refused with that reason rather than reported as forged.
+    pub fn verify(&self, source_public_key: &[u8]) -> Result<(), String> {
+        require_backend()?;
+        if self.version != SNAPSHOT_VERSION {
+            return Err(format!("unsupported snapshot version {}", self.version));
+        }
+        if crypto::sha3_256(&self.data) != self.content_hash {
+            return Err("snapshot content hash mismatch".to_string());
+        }
+        match crypto::verify(source_public_key, &self.signed_bytes(), &self.signature) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("snapshot signature invalid".to_string()),
+            Err(e) => Err(format!("snapshot signature check failed: {:?}", e)),
+        }
+    }
+}
+
+fn require_backend() -> Result<(), String> {
+    if !crypto::backend_available() {
+        return Err("snapshot signatures unavailable: crypto backend not bound".to_string());
+    }
+    Ok(())
+}
+
+impl WireVersion for Delta {
+    const VERSION: u16 = 1;
+}
+
+impl WireVersion for Snapshot {
+    const VERSION: u16 = SNAPSHOT_VERSION;
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DeltaOp {
     Insert,
     Update,
     Delete,
+    /// Insert if absent, else update. Replays of an applied upsert are no-ops.
+    Upsert,
+}
+
+/// Build the delta for a local row change: `old` absent → Insert, both
+/// present → Update, `new` absent → Delete (also when both are absent).
+/// Deltas are built for the global region; set `region` for regional tables.
+pub fn make_delta(
+    table: &str,
+    key: &[u8],
+    old: Option<&[u8]>,
+    new: Option<&[u8]>,
+    next_sequence: u64,
+) -> Delta {
+    let operation = match (old, new) {
+        (None, Some(_)) => DeltaOp::Insert,
+        (Some(_), Some(_)) => DeltaOp::Update,
+        (_, None) => DeltaOp::Delete,
+    };
+    Delta {
+        region: Region::Global,
+        sequence: next_sequence,
+        operation,
+        table: table.to_string(),
+        key: key.to_vec(),
+        data: new.map(|d| d.to_vec()),
+    }
+}
+
+/// Compact a delta queue before emission. Per (table, region, key):
+/// a run of updates collapses to the last one, an insert followed by
+/// updates becomes one insert with the final data, and an insert later
+/// deleted disappears. A merged delta takes the position of its last write,
+/// so different keys keep their relative order. Sequences are renumbered
+/// per (table, region) from the lowest input sequence, keeping them
+/// contiguous for `apply_delta` even when the first delta was dropped.
+pub fn compact_deltas(deltas: Vec<Delta>) -> Vec<Delta> {
+    let mut next_sequence: std::collections::HashMap<(String, Region), u64> =
+        std::collections::HashMap::new();
+    for delta in &deltas {
+        next_sequence
+            .entry((delta.table.clone(), delta.region))
+            .and_modify(|min| *min = (*min).min(delta.sequence))
+            .or_insert(delta.sequence);
+    }
+
+    let mut slots: Vec<Option<Delta>> = Vec::with_capacity(deltas.len());
+    let mut latest: std::collections::HashMap<(String, Region, Vec<u8>), usize> =
+        std::collections::HashMap::new();
+
+    for delta in deltas {
+        let id = (delta.table.clone(), delta.region, delta.key.clone());
+        let prior = latest
+            .get(&id)
+            .and_then(|&i| slots[i].as_ref().map(|d| (i, d.operation.clone())));
+        let merged = match prior {
+            Some((i, prior_op)) => match (prior_op, &delta.operation) {
+                (DeltaOp::Insert, DeltaOp::Delete) => {
+                    slots[i] = None;
+                    latest.remove(&id);
+                    continue;
+                }
+                (DeltaOp::Insert, DeltaOp::Update | DeltaOp::Upsert) => {
+                    slots[i] = None;
+                    Delta { operation: DeltaOp::Insert, ..delta }
+                }
+                (DeltaOp::Update, DeltaOp::Update | DeltaOp::Upsert) => {
+                    slots[i] = None;
+                    Delta { operation: DeltaOp::Update, ..delta }
+                }
+                (DeltaOp::Upsert, DeltaOp::Update | DeltaOp::Upsert)
+                | (DeltaOp::Update | DeltaOp::Upsert, DeltaOp::Delete) => {
+                    slots[i] = None;
+                    delta
+                }
+                // Delete → re-insert, or an insert over a live key: keep both
+                _ => delta,
+            },
+            None => delta,
+        };
+        latest.insert(id, slots.len());
+        slots.push(Some(merged));
+    }
+
+    slots
+        .into_iter()
+        .flatten()
+        .map(|mut delta| {
+            let next = next_sequence
+                .get_mut(&(delta.table.clone(), delta.region))
+                .expect("every input (table, region) has a starting sequence");
+            delta.sequence = *next;
+            *next += 1;
+            delta
+        })
+        .collect()
+}
+
+/// Receives byte counts for sync traffic so the Bandwidth and Storage
+/// dimensions include it.
+pub trait MeteringSink {
+    fn record(&mut self, user_id: &str, dimension: MeteringDimension, bytes: u64);
+}
+
+impl<F: FnMut(&str, MeteringDimension, u64)> MeteringSink for F {
+    fn record(&mut self, user_id: &str, dimension: MeteringDimension, bytes: u64) {
+        self(user_id, dimension, bytes)
+    }
+}
+
+/// How concurrent writes to one key from different regions of a fan-in
+/// table are resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ConflictPolicy {
+    /// The most recently applied write wins
+    #[default]
+    LastWriteWins,
+    /// A write from the table's home region is never overwritten by
+    /// another region's
+    HomeRegionWins,
+}
+
+/// Record of a resolved conflict. The overwritten value is referenced by
+/// hash only, so the audit trail doesn't retain it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConflictRecord {
+    pub table: String,
+    pub key: Vec<u8>,
+    pub winning_region: Region,
+    pub winning_sequence: u64,
+    pub losing_region: Region,
+    pub losing_sequence: u64,
+    /// Hex SHA3-256 of the losing value (`None` if the losing write was a delete)
+    pub losing_data_hash: Option<String>,
+    pub policy: ConflictPolicy,
+}
+
+/// Receives conflict records as they're resolved.
+pub trait ConflictSink {
+    fn record(&mut self, record: ConflictRecord);
+}
+
+impl<F: FnMut(ConflictRecord)> ConflictSink for F {
+    fn record(&mut self, record: ConflictRecord) {
+        self(record)
+    }
+}
+
+/// Last write applied to a key of a fan-in table.
+struct LastWrite {
+    region: Region,
+    sequence: u64,
+    data_hash: Option<[u8; 32]>,
 }
 
 /// Sync manager for a set of ESLite tables.
 pub struct SyncManager {
-    states: std::collections::HashMap<String, SyncState>,
+    /// Sync state per (table, region)
+    states: std::collections::HashMap<(String, Region), SyncState>,
+    /// Regions each table syncs from; the first is its home region
+    regions: std::collections::HashMap<String, Vec<Region>>,
+    /// When each (table, region) last applied a snapshot or delta
+    synced_at_ms: std::collections::HashMap<(String, Region), u64>,
+    clock: Box<dyn Clock>,
+    /// Where sync bytes are metered, and the user they're billed to
+    metering: Option<(String, Box<dyn MeteringSink>)>,
+    conflict_policy: ConflictPolicy,
+    conflict_sink: Option<Box<dyn ConflictSink>>,
+    /// Last write per (table, key), kept for fan-in tables only
+    last_writes: std::collections::HashMap<(String, Vec<u8>), LastWrite>,
 }
 
 impl SyncManager {
     pub fn new() -> Self {
+        Self::with_clock(Box::new(HostClock))
+    }
+
+    pub fn with_clock(clock: Box<dyn Clock>) -> Self {
         Self {
             states: std::collections::HashMap::new(),
+            regions: std::collections::HashMap::new(),
+            synced_at_ms: std::collections::HashMap::new(),
+            clock,
+            metering: None,
+            conflict_policy: ConflictPolicy::default(),
+            conflict_sink: None,
+            last_writes: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Resolve cross-region write conflicts on fan-in tables with `policy`,
+    /// reporting each one to `sink`.
+    pub fn with_conflict_policy(
+        mut self,
+        policy: ConflictPolicy,
+        sink: Box<dyn ConflictSink>,
+    ) -> Self {
+        self.conflict_policy = policy;
+        self.conflict_sink = Some(sink);
+        self
+    }
+
+    /// Track the write and resolve it against the key's previous write from
+    /// another region. Returns whether the delta's value should be applied.
+    fn resolve_write(&mut self, delta: &Delta) -> bool {
+        if self.regions(&delta.table).len() < 2 {
+            return true;
+        }
+        let incoming = LastWrite {
+            region: delta.region,
+            sequence: delta.sequence,
+            data_hash: delta.data.as_ref().map(|d| crypto::sha3_256(d)),
+        };
+        let row = (delta.table.clone(), delta.key.clone());
+        let previous = match self.last_writes.get(&row) {
+            Some(previous) if previous.region != delta.region => previous,
+            _ => {
+                self.last_writes.insert(row, incoming);
+                return true;
+            }
+        };
+
+        let home = self.regions(&delta.table)[0];
+        let incoming_wins = match self.conflict_policy {
+            ConflictPolicy::LastWriteWins => true,
+            ConflictPolicy::HomeRegionWins => previous.region != home,
+        };
+        let (winner, loser) =
+            if incoming_wins { (&incoming, previous) } else { (previous, &incoming) };
+        let record = ConflictRecord {
+            table: delta.table.clone(),
+            key: delta.key.clone(),
+            winning_region: winner.region,
+            winning_sequence: winner.sequence,
+            losing_region: loser.region,
+            losing_sequence: loser.sequence,
+            losing_data_hash: loser
+                .data_hash
+                .map(|hash| hash.iter().map(|b| format!("{:02x}", b)).collect()),
+            policy: self.conflict_policy,
+        };
+        if let Some(sink) = &mut self.conflict_sink {
+            sink.record(record);
+        }
+        if incoming_wins {
+            self.last_writes.insert(row, incoming);
         }
+        incoming_wins
     }
 
-    /// Register a table for sync.
+    /// Meter sync traffic to `user_id`: every applied snapshot or delta
+    /// records its payload bytes as Bandwidth, and the bytes it persists
+    /// as Storage.
+    pub fn with_metering(mut self, user_id: &str, sink: Box<dyn MeteringSink>) -> Self {
+        self.metering = Some((user_id.to_string(), sink));
+        self
+    }
+
+    fn meter(&mut self, bandwidth: usize, storage: usize) {
+        if let Some((user_id, sink)) = &mut self.metering {
+            sink.record(user_id, MeteringDimension::Bandwidth, bandwidth as u64);
+            if storage > 0 {
+                sink.record(user_id, MeteringDimension::Storage, storage as u64);
+            }
+        }
+    }
+
+    /// Register a table for sync from the global lex.
     pub fn register(&mut self, table: &str) {
-        self.states.insert(table.to_string(), SyncState::Unsynced);
+        self.assign_regions(table, &[Region::Global]);
+    }
+
+    /// Register a table for sync from a single regional lex.
+    pub fn register_in_region(&mut self, table: &str, region: Region) {
+        self.assign_regions(table, &[region]);
+    }
+
+    /// Register a table that merges several regional streams. Each region
+    /// keeps its own sequence; the first is the table's home region.
+    pub fn register_fan_in(&mut self, table: &str, regions: &[Region]) -> Result<(), String> {
+        if regions.is_empty() {
+            return Err(format!("table {} needs at least one region", table));
+        }
+        self.assign_regions(table, regions);
+        Ok(())
+    }
+
+    fn assign_regions(&mut self, table: &str, regions: &[Region]) {
+        for region in regions {
+            self.states.insert((table.to_string(), *region), SyncState::Unsynced);
+        }
+        self.regions.insert(table.to_string(), regions.to_vec());
+    }
+
+    /// Regions a table syncs from (global if unregistered).
+    pub fn regions(&self, table: &str) -> &[Region] {
+        self.regions.get(table).map(|r| r.as_slice()).unwrap_or(&[Region::Global])
     }
 
-    /// Apply a snapshot (full state replace).
-    pub fn apply_snapshot(&mut self, table: &str, _data: &[u8], sequence: u64) {
-        self.states.insert(table.to_string(), SyncState::Synced { last_sequence: sequence });
+    /// Snapshot/delta topics to subscribe to for a table.
+    pub fn topics(&self, table: &str, org: &str) -> Vec<RegionTopics> {
+        self.regions(table)
+            .iter()
+            .map(|region| {
+                let base = format!("{}/{}", region.lex_path(org), table);
+                RegionTopics {
+                    region: *region,
+                    snapshot: format!("{}.snapshot", base),
+                    delta: format!("{}.delta", base),
+                }
+            })
+            .collect()
+    }
+
+    fn check_region(&self, table: &str, region: Region) -> Result<(), String> {
+        if self.regions.contains_key(table) && !self.regions(table).contains(&region) {
+            return Err(format!("table {} does not sync from region {}", table, region.as_str()));
+        }
+        Ok(())
+    }
+
+    /// Apply a snapshot (full state replace) after verifying its hash and
+    /// source signature. Snapshots older than the current state are rejected.
+    pub fn apply_snapshot(&mut self, snapshot: &Snapshot, source_public_key: &[u8]) -> Result<(), String> {
+        snapshot.verify(source_public_key)?;
+        self.check_region(&snapshot.table, snapshot.region)?;
+
+        if let SyncState::Synced { last_sequence } | SyncState::Paused { last_sequence } =
+            self.region_state(&snapshot.table, snapshot.region)
+        {
+            if snapshot.sequence < *last_sequence {
+                return Err(format!(
+                    "stale snapshot: sequence {} is behind current {}",
+                    snapshot.sequence, last_sequence
+                ));
+            }
+        }
+
+        // In production: replace this region's rows via host import eslite::load_snapshot
+        let key = (snapshot.table.clone(), snapshot.region);
+        self.synced_at_ms.insert(key.clone(), self.clock.now_ms());
+        self.states.insert(key, SyncState::Synced { last_sequence: snapshot.sequence });
+        self.meter(snapshot.data.len(), snapshot.data.len());
+        Ok(())
     }
 
-    /// Apply a delta (incremental update).
+    /// Apply a delta (incremental update). A replayed upsert is metered as
+    /// bandwidth only, since nothing new is persisted.
     pub fn apply_delta(&mut self, delta: &Delta) -> Result<(), String> {
-        match self.states.get(&delta.table) {
+        self.check_region(&delta.table, delta.region)?;
+
+        let payload = delta.key.len() + delta.data.as_ref().map_or(0, |d| d.len());
+        let key = (delta.table.clone(), delta.region);
+        match self.states.get(&key) {
             Some(SyncState::Synced { last_sequence }) => {
+                if matches!(delta.operation, DeltaOp::Upsert) && delta.sequence <= *last_sequence {
+                    // Already applied — upserts are idempotent on replay
+                    self.meter(payload, 0);
+                    return Ok(());
+                }
                 if delta.sequence != last_sequence + 1 {
                     return Err(format!(
                         "sequence gap: expected {}, got {}",
@@ -70,18 +546,478 @@ impl SyncManager {
                         delta.sequence
                     ));
                 }
-                self.states.insert(
-                    delta.table.clone(),
-                    SyncState::Synced { last_sequence: delta.sequence },
-                );
+                self.synced_at_ms.insert(key.clone(), self.clock.now_ms());
+                self.states.insert(key, SyncState::Synced { last_sequence: delta.sequence });
+                // A losing write still advances its region's sequence
+                let stored = if self.resolve_write(delta) {
+                    // In production: write the row via host import eslite::apply_delta
+                    delta.data.as_ref().map_or(0, |d| d.len())
+                } else {
+                    0
+                };
+                self.meter(payload, stored);
                 Ok(())
             }
             _ => Err("table not synced".to_string()),
         }
     }
 
-    /// Get sync state for a table.
+    /// Milliseconds since one region of a table last applied an update;
+    /// `None` if it never has.
+    pub fn staleness_ms(&self, table: &str, region: Region) -> Option<u64> {
+        self.synced_at_ms
+            .get(&(table.to_string(), region))
+            .map(|at| self.clock.now_ms().saturating_sub(*at))
+    }
+
+    /// Get sync state for a table's home region.
     pub fn state(&self, table: &str) -> &SyncState {
-        self.states.get(table).unwrap_or(&SyncState::Unsynced)
+        self.region_state(table, self.regions(table)[0])
+    }
+
+    /// Get sync state for one region of a table.
+    pub fn region_state(&self, table: &str, region: Region) -> &SyncState {
+        self.states
+            .get(&(table.to_string(), region))
+            .unwrap_or(&SyncState::Unsynced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polykit_core::crypto::SeededRng;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Metered = Rc<RefCell<Vec<(MeteringDimension, u64)>>>;
+
+    fn source_keys() -> (Vec<u8>, Vec<u8>) {
+        let (public_key, secret_key) = crypto::signing_keypair(&mut SeededRng::seed_from_u64(7));
+        (public_key, secret_key.to_vec())
+    }
+
+    /// Sync `region` of `table` to `sequence` from a signed snapshot.
+    fn synced_in(manager: &mut SyncManager, table: &str, region: Region, sequence: u64) {
+        let (public_key, secret_key) = source_keys();
+        let snapshot =
+            Snapshot::sign_in_region(region, table, b"rows".to_vec(), sequence, &secret_key);
+        manager.apply_snapshot(&snapshot.unwrap(), &public_key).unwrap();
+    }
+
+    fn metered_manager() -> (SyncManager, Metered) {
+        let metered: Metered = Rc::default();
+        let sink = metered.clone();
+        let manager = SyncManager::new().with_metering(
+            "user-1",
+            Box::new(move |_: &str, dimension, bytes| sink.borrow_mut().push((dimension, bytes))),
+        );
+        (manager, metered)
+    }
+
+    fn stored_bytes(metered: &Metered) -> u64 {
+        metered
+            .borrow()
+            .iter()
+            .filter(|(dimension, _)| matches!(dimension, MeteringDimension::Storage))
+            .map(|(_, bytes)| bytes)
+            .sum()
+    }
+
+    fn delta(operation: DeltaOp, sequence: u64, key: &[u8]) -> Delta {
+        Delta {
+            region: Region::Global,
+            sequence,
+            operation,
+            table: "contacts".to_string(),
+            key: key.to_vec(),
+            data: Some(b"row".to_vec()),
+        }
+    }
+
+    #[test]
+    fn replayed_upsert_is_a_no_op() {
+        let (mut manager, metered) = metered_manager();
+        manager.register("contacts");
+        synced_in(&mut manager, "contacts", Region::Global, 10);
+        let before = stored_bytes(&metered);
+
+        let upsert = delta(DeltaOp::Upsert, 11, b"k1");
+        manager.apply_delta(&upsert).unwrap();
+        manager.apply_delta(&upsert).unwrap();
+
+        // One row written: the replay persists nothing
+        assert_eq!(stored_bytes(&metered) - before, 3);
+        assert!(matches!(manager.state("contacts"), SyncState::Synced { last_sequence: 11 }));
+    }
+
+    #[test]
+    fn replayed_insert_is_rejected() {
+        let mut manager = SyncManager::new();
+        manager.register("contacts");
+        synced_in(&mut manager, "contacts", Region::Global, 10);
+        let insert = delta(DeltaOp::Insert, 11, b"k1");
+        manager.apply_delta(&insert).unwrap();
+        assert!(manager.apply_delta(&insert).is_err());
+    }
+
+    #[test]
+    fn upsert_requires_table_and_key() {
+        assert!(crate::query::upsert("contacts", b"k1", serde_json::json!({})).is_ok());
+        assert!(crate::query::upsert("", b"k1", serde_json::json!({})).is_err());
+        assert!(crate::query::upsert("contacts", b"", serde_json::json!({})).is_err());
+    }
+
+    #[test]
+    fn regional_table_tracks_its_own_sequence() {
+        let mut manager = SyncManager::new();
+        manager.register_in_region("patients", Region::Us);
+        manager.register("metrics");
+        synced_in(&mut manager, "patients", Region::Us, 40);
+        synced_in(&mut manager, "metrics", Region::Global, 7);
+
+        let mut us_delta = delta(DeltaOp::Insert, 41, b"p1");
+        us_delta.table = "patients".to_string();
+        us_delta.region = Region::Us;
+        manager.apply_delta(&us_delta).unwrap();
+
+        assert!(matches!(manager.state("patients"), SyncState::Synced { last_sequence: 41 }));
+        assert!(matches!(manager.state("metrics"), SyncState::Synced { last_sequence: 7 }));
+
+        let topics = manager.topics("patients", "polylabs");
+        assert_eq!(topics.len(), 1);
+        assert_eq!(topics[0].delta, "esn/region/us/org/polylabs/patients.delta");
+    }
+
+    #[test]
+    fn regional_table_rejects_other_regions() {
+        let mut manager = SyncManager::new();
+        manager.register_in_region("patients", Region::Us);
+        synced_in(&mut manager, "patients", Region::Us, 40);
+        let mut eu_delta = delta(DeltaOp::Insert, 41, b"p1");
+        eu_delta.table = "patients".to_string();
+        eu_delta.region = Region::Eu;
+        assert!(manager.apply_delta(&eu_delta).is_err());
+    }
+
+    #[test]
+    fn fan_in_keeps_sequences_per_region() {
+        let mut manager = SyncManager::new();
+        manager.register_fan_in("orders", &[Region::Us, Region::Eu]).unwrap();
+        synced_in(&mut manager, "orders", Region::Us, 10);
+        synced_in(&mut manager, "orders", Region::Eu, 500);
+
+        let mut eu_delta = delta(DeltaOp::Insert, 501, b"o1");
+        eu_delta.table = "orders".to_string();
+        eu_delta.region = Region::Eu;
+        manager.apply_delta(&eu_delta).unwrap();
+
+        assert!(matches!(manager.state("orders"), SyncState::Synced { last_sequence: 10 }));
+        assert!(matches!(
+            manager.region_state("orders", Region::Eu),
+            SyncState::Synced { last_sequence: 501 }
+        ));
+    }
+
+    #[test]
+    fn fan_in_without_regions_is_rejected() {
+        let mut manager = SyncManager::new();
+        assert!(manager.register_fan_in("orders", &[]).is_err());
+        assert!(matches!(manager.state("orders"), SyncState::Unsynced));
+    }
+
+    #[test]
+    fn make_delta_infers_insert() {
+        let delta = make_delta("contacts", b"k1", None, Some(b"new"), 5);
+        assert!(matches!(delta.operation, DeltaOp::Insert));
+        assert_eq!(delta.data.as_deref(), Some(&b"new"[..]));
+        assert_eq!(delta.sequence, 5);
+        assert_eq!(delta.region, Region::Global);
+    }
+
+    #[test]
+    fn make_delta_infers_update() {
+        let delta = make_delta("contacts", b"k1", Some(b"old"), Some(b"new"), 6);
+        assert!(matches!(delta.operation, DeltaOp::Update));
+        assert_eq!(delta.data.as_deref(), Some(&b"new"[..]));
+    }
+
+    #[test]
+    fn make_delta_infers_delete() {
+        let delta = make_delta("contacts", b"k1", Some(b"old"), None, 7);
+        assert!(matches!(delta.operation, DeltaOp::Delete));
+        assert_eq!(delta.data, None);
+        assert_eq!(delta.key, b"k1");
+    }
+
+    #[test]
+    fn versioned_delta_round_trips() {
+        let original = delta(DeltaOp::Update, 7, b"k1");
+        let encoded = polykit_core::versioned::encode(&original).unwrap();
+        assert!(encoded.starts_with("{\"v\":1,"));
+
+        let decoded: Delta = polykit_core::versioned::decode(&encoded).unwrap();
+        assert_eq!(decoded.sequence, 7);
+        assert_eq!(decoded.key, b"k1");
+        assert_eq!(decoded.data, original.data);
+        assert!(matches!(decoded.operation, DeltaOp::Update));
+    }
+
+    #[test]
+    fn newer_delta_version_is_rejected() {
+        let encoded = polykit_core::versioned::encode(&delta(DeltaOp::Insert, 1, b"k1")).unwrap();
+        let future = encoded.replacen("{\"v\":1,", "{\"v\":2,", 1);
+        let err = polykit_core::versioned::decode::<Delta>(&future).unwrap_err();
+        assert!(err.to_string().contains("schema v2"), "{}", err);
+    }
+
+    fn compacted(deltas: Vec<Delta>) -> Vec<(u64, Vec<u8>, DeltaOp)> {
+        compact_deltas(deltas).into_iter().map(|d| (d.sequence, d.key, d.operation)).collect()
+    }
+
+    #[test]
+    fn update_run_collapses_to_last_write() {
+        let mut last = delta(DeltaOp::Update, 3, b"k1");
+        last.data = Some(b"final".to_vec());
+        let out = compact_deltas(vec![
+            delta(DeltaOp::Update, 1, b"k1"),
+            delta(DeltaOp::Update, 2, b"k1"),
+            last,
+        ]);
+        assert_eq!(out.len(), 1);
+        assert_eq!((out[0].sequence, out[0].data.as_deref()), (1, Some(&b"final"[..])));
+        assert!(matches!(out[0].operation, DeltaOp::Update));
+    }
+
+    #[test]
+    fn insert_then_delete_cancels_out() {
+        let out = compact_deltas(vec![
+            delta(DeltaOp::Insert, 1, b"k1"),
+            delta(DeltaOp::Delete, 2, b"k1"),
+        ]);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn survivors_renumber_from_lowest_input_sequence() {
+        let out = compacted(vec![
+            delta(DeltaOp::Insert, 5, b"k1"),
+            delta(DeltaOp::Delete, 6, b"k1"),
+            delta(DeltaOp::Update, 7, b"k2"),
+        ]);
+        assert_eq!(out.len(), 1);
+        assert_eq!((out[0].0, out[0].1.as_slice()), (5, &b"k2"[..]));
+    }
+
+    #[test]
+    fn interleaved_keys_keep_order_and_stay_contiguous() {
+        let out = compacted(vec![
+            delta(DeltaOp::Update, 5, b"k1"),
+            delta(DeltaOp::Update, 6, b"k2"),
+            delta(DeltaOp::Update, 7, b"k1"),
+        ]);
+        let keys: Vec<(u64, &[u8])> = out.iter().map(|(s, k, _)| (*s, k.as_slice())).collect();
+        assert_eq!(keys, [(5, &b"k2"[..]), (6, &b"k1"[..])]);
+    }
+
+    #[test]
+    fn compacted_queue_applies_after_synced_state() {
+        let mut manager = SyncManager::new();
+        manager.register("contacts");
+        synced_in(&mut manager, "contacts", Region::Global, 4);
+        let queue = compact_deltas(vec![
+            delta(DeltaOp::Upsert, 5, b"k1"),
+            delta(DeltaOp::Upsert, 6, b"k2"),
+            delta(DeltaOp::Upsert, 7, b"k1"),
+        ]);
+        assert_eq!(queue.len(), 2);
+        for delta in &queue {
+            manager.apply_delta(delta).unwrap();
+        }
+        assert!(matches!(manager.state("contacts"), SyncState::Synced { last_sequence: 6 }));
+    }
+
+    #[test]
+    fn staleness_follows_the_injected_clock() {
+        let clock = Rc::new(polykit_core::clock::FakeClock::new(10_000));
+        let source = clock.clone();
+        let mut manager = SyncManager::with_clock(Box::new(move || source.now_ms()));
+        manager.register("contacts");
+        assert_eq!(manager.staleness_ms("contacts", Region::Global), None);
+
+        synced_in(&mut manager, "contacts", Region::Global, 1);
+        assert_eq!(manager.staleness_ms("contacts", Region::Global), Some(0));
+        clock.advance(4_000);
+        assert_eq!(manager.staleness_ms("contacts", Region::Global), Some(4_000));
+
+        manager.apply_delta(&delta(DeltaOp::Upsert, 2, b"k1")).unwrap();
+        assert_eq!(manager.staleness_ms("contacts", Region::Global), Some(0));
+    }
+
+    fn bandwidth_bytes(metered: &Metered) -> u64 {
+        metered
+            .borrow()
+            .iter()
+            .filter(|(dimension, _)| matches!(dimension, MeteringDimension::Bandwidth))
+            .map(|(_, bytes)| bytes)
+            .sum()
+    }
+
+    #[test]
+    fn metered_bandwidth_equals_bytes_processed() {
+        let (mut manager, metered) = metered_manager();
+        manager.register("contacts");
+        synced_in(&mut manager, "contacts", Region::Global, 10);
+        let deltas = [
+            delta(DeltaOp::Insert, 11, b"k1"),
+            delta(DeltaOp::Update, 12, b"k1"),
+            Delta { data: None, ..delta(DeltaOp::Delete, 13, b"k1") },
+        ];
+        for d in &deltas {
+            manager.apply_delta(d).unwrap();
+        }
+
+        let delta_bytes: usize =
+            deltas.iter().map(|d| d.key.len() + d.data.as_ref().map_or(0, |v| v.len())).sum();
+        assert_eq!(bandwidth_bytes(&metered), (b"rows".len() + delta_bytes) as u64);
+        // The snapshot and the two row writes are persisted; the delete is not
+        assert_eq!(stored_bytes(&metered), 4 + 3 + 3);
+    }
+
+    #[test]
+    fn rejected_sync_traffic_is_not_metered() {
+        let (mut manager, metered) = metered_manager();
+        manager.register("contacts");
+        synced_in(&mut manager, "contacts", Region::Global, 10);
+        let before = bandwidth_bytes(&metered);
+
+        assert!(manager.apply_delta(&delta(DeltaOp::Insert, 15, b"k1")).is_err());
+        assert_eq!(bandwidth_bytes(&metered), before);
+    }
+
+    type Conflicts = Rc<RefCell<Vec<ConflictRecord>>>;
+
+    fn fan_in_with(policy: ConflictPolicy) -> (SyncManager, Conflicts) {
+        let conflicts: Conflicts = Rc::default();
+        let sink = conflicts.clone();
+        let mut manager = SyncManager::new().with_conflict_policy(
+            policy,
+            Box::new(move |record| sink.borrow_mut().push(record)),
+        );
+        manager.register_fan_in("orders", &[Region::Us, Region::Eu]).unwrap();
+        synced_in(&mut manager, "orders", Region::Us, 10);
+        synced_in(&mut manager, "orders", Region::Eu, 500);
+        (manager, conflicts)
+    }
+
+    fn order_write(region: Region, sequence: u64, data: &[u8]) -> Delta {
+        Delta {
+            region,
+            table: "orders".to_string(),
+            data: Some(data.to_vec()),
+            ..delta(DeltaOp::Upsert, sequence, b"o1")
+        }
+    }
+
+    fn hex(data: &[u8]) -> String {
+        crypto::sha3_256(data).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn last_write_wins_records_the_overwritten_value() {
+        let (mut manager, conflicts) = fan_in_with(ConflictPolicy::LastWriteWins);
+        manager.apply_delta(&order_write(Region::Us, 11, b"us-value")).unwrap();
+        manager.apply_delta(&order_write(Region::Eu, 501, b"eu-value")).unwrap();
+
+        let conflicts = conflicts.borrow();
+        assert_eq!(conflicts.len(), 1);
+        let record = &conflicts[0];
+        assert_eq!(record.key, b"o1");
+        assert_eq!((record.winning_region, record.winning_sequence), (Region::Eu, 501));
+        assert_eq!((record.losing_region, record.losing_sequence), (Region::Us, 11));
+        assert_eq!(record.losing_data_hash, Some(hex(b"us-value")));
+        assert_eq!(record.policy, ConflictPolicy::LastWriteWins);
+    }
+
+    #[test]
+    fn home_region_write_is_kept() {
+        let (mut manager, conflicts) = fan_in_with(ConflictPolicy::HomeRegionWins);
+        manager.apply_delta(&order_write(Region::Us, 11, b"us-value")).unwrap();
+        manager.apply_delta(&order_write(Region::Eu, 501, b"eu-value")).unwrap();
+
+        let record = &conflicts.borrow()[0];
+        assert_eq!((record.winning_region, record.winning_sequence), (Region::Us, 11));
+        assert_eq!(record.losing_data_hash, Some(hex(b"eu-value")));
+        // The losing write still advances its region
+        assert!(matches!(
+            manager.region_state("orders", Region::Eu),
+            SyncState::Synced { last_sequence: 501 }
+        ));
+    }
+
+    #[test]
+    fn writes_from_one_region_are_not_conflicts() {
+        let (mut manager, conflicts) = fan_in_with(ConflictPolicy::LastWriteWins);
+        manager.apply_delta(&order_write(Region::Us, 11, b"v1")).unwrap();
+        manager.apply_delta(&order_write(Region::Us, 12, b"v2")).unwrap();
+        assert!(conflicts.borrow().is_empty());
+    }
+
+    #[test]
+    fn signed_snapshot_is_applied() {
+        let (mut manager, metered) = metered_manager();
+        manager.register("contacts");
+        let (public_key, secret_key) = source_keys();
+        let snapshot = Snapshot::sign("contacts", b"rows".to_vec(), 10, &secret_key).unwrap();
+        snapshot.verify(&public_key).unwrap();
+        manager.apply_snapshot(&snapshot, &public_key).unwrap();
+        assert!(matches!(manager.state("contacts"), SyncState::Synced { last_sequence: 10 }));
+        assert_eq!(stored_bytes(&metered), 4);
+    }
+
+    #[test]
+    fn tampered_snapshot_is_rejected() {
+        let mut manager = SyncManager::new();
+        manager.register("contacts");
+        let (public_key, secret_key) = source_keys();
+        let snapshot = Snapshot::sign("contacts", b"rows".to_vec(), 10, &secret_key).unwrap();
+
+        let mut data = snapshot.clone();
+        data.data = b"evil".to_vec();
+        let err = manager.apply_snapshot(&data, &public_key).unwrap_err();
+        assert_eq!(err, "snapshot content hash mismatch");
+
+        // Rehashing the new data doesn't help without the source key
+        data.content_hash = crypto::sha3_256(&data.data);
+        let err = manager.apply_snapshot(&data, &public_key).unwrap_err();
+        assert_eq!(err, "snapshot signature invalid");
+
+        let mut sequence = snapshot.clone();
+        sequence.sequence = 11;
+        assert!(manager.apply_snapshot(&sequence, &public_key).is_err());
+
+        let (other_key, _) = crypto::signing_keypair(&mut SeededRng::seed_from_u64(8));
+        let err = manager.apply_snapshot(&snapshot, &other_key).unwrap_err();
+        assert_eq!(err, "snapshot signature invalid");
+
+        let mut version = snapshot;
+        version.version = SNAPSHOT_VERSION + 1;
+        assert!(manager.apply_snapshot(&version, &public_key).unwrap_err().contains("version"));
+        assert!(matches!(manager.state("contacts"), SyncState::Unsynced));
+    }
+
+    #[test]
+    fn stale_snapshot_is_rejected() {
+        let mut manager = SyncManager::new();
+        manager.register("contacts");
+        synced_in(&mut manager, "contacts", Region::Global, 10);
+        let (public_key, secret_key) = source_keys();
+        let stale = Snapshot::sign("contacts", b"old".to_vec(), 9, &secret_key).unwrap();
+        let err = manager.apply_snapshot(&stale, &public_key).unwrap_err();
+        assert_eq!(err, "stale snapshot: sequence 9 is behind current 10");
+        assert!(matches!(manager.state("contacts"), SyncState::Synced { last_sequence: 10 }));
+
+        // Re-applying the current sequence is allowed
+        synced_in(&mut manager, "contacts", Region::Global, 10);
     }
 }
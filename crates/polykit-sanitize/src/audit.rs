@@ -1,19 +1,112 @@
 //! Stage 3: PoVC-Witnessed Audit Record
 
+use std::collections::HashSet;
+
+use polykit_core::clock::{Clock, HostClock};
+use polykit_core::crypto;
+use polykit_core::error::{PolykitError, Result};
+use polykit_core::secret::Secret;
+use serde::{Deserialize, Serialize};
 use crate::{AuditEntry, Detection, Stage};
 
+/// ML-DSA-87 witness key used to sign audit entries.
+pub struct WitnessKey {
+    /// Reference stored on each signed entry (e.g., the witness user_id hex)
+    pub key_id: String,
+    pub secret_key: Secret<Vec<u8>>,
+}
+
 /// Create audit trail entries for all detections.
 /// In production, each entry is PoVC-witnessed (hash chain + ML-DSA-87 signature).
 pub fn record(detections: &[Detection]) -> Vec<AuditEntry> {
-    let timestamp = current_timestamp_ms();
+    record_chained(detections, CHAIN_GENESIS)
+}
+
+/// Starting link for a fresh audit hash chain.
+pub const CHAIN_GENESIS: &str = "";
+
+/// Default minimum witness-hash bytes shown in an audit reference.
+pub const DEFAULT_AUDIT_REF_BYTES: usize = 8;
+
+/// Create audit entries continuing the hash chain from `prev_chain_hash`
+/// (the `chain_hash` of the last entry of a previous record).
+pub fn record_chained(detections: &[Detection], prev_chain_hash: &str) -> Vec<AuditEntry> {
+    record_with(detections, prev_chain_hash, DEFAULT_AUDIT_REF_BYTES, &HostClock)
+}
+
+/// `record_chained` with audit references of at least `ref_bytes` bytes,
+/// timestamped from `clock`.
+pub fn record_with(
+    detections: &[Detection],
+    prev_chain_hash: &str,
+    ref_bytes: usize,
+    clock: &dyn Clock,
+) -> Vec<AuditEntry> {
+    let mut entries = record_unchained(detections, ref_bytes, clock);
+    link_chain(&mut entries, prev_chain_hash);
+    entries
+}
+
+/// Shortest prefix length (in bytes, at least `min_bytes`) at which the
+/// distinct witness hashes all have distinct prefixes.
+fn audit_ref_bytes(witness_hashes: &[String], min_bytes: usize) -> usize {
+    let max_bytes = witness_hashes.iter().map(|h| h.len() / 2).max().unwrap_or(0);
+    let distinct: HashSet<&str> = witness_hashes.iter().map(|h| h.as_str()).collect();
+    let mut bytes = min_bytes.max(1);
+    while bytes < max_bytes {
+        let prefixes: HashSet<&str> = distinct.iter().map(|h| &h[..bytes * 2]).collect();
+        if prefixes.len() == distinct.len() {
+            break;
+        }
+        bytes += 1;
+    }
+    bytes.min(max_bytes)
+}
+
+/// Check that entries form an unbroken chain starting at `prev_chain_hash`.
+pub fn verify_chain(entries: &[AuditEntry], prev_chain_hash: &str) -> bool {
+    let mut prev = prev_chain_hash.to_string();
+    for entry in entries {
+        if entry.chain_hash != chain_link(&prev, &entry.witness_hash) {
+            return false;
+        }
+        prev = entry.chain_hash.clone();
+    }
+    true
+}
+
+pub(crate) fn link_chain(entries: &mut [AuditEntry], prev_chain_hash: &str) {
+    let mut prev = prev_chain_hash.to_string();
+    for entry in entries.iter_mut() {
+        entry.chain_hash = chain_link(&prev, &entry.witness_hash);
+        prev = entry.chain_hash.clone();
+    }
+}
+
+fn chain_link(prev: &str, witness_hash: &str) -> String {
+    let mut input = prev.as_bytes().to_vec();
+    input.extend_from_slice(witness_hash.as_bytes());
+    hex_encode(&crypto::sha3_256(&input))
+}
+
+fn record_unchained(
+    detections: &[Detection],
+    ref_bytes: usize,
+    clock: &dyn Clock,
+) -> Vec<AuditEntry> {
+    let timestamp = clock.now_ms();
     let mut entries = Vec::new();
 
-    for detection in detections {
-        let witness_hash = compute_witness_hash(detection, timestamp);
+    let witness_hashes: Vec<String> =
+        detections.iter().map(|d| compute_witness_hash(d, timestamp)).collect();
+    let ref_len = audit_ref_bytes(&witness_hashes, ref_bytes) * 2;
+
+    for (detection, witness_hash) in detections.iter().zip(witness_hashes) {
         let regulations: Vec<String> = detection.regulation.iter().map(|r| format!("{:?}", r)).collect();
 
         // Stage 1 audit: what was detected
         entries.push(AuditEntry {
+            entry_id: String::new(),
             timestamp_ms: timestamp,
             stage: Stage::PiiDetect,
             field_path: detection.field_path.clone(),
@@ -21,10 +114,14 @@ pub fn record(detections: &[Detection]) -> Vec<AuditEntry> {
             placeholder: String::new(),
             regulations: regulations.clone(),
             witness_hash: witness_hash.clone(),
+            witness_signature: Vec::new(),
+            witness_key_id: String::new(),
+            chain_hash: String::new(),
         });
 
         // Stage 2 audit: what was replaced
         entries.push(AuditEntry {
+            entry_id: String::new(),
             timestamp_ms: timestamp,
             stage: Stage::ValueTransform,
             field_path: detection.field_path.clone(),
@@ -32,23 +129,219 @@ pub fn record(detections: &[Detection]) -> Vec<AuditEntry> {
             placeholder: placeholder_for_type(&detection.data_type),
             regulations: regulations.clone(),
             witness_hash: witness_hash.clone(),
+            witness_signature: Vec::new(),
+            witness_key_id: String::new(),
+            chain_hash: String::new(),
         });
 
         // Stage 3 audit: the record itself
         entries.push(AuditEntry {
+            entry_id: String::new(),
             timestamp_ms: timestamp,
             stage: Stage::AuditRecord,
             field_path: detection.field_path.clone(),
             original_type: format!("{:?}", detection.data_type),
-            placeholder: format!("[AUDIT_REF:0x{}]", &witness_hash[..4]),
+            placeholder: format!("[AUDIT_REF:0x{}]", &witness_hash[..ref_len]),
             regulations,
             witness_hash,
+            witness_signature: Vec::new(),
+            witness_key_id: String::new(),
+            chain_hash: String::new(),
         });
     }
 
+    // Stable order and ids, so identical input yields identical entries
+    entries.sort_by(|a, b| {
+        (a.timestamp_ms, a.stage, &a.field_path).cmp(&(b.timestamp_ms, b.stage, &b.field_path))
+    });
+    for entry in entries.iter_mut() {
+        entry.entry_id = entry_id(entry);
+    }
+
     entries
 }
 
+/// Sign each entry's witness_hash with the witness key (ML-DSA-87).
+pub fn sign_entries(entries: &mut [AuditEntry], witness: &WitnessKey) -> Result<()> {
+    for entry in entries.iter_mut() {
+        entry.witness_signature = crypto::sign(&witness.secret_key, entry.witness_hash.as_bytes())?;
+        entry.witness_key_id = witness.key_id.clone();
+    }
+    Ok(())
+}
+
+/// One persisted audit log row, keyed by its position in the chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredAuditRecord {
+    pub position: u64,
+    pub entry: AuditEntry,
+}
+
+/// Append-only audit log. Appending relinks the entry onto the running
+/// chain hash; entries can't be removed or reordered once appended.
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an entry, overwriting its `chain_hash` with the link from
+    /// the current head.
+    pub fn append(&mut self, mut entry: AuditEntry) {
+        entry.chain_hash = chain_link(self.head(), &entry.witness_hash);
+        self.entries.push(entry);
+    }
+
+    pub fn append_all(&mut self, entries: impl IntoIterator<Item = AuditEntry>) {
+        for entry in entries {
+            self.append(entry);
+        }
+    }
+
+    /// Chain hash of the last entry (`CHAIN_GENESIS` when empty). Anchoring
+    /// this value elsewhere makes truncation detectable too.
+    pub fn head(&self) -> &str {
+        self.entries.last().map_or(CHAIN_GENESIS, |e| e.chain_hash.as_str())
+    }
+
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Rows to persist (e.g., one ESLite row per record, keyed by position).
+    pub fn to_records(&self) -> Vec<StoredAuditRecord> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| StoredAuditRecord { position: i as u64, entry: entry.clone() })
+            .collect()
+    }
+
+    /// Rebuild a log from persisted rows in any order. Rejects gaps or
+    /// duplicate positions, entries whose id or witness hash no longer
+    /// match their contents, and a broken chain.
+    pub fn load(mut records: Vec<StoredAuditRecord>) -> Result<Self> {
+        records.sort_by_key(|r| r.position);
+        let mut prev = CHAIN_GENESIS.to_string();
+        for (i, record) in records.iter().enumerate() {
+            let entry = &record.entry;
+            let fail = |reason: &str| {
+                Err(PolykitError::Storage(format!("audit record {}: {}", record.position, reason)))
+            };
+            if record.position != i as u64 {
+                return fail(&format!("expected position {}", i));
+            }
+            if entry.entry_id != entry_id(entry) {
+                return fail("entry id does not match contents");
+            }
+            let witness =
+                witness_hash_of(&entry.field_path, &entry.original_type, entry.timestamp_ms);
+            if entry.witness_hash != witness {
+                return fail("witness hash does not match contents");
+            }
+            if entry.chain_hash != chain_link(&prev, &entry.witness_hash) {
+                return fail("chain hash does not link to the previous record");
+            }
+            prev = entry.chain_hash.clone();
+        }
+        Ok(Self { entries: records.into_iter().map(|r| r.entry).collect() })
+    }
+}
+
+/// A witness public key trusted for entries timestamped within its window.
+#[derive(Debug, Clone)]
+pub struct TrustAnchor {
+    pub key_id: String,
+    pub public_key: Vec<u8>,
+    pub valid_from_ms: u64,
+    /// Exclusive end; `None` for the current key
+    pub valid_until_ms: Option<u64>,
+}
+
+impl TrustAnchor {
+    pub fn is_valid_at(&self, timestamp_ms: u64) -> bool {
+        timestamp_ms >= self.valid_from_ms
+            && self.valid_until_ms.is_none_or(|end| timestamp_ms < end)
+    }
+}
+
+/// Witness keys trusted over time. Rotating adds the new key and closes
+/// the old key's window, so older entries keep verifying.
+#[derive(Debug, Clone, Default)]
+pub struct TrustAnchors {
+    anchors: Vec<TrustAnchor>,
+}
+
+impl TrustAnchors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_anchor(mut self, anchor: TrustAnchor) -> Self {
+        self.anchors.push(anchor);
+        self
+    }
+
+    /// Close the open window of `retiring_key_id` at `at_ms` and trust
+    /// `next` from then on.
+    pub fn rotate(
+        &mut self,
+        retiring_key_id: &str,
+        next_key_id: &str,
+        next_public_key: Vec<u8>,
+        at_ms: u64,
+    ) {
+        for anchor in self.anchors.iter_mut() {
+            if anchor.key_id == retiring_key_id && anchor.valid_until_ms.is_none() {
+                anchor.valid_until_ms = Some(at_ms);
+            }
+        }
+        self.anchors.push(TrustAnchor {
+            key_id: next_key_id.to_string(),
+            public_key: next_public_key,
+            valid_from_ms: at_ms,
+            valid_until_ms: None,
+        });
+    }
+
+    /// The anchor for `key_id` whose window covers `timestamp_ms`.
+    pub fn key_at(&self, key_id: &str, timestamp_ms: u64) -> Option<&TrustAnchor> {
+        self.anchors
+            .iter()
+            .find(|a| a.key_id == key_id && a.is_valid_at(timestamp_ms))
+    }
+}
+
+/// Verify an entry's witness signature with the trust anchor that was
+/// valid at the entry's timestamp. Unsigned entries, entries whose key
+/// wasn't valid at that time, and entries whose witness hash doesn't
+/// match their own fields (e.g., a rewritten timestamp) verify as `false`.
+pub fn verify_witness(entry: &AuditEntry, anchors: &TrustAnchors) -> Result<bool> {
+    if entry.witness_signature.is_empty() {
+        return Ok(false);
+    }
+    let Some(anchor) = anchors.key_at(&entry.witness_key_id, entry.timestamp_ms) else {
+        return Ok(false);
+    };
+    let expected = witness_hash_of(&entry.field_path, &entry.original_type, entry.timestamp_ms);
+    if entry.witness_hash != expected {
+        return Ok(false);
+    }
+    crypto::verify(&anchor.public_key, entry.witness_hash.as_bytes(), &entry.witness_signature)
+}
+
 fn placeholder_for_type(dt: &crate::DataType) -> String {
     match dt {
         crate::DataType::Ssn => "***-**-XXXX".to_string(),
@@ -58,24 +351,279 @@ fn placeholder_for_type(dt: &crate::DataType) -> String {
     }
 }
 
-fn current_timestamp_ms() -> u64 {
-    // In production: host import estream::get_time
-    0
+/// SHA3-256(field_path, stage, timestamp, witness_hash), first 16 bytes as hex.
+pub fn entry_id(entry: &AuditEntry) -> String {
+    let mut input = Vec::new();
+    for field in [
+        entry.field_path.as_bytes(),
+        format!("{:?}", entry.stage).as_bytes(),
+        &entry.timestamp_ms.to_be_bytes(),
+        entry.witness_hash.as_bytes(),
+    ] {
+        input.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        input.extend_from_slice(field);
+    }
+    hex_encode(&crypto::sha3_256(&input)[..16])
 }
 
 fn compute_witness_hash(detection: &Detection, timestamp: u64) -> String {
-    // In production: SHA3-256(field_path || data_type || timestamp) signed by witness
-    let input = format!("{}::{:?}::{}", detection.field_path, detection.data_type, timestamp);
-    let hash_bytes = simple_hash(input.as_bytes());
-    hex_encode(&hash_bytes[..6])
+    witness_hash_of(&detection.field_path, &format!("{:?}", detection.data_type), timestamp)
 }
 
-fn simple_hash(data: &[u8]) -> [u8; 32] {
-    // Stub — delegates to estream::sha3_256 in production
-    let _ = data;
-    [0u8; 32]
+fn witness_hash_of(field_path: &str, data_type: &str, timestamp: u64) -> String {
+    // In production: SHA3-256(field_path || data_type || timestamp) signed by witness
+    let input = format!("{}::{}::{}", field_path, data_type, timestamp);
+    hex_encode(&crypto::sha3_256(input.as_bytes()))
 }
 
 fn hex_encode(bytes: &[u8]) -> String {
     bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, Regulation};
+    use polykit_core::crypto::SeededRng;
+
+    fn detection(field_path: &str) -> Detection {
+        Detection {
+            field_path: field_path.to_string(),
+            data_type: DataType::Ssn,
+            regulation: vec![Regulation::Hipaa],
+            confidence: 1.0,
+        }
+    }
+
+    fn witnessed() -> (Vec<AuditEntry>, TrustAnchors) {
+        let (public_key, secret_key) = crypto::signing_keypair(&mut SeededRng::seed_from_u64(1));
+        let witness = WitnessKey { key_id: "witness-1".to_string(), secret_key };
+        let anchors = TrustAnchors::new().with_anchor(TrustAnchor {
+            key_id: "witness-1".to_string(),
+            public_key,
+            valid_from_ms: 0,
+            valid_until_ms: None,
+        });
+        let mut entries =
+            record_with(&[detection("patient.ssn")], CHAIN_GENESIS, 8, &|| 1_000);
+        sign_entries(&mut entries, &witness).unwrap();
+        (entries, anchors)
+    }
+
+    #[test]
+    fn signed_entries_verify() {
+        let (entries, anchors) = witnessed();
+        assert_eq!(entries.len(), 3);
+        for entry in &entries {
+            assert_eq!(entry.witness_key_id, "witness-1");
+            assert!(verify_witness(entry, &anchors).unwrap());
+        }
+    }
+
+    #[test]
+    fn tampered_witness_hash_fails() {
+        let (entries, anchors) = witnessed();
+        let mut entry = entries[0].clone();
+        entry.witness_hash = witness_hash_of("patient.name", &entry.original_type, 1_000);
+        assert!(!verify_witness(&entry, &anchors).unwrap());
+    }
+
+    #[test]
+    fn tampered_signature_fails() {
+        let (entries, anchors) = witnessed();
+        let mut entry = entries[0].clone();
+        let last = entry.witness_signature.len() - 1;
+        entry.witness_signature[last] ^= 1;
+        assert!(!verify_witness(&entry, &anchors).unwrap());
+    }
+
+    #[test]
+    fn unsigned_and_untrusted_entries_fail() {
+        let (entries, anchors) = witnessed();
+        let unsigned = record_with(&[detection("patient.ssn")], CHAIN_GENESIS, 8, &|| 1_000);
+        assert!(!verify_witness(&unsigned[0], &anchors).unwrap());
+
+        let mut foreign = entries[0].clone();
+        foreign.witness_key_id = "witness-2".to_string();
+        assert!(!verify_witness(&foreign, &anchors).unwrap());
+    }
+
+    #[test]
+    fn entry_ids_are_stable_across_runs() {
+        let detections = [detection("patient.ssn"), detection("patient.mrn")];
+        let first = record_with(&detections, CHAIN_GENESIS, 8, &|| 1_000);
+        let second = record_with(&detections, CHAIN_GENESIS, 8, &|| 1_000);
+        let ids = |entries: &[AuditEntry]| -> Vec<String> {
+            entries.iter().map(|e| e.entry_id.clone()).collect()
+        };
+        assert_eq!(ids(&first), ids(&second));
+        assert!(first.iter().all(|e| e.entry_id == entry_id(e)));
+    }
+
+    #[test]
+    fn entry_ids_differ_across_fields_and_stages() {
+        let detections = [detection("patient.ssn"), detection("patient.mrn")];
+        let entries = record_with(&detections, CHAIN_GENESIS, 8, &|| 0);
+        let ids: HashSet<&str> = entries.iter().map(|e| e.entry_id.as_str()).collect();
+        assert_eq!(ids.len(), entries.len());
+    }
+
+    #[test]
+    fn entries_are_ordered_by_stage_then_field() {
+        let entries =
+            record_with(&[detection("b.ssn"), detection("a.ssn")], CHAIN_GENESIS, 8, &|| 0);
+        let order: Vec<(Stage, &str)> =
+            entries.iter().map(|e| (e.stage, e.field_path.as_str())).collect();
+        assert_eq!(
+            order,
+            [
+                (Stage::PiiDetect, "a.ssn"),
+                (Stage::PiiDetect, "b.ssn"),
+                (Stage::ValueTransform, "a.ssn"),
+                (Stage::ValueTransform, "b.ssn"),
+                (Stage::AuditRecord, "a.ssn"),
+                (Stage::AuditRecord, "b.ssn"),
+            ]
+        );
+    }
+
+    fn audit_refs(entries: &[AuditEntry]) -> Vec<&str> {
+        entries
+            .iter()
+            .filter(|e| e.stage == Stage::AuditRecord)
+            .map(|e| e.placeholder.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn audit_refs_are_unique_across_a_large_run() {
+        let detections: Vec<Detection> =
+            (0..5_000).map(|i| detection(&format!("rows[{}].ssn", i))).collect();
+        let entries = record_with(&detections, CHAIN_GENESIS, DEFAULT_AUDIT_REF_BYTES, &|| 7);
+        let refs = audit_refs(&entries);
+        assert_eq!(refs.len(), 5_000);
+        assert_eq!(refs.iter().collect::<HashSet<_>>().len(), refs.len());
+        let expected_len = "[AUDIT_REF:0x]".len() + DEFAULT_AUDIT_REF_BYTES * 2;
+        assert!(refs.iter().all(|r| r.len() >= expected_len));
+    }
+
+    #[test]
+    fn audit_ref_length_is_configurable() {
+        let entries = record_with(&[detection("patient.ssn")], CHAIN_GENESIS, 12, &|| 7);
+        let hex = audit_refs(&entries)[0]
+            .trim_start_matches("[AUDIT_REF:0x")
+            .trim_end_matches(']');
+        assert_eq!(hex.len(), 24);
+        assert!(entries[0].witness_hash.starts_with(hex));
+    }
+
+    #[test]
+    fn colliding_prefixes_lengthen_the_ref() {
+        let hashes = ["aabbccdd01".to_string(), "aabbccdd02".to_string(), "aabbccdd01".to_string()];
+        assert_eq!(audit_ref_bytes(&hashes, 2), 5);
+        assert_eq!(audit_ref_bytes(&hashes[..1], 2), 2);
+    }
+
+    fn signed_at(timestamp_ms: u64, key_id: &str, seed: u64) -> (AuditEntry, Vec<u8>) {
+        let (public_key, secret_key) = crypto::signing_keypair(&mut SeededRng::seed_from_u64(seed));
+        let witness = WitnessKey { key_id: key_id.to_string(), secret_key };
+        let mut entries =
+            record_with(&[detection("patient.ssn")], CHAIN_GENESIS, 8, &move || timestamp_ms);
+        sign_entries(&mut entries, &witness).unwrap();
+        (entries.remove(0), public_key)
+    }
+
+    #[test]
+    fn entries_verify_across_key_rotation() {
+        let (old_entry, old_key) = signed_at(1_000, "witness-1", 1);
+        let (new_entry, new_key) = signed_at(6_000, "witness-2", 2);
+        let mut anchors = TrustAnchors::new().with_anchor(TrustAnchor {
+            key_id: "witness-1".to_string(),
+            public_key: old_key,
+            valid_from_ms: 0,
+            valid_until_ms: None,
+        });
+        anchors.rotate("witness-1", "witness-2", new_key, 5_000);
+
+        assert!(verify_witness(&old_entry, &anchors).unwrap());
+        assert!(verify_witness(&new_entry, &anchors).unwrap());
+        assert_eq!(anchors.key_at("witness-1", 4_999).unwrap().valid_until_ms, Some(5_000));
+    }
+
+    #[test]
+    fn entry_signed_after_key_window_fails() {
+        let (late_entry, old_key) = signed_at(7_000, "witness-1", 1);
+        let (_, new_key) = signed_at(0, "witness-2", 2);
+        let mut anchors = TrustAnchors::new().with_anchor(TrustAnchor {
+            key_id: "witness-1".to_string(),
+            public_key: old_key,
+            valid_from_ms: 0,
+            valid_until_ms: None,
+        });
+        anchors.rotate("witness-1", "witness-2", new_key, 5_000);
+        assert!(!verify_witness(&late_entry, &anchors).unwrap());
+    }
+
+    #[test]
+    fn entry_before_key_window_fails() {
+        let (early_entry, key) = signed_at(500, "witness-1", 1);
+        let anchors = TrustAnchors::new().with_anchor(TrustAnchor {
+            key_id: "witness-1".to_string(),
+            public_key: key,
+            valid_from_ms: 1_000,
+            valid_until_ms: None,
+        });
+        assert!(!verify_witness(&early_entry, &anchors).unwrap());
+    }
+
+    fn persisted_log() -> String {
+        let mut log = AuditLog::new();
+        log.append_all(record_with(&[detection("patient.ssn")], CHAIN_GENESIS, 8, &|| 1_000));
+        log.append_all(record_with(&[detection("patient.mrn")], CHAIN_GENESIS, 8, &|| 2_000));
+        assert_eq!(log.len(), 6);
+        assert!(verify_chain(log.entries(), CHAIN_GENESIS));
+        serde_json::to_string(&log.to_records()).unwrap()
+    }
+
+    fn reload(stored: &str) -> Result<AuditLog> {
+        AuditLog::load(serde_json::from_str(stored).unwrap())
+    }
+
+    #[test]
+    fn persisted_log_reloads_and_verifies() {
+        let stored = persisted_log();
+        let log = reload(&stored).unwrap();
+        assert_eq!(log.len(), 6);
+        assert_eq!(log.head(), log.entries()[5].chain_hash);
+    }
+
+    #[test]
+    fn records_load_in_any_stored_order() {
+        let mut records: Vec<StoredAuditRecord> = serde_json::from_str(&persisted_log()).unwrap();
+        records.reverse();
+        assert_eq!(AuditLog::load(records).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn tampered_persisted_record_fails() {
+        let stored = persisted_log().replacen("patient.mrn", "patient.name", 1);
+        let err = reload(&stored).unwrap_err().to_string();
+        assert!(err.contains("audit record 3"));
+    }
+
+    #[test]
+    fn reordered_entries_fail() {
+        let mut records: Vec<StoredAuditRecord> = serde_json::from_str(&persisted_log()).unwrap();
+        records[0].position = 1;
+        records[1].position = 0;
+        assert!(AuditLog::load(records).is_err());
+    }
+
+    #[test]
+    fn missing_record_fails() {
+        let mut records: Vec<StoredAuditRecord> = serde_json::from_str(&persisted_log()).unwrap();
+        records.remove(2);
+        let err = AuditLog::load(records).unwrap_err().to_string();
+        assert!(err.contains("expected position 2"));
+    }
+}
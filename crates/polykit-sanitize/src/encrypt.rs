@@ -0,0 +1,202 @@
+//! Field-level encryption (alternative Stage 2)
+//!
+//! Replaces each detected value with an AES-256-GCM envelope instead of a
+//! placeholder, so key holders can recover it while the document keeps its
+//! shape. Each field gets its own key via HKDF over the field path, and the
+//! path is bound as AAD so an envelope can't be moved to another field.
+
+use std::collections::HashSet;
+
+use polykit_core::crypto::{self, Rng, AEAD_NONCE_LEN};
+use polykit_core::error::{PolykitError, Result};
+use polykit_core::secret::Secret;
+use crate::Detection;
+
+/// Prefix marking an encrypted field value.
+pub const ENVELOPE_PREFIX: &str = "enc:v1:";
+
+/// Encrypt every detected value in place; everything else is untouched.
+pub fn encrypt_fields(
+    input: &serde_json::Value,
+    detections: &[Detection],
+    master_key: &[u8; 32],
+    rng: &mut dyn Rng,
+) -> Result<serde_json::Value> {
+    let paths: HashSet<&str> = detections.iter().map(|d| d.field_path.as_str()).collect();
+    let mut output = input.clone();
+    walk_strings(&mut output, "", &mut |path, value| {
+        if !paths.contains(path) {
+            return Ok(());
+        }
+        let mut nonce = [0u8; AEAD_NONCE_LEN];
+        rng.fill_bytes(&mut nonce);
+        let key = field_key(master_key, path)?;
+        let sealed = crypto::aead_seal(&key, &nonce, path.as_bytes(), value.as_bytes())?;
+
+        let mut envelope = nonce.to_vec();
+        envelope.extend_from_slice(&sealed);
+        *value = format!("{}{}", ENVELOPE_PREFIX, base64_encode(&envelope));
+        Ok(())
+    })?;
+    Ok(output)
+}
+
+/// Decrypt every envelope produced by `encrypt_fields`.
+pub fn decrypt_fields(input: &serde_json::Value, master_key: &[u8; 32]) -> Result<serde_json::Value> {
+    let mut output = input.clone();
+    walk_strings(&mut output, "", &mut |path, value| {
+        let encoded = match value.strip_prefix(ENVELOPE_PREFIX) {
+            Some(e) => e,
+            None => return Ok(()),
+        };
+        let envelope = base64_decode(encoded)
+            .ok_or_else(|| PolykitError::Sanitization(format!("{}: malformed envelope", path)))?;
+        if envelope.len() < AEAD_NONCE_LEN {
+            return Err(PolykitError::Sanitization(format!("{}: truncated envelope", path)));
+        }
+        let (nonce, sealed) = envelope.split_at(AEAD_NONCE_LEN);
+        let nonce: [u8; AEAD_NONCE_LEN] = nonce.try_into().expect("split at nonce length");
+        let key = field_key(master_key, path)?;
+        let plaintext = crypto::aead_open(&key, &nonce, path.as_bytes(), sealed)?;
+
+        *value = String::from_utf8(plaintext)
+            .map_err(|_| PolykitError::Sanitization(format!("{}: plaintext is not UTF-8", path)))?;
+        Ok(())
+    })?;
+    Ok(output)
+}
+
+fn field_key(master_key: &[u8; 32], path: &str) -> Result<Secret<[u8; 32]>> {
+    let info = format!("polykit-sanitize/field/{}", path);
+    crypto::hkdf_sha3_256(master_key, info.as_bytes())
+}
+
+/// Visit every string leaf with its field path (same format as `detect::scan`).
+fn walk_strings(
+    value: &mut serde_json::Value,
+    path: &str,
+    f: &mut dyn FnMut(&str, &mut String) -> Result<()>,
+) -> Result<()> {
+    match value {
+        serde_json::Value::String(s) => f(path, s),
+        serde_json::Value::Object(map) => {
+            for (key, val) in map.iter_mut() {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                walk_strings(val, &child_path, f)?;
+            }
+            Ok(())
+        }
+        serde_json::Value::Array(arr) => {
+            for (i, val) in arr.iter_mut().enumerate() {
+                walk_strings(val, &format!("{}[{}]", path, i), f)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+pub(crate) fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(s.len() / 4 * 3);
+    for chunk in s.as_bytes().chunks(4) {
+        let pad = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if pad > 2 {
+            return None;
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - pad] {
+            let v = BASE64_ALPHABET.iter().position(|&a| a == c)? as u32;
+            n = n << 6 | v;
+        }
+        n <<= 6 * pad as u32;
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - pad]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sanitize_encrypted, SanitizeOptions};
+    use polykit_core::crypto::SeededRng;
+
+    const KEY: [u8; 32] = [7u8; 32];
+
+    fn document() -> serde_json::Value {
+        serde_json::json!({
+            "patient": { "ssn": "123-45-6789", "email": "alice@example.com" },
+            "visits": [{ "note": "routine checkup" }],
+            "count": 3,
+        })
+    }
+
+    fn encrypted() -> serde_json::Value {
+        let mut rng = SeededRng::seed_from_u64(5);
+        sanitize_encrypted(&document(), &SanitizeOptions::default(), &KEY, &mut rng)
+            .unwrap()
+            .sanitized_data
+    }
+
+    #[test]
+    fn only_detected_fields_are_encrypted() {
+        let encrypted = encrypted();
+        for path in ["ssn", "email"] {
+            let value = encrypted["patient"][path].as_str().unwrap();
+            assert!(value.starts_with(ENVELOPE_PREFIX), "{}", value);
+        }
+        assert_eq!(encrypted["visits"], document()["visits"]);
+        assert_eq!(encrypted["count"], 3);
+    }
+
+    #[test]
+    fn decrypt_recovers_original() {
+        assert_eq!(decrypt_fields(&encrypted(), &KEY).unwrap(), document());
+    }
+
+    #[test]
+    fn wrong_key_fails() {
+        assert!(decrypt_fields(&encrypted(), &[8u8; 32]).is_err());
+    }
+
+    #[test]
+    fn moved_envelope_fails() {
+        // The field path is AAD, so an envelope only opens where it was sealed
+        let mut moved = encrypted();
+        moved["patient"]["email"] = moved["patient"]["ssn"].clone();
+        assert!(decrypt_fields(&moved, &KEY).is_err());
+    }
+
+    #[test]
+    fn base64_round_trips() {
+        for len in 0..8 {
+            let bytes: Vec<u8> = (0..len as u8).map(|b| b.wrapping_mul(37)).collect();
+            assert_eq!(base64_decode(&base64_encode(&bytes)).unwrap(), bytes);
+        }
+        assert!(base64_decode("abc").is_none());
+    }
+}
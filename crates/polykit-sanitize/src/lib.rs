@@ -11,8 +11,12 @@
 pub mod detect;
 pub mod transform;
 pub mod audit;
+pub mod encrypt;
 
 use serde::{Deserialize, Serialize};
+use polykit_core::classification::Classification;
+use polykit_core::clock::HostClock;
+use polykit_core::versioned::WireVersion;
 
 /// Detected sensitive data.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,7 +28,7 @@ pub struct Detection {
 }
 
 /// Sensitive data types.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DataType {
     Ssn,
     CreditCard,
@@ -36,6 +40,10 @@ pub enum DataType {
     MedicalRecord,
     FinancialAccount,
     BiometricData,
+    PassportNumber,
+    DriversLicense,
+    /// Credentials: API keys, bearer tokens, JWTs
+    SecretMaterial,
     Custom(String),
 }
 
@@ -56,11 +64,78 @@ pub struct SanitizationResult {
     pub sanitized_data: serde_json::Value,
     /// Audit entries for each detected item
     pub audit_entries: Vec<AuditEntry>,
+    /// The input went through detection (always true from the pipeline)
+    #[serde(default)]
+    pub scanned: bool,
+    /// Affirmative no-PII signal: scanned and nothing detected, including
+    /// matches below the confidence threshold that were left in place
+    #[serde(default)]
+    pub clean: bool,
+    /// Token → original mappings (Tokenize mode only). Never serialized,
+    /// so originals stay in WASM memory.
+    #[serde(skip)]
+    pub token_vault: Vec<transform::TokenMapping>,
+}
+
+impl WireVersion for SanitizationResult {
+    const VERSION: u16 = 1;
+}
+
+/// How stage 2 replaces detected values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransformMode {
+    /// Irreversible type placeholder, e.g. `[PII_SSN]`
+    Redact,
+    /// Deterministic token, reversible via the result's token vault
+    Tokenize,
+}
+
+/// Pipeline tuning. Start from `options_for(classification)` and override
+/// individual fields as needed.
+#[derive(Debug, Clone)]
+pub struct SanitizeOptions {
+    /// Detections below this confidence are left untouched
+    pub min_confidence: f64,
+    pub mode: TransformMode,
+    pub detect: detect::DetectOptions,
+    /// In `sanitize_batch`, continue one audit hash chain across records
+    pub share_audit_chain: bool,
+    /// Minimum witness-hash bytes in `[AUDIT_REF:0x..]` placeholders;
+    /// lengthened within a run until every reference is unique
+    pub audit_ref_bytes: usize,
+}
+
+impl Default for SanitizeOptions {
+    fn default() -> Self {
+        options_for(Classification::Internal)
+    }
+}
+
+/// Tuned defaults per classification tier: higher tiers act on weaker
+/// matches and disable reversible tokenization.
+pub fn options_for(classification: Classification) -> SanitizeOptions {
+    let (min_confidence, mode) = match classification {
+        Classification::Public => (0.90, TransformMode::Tokenize),
+        Classification::Internal => (0.85, TransformMode::Tokenize),
+        Classification::Confidential => (0.75, TransformMode::Tokenize),
+        Classification::Restricted => (0.60, TransformMode::Redact),
+        Classification::Sovereign => (0.50, TransformMode::Redact),
+    };
+    SanitizeOptions {
+        min_confidence,
+        mode,
+        detect: detect::DetectOptions::default(),
+        share_audit_chain: true,
+        audit_ref_bytes: audit::DEFAULT_AUDIT_REF_BYTES,
+    }
 }
 
 /// Audit entry from stage 3.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditEntry {
+    /// Deterministic id: hash of (field_path, stage, timestamp, witness_hash)
+    #[serde(default)]
+    pub entry_id: String,
     pub timestamp_ms: u64,
     pub stage: Stage,
     pub field_path: String,
@@ -68,15 +143,29 @@ pub struct AuditEntry {
     pub placeholder: String,
     pub regulations: Vec<String>,
     pub witness_hash: String,
+    /// ML-DSA-87 signature over `witness_hash` (empty until witnessed)
+    #[serde(default)]
+    pub witness_signature: Vec<u8>,
+    /// Identifier of the witness key that produced `witness_signature`
+    #[serde(default)]
+    pub witness_key_id: String,
+    /// SHA3-256 link over the previous entry's chain_hash and this witness_hash
+    #[serde(default)]
+    pub chain_hash: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Stage {
     PiiDetect,
     ValueTransform,
     AuditRecord,
 }
 
+/// Fast path for inline form validation: is this single value PII?
+pub fn detect_field(field_name: &str, value: &str) -> Option<Detection> {
+    detect::detect_field(field_name, value, &detect::DetectOptions::default())
+}
+
 /// Run the full 3-stage sanitization pipeline on input data.
 pub fn sanitize(input: &serde_json::Value) -> SanitizationResult {
     // Stage 1: Detect PII
@@ -91,5 +180,277 @@ pub fn sanitize(input: &serde_json::Value) -> SanitizationResult {
     SanitizationResult {
         sanitized_data: sanitized,
         audit_entries,
+        scanned: true,
+        clean: detections.is_empty(),
+        token_vault: Vec::new(),
+    }
+}
+
+/// Run the pipeline with explicit options (see `options_for`).
+pub fn sanitize_with(input: &serde_json::Value, options: &SanitizeOptions) -> SanitizationResult {
+    // Stage 1: Detect PII
+    let detections = detect::scan_with(input, &options.detect);
+    transform_and_audit(input, options, detections)
+}
+
+/// Sanitize many records in one call, so a page of documents crosses the
+/// WASM boundary once. Each record runs `sanitize_with` independently
+/// (nothing is precompiled across records). With `share_audit_chain` each
+/// record's audit entries continue the chain from the previous record's
+/// last entry; a record with no detections leaves the chain where it was.
+pub fn sanitize_batch(inputs: &[serde_json::Value], options: &SanitizeOptions) -> Vec<SanitizationResult> {
+    let mut chain_head = audit::CHAIN_GENESIS.to_string();
+    inputs
+        .iter()
+        .map(|input| {
+            let mut result = sanitize_with(input, options);
+            if options.share_audit_chain {
+                audit::link_chain(&mut result.audit_entries, &chain_head);
+                if let Some(last) = result.audit_entries.last() {
+                    chain_head = last.chain_hash.clone();
+                }
+            }
+            result
+        })
+        .collect()
+}
+
+/// Stages 2 and 3 for `sanitize_with`-style runs. Drops detections below
+/// the tier's confidence threshold first.
+fn transform_and_audit(
+    input: &serde_json::Value,
+    options: &SanitizeOptions,
+    detections: Vec<Detection>,
+) -> SanitizationResult {
+    let clean = detections.is_empty();
+    let detections: Vec<Detection> = detections
+        .into_iter()
+        .filter(|d| d.confidence >= options.min_confidence)
+        .collect();
+
+    // Stage 2: Transform values
+    let (sanitized, token_vault) = match options.mode {
+        TransformMode::Redact => (transform::redact(input, &detections), Vec::new()),
+        TransformMode::Tokenize => transform::tokenize(input, &detections),
+    };
+
+    // Stage 3: Create audit trail
+    let audit_entries =
+        audit::record_with(&detections, audit::CHAIN_GENESIS, options.audit_ref_bytes, &HostClock);
+
+    SanitizationResult {
+        sanitized_data: sanitized,
+        audit_entries,
+        scanned: true,
+        clean,
+        token_vault,
+    }
+}
+
+/// Run the pipeline, stopping early if `token` is cancelled. A cancelled
+/// run returns no partial output: unscanned fields may still hold PII.
+pub fn sanitize_cancellable(
+    input: &serde_json::Value,
+    options: &SanitizeOptions,
+    token: &polykit_core::cancel::CancellationToken,
+) -> Result<SanitizationResult, polykit_core::cancel::Cancelled<()>> {
+    let detections = detect::scan_cancellable(input, &options.detect, token)
+        .map_err(|c| polykit_core::cancel::Cancelled { partial: (), processed: c.processed })?;
+    Ok(transform_and_audit(input, options, detections))
+}
+
+/// Run the pipeline with field-level encryption in place of redaction.
+/// Detected values become AES-256-GCM envelopes recoverable with
+/// `encrypt::decrypt_fields` and the same master key.
+pub fn sanitize_encrypted(
+    input: &serde_json::Value,
+    options: &SanitizeOptions,
+    master_key: &[u8; 32],
+    rng: &mut dyn polykit_core::crypto::Rng,
+) -> polykit_core::error::Result<SanitizationResult> {
+    let detections = detect::scan_with(input, &options.detect);
+    let clean = detections.is_empty();
+    let detections: Vec<Detection> = detections
+        .into_iter()
+        .filter(|d| d.confidence >= options.min_confidence)
+        .collect();
+
+    let sanitized = encrypt::encrypt_fields(input, &detections, master_key, rng)?;
+    let audit_entries =
+        audit::record_with(&detections, audit::CHAIN_GENESIS, options.audit_ref_bytes, &HostClock);
+
+    Ok(SanitizationResult {
+        sanitized_data: sanitized,
+        audit_entries,
+        scanned: true,
+        clean,
+        token_vault: Vec::new(),
+    })
+}
+
+/// Run the pipeline and sign every audit entry with the given witness key.
+pub fn sanitize_witnessed(
+    input: &serde_json::Value,
+    witness: &audit::WitnessKey,
+) -> polykit_core::error::Result<SanitizationResult> {
+    let mut result = sanitize(input);
+    audit::sign_entries(&mut result.audit_entries, witness)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sovereign_is_stricter_than_public() {
+        let sovereign = options_for(Classification::Sovereign);
+        let public = options_for(Classification::Public);
+        assert!(sovereign.min_confidence < public.min_confidence);
+        assert_eq!(sovereign.mode, TransformMode::Redact);
+        assert_eq!(public.mode, TransformMode::Tokenize);
+    }
+
+    #[test]
+    fn thresholds_tighten_with_tier() {
+        let tiers = [
+            Classification::Public,
+            Classification::Internal,
+            Classification::Confidential,
+            Classification::Restricted,
+            Classification::Sovereign,
+        ];
+        for pair in tiers.windows(2) {
+            assert!(options_for(pair[1]).min_confidence <= options_for(pair[0]).min_confidence);
+        }
+    }
+
+    #[test]
+    fn tier_decides_weak_matches_and_reversibility() {
+        // Name-field matches are low confidence (0.70)
+        let input = serde_json::json!({ "name": "Alice Smith", "email": "alice@example.com" });
+
+        let sovereign = sanitize_with(&input, &options_for(Classification::Sovereign));
+        assert_ne!(sovereign.sanitized_data["name"], "Alice Smith");
+        assert!(sovereign.token_vault.is_empty());
+
+        let public = sanitize_with(&input, &options_for(Classification::Public));
+        assert_eq!(public.sanitized_data["name"], "Alice Smith");
+        assert_ne!(public.sanitized_data["email"], "alice@example.com");
+        assert!(!public.token_vault.is_empty());
+    }
+
+    #[test]
+    fn callers_can_override_tier_defaults() {
+        let options = SanitizeOptions {
+            mode: TransformMode::Redact,
+            ..options_for(Classification::Public)
+        };
+        let input = serde_json::json!({ "email": "alice@example.com" });
+        assert!(sanitize_with(&input, &options).token_vault.is_empty());
+    }
+
+    #[test]
+    fn cancelled_sanitize_returns_no_output() {
+        let records: Vec<_> = (0..500)
+            .map(|i| serde_json::json!({ "email": format!("u{}@example.com", i) }))
+            .collect();
+        let input = serde_json::Value::Array(records);
+        let token = polykit_core::cancel::CancellationToken::new();
+        assert!(sanitize_cancellable(&input, &SanitizeOptions::default(), &token).is_ok());
+
+        token.cancel();
+        let cancelled = sanitize_cancellable(&input, &SanitizeOptions::default(), &token);
+        assert_eq!(cancelled.unwrap_err().processed, 0);
+    }
+
+    #[test]
+    fn cancelled_scan_keeps_partial_detections() {
+        let token = polykit_core::cancel::CancellationToken::new();
+        token.cancel();
+        let input = serde_json::json!({ "email": "alice@example.com" });
+        let cancelled = detect::scan_cancellable(&input, &Default::default(), &token).unwrap_err();
+        assert!(cancelled.partial.is_empty());
+    }
+
+    fn batch() -> Vec<serde_json::Value> {
+        vec![
+            serde_json::json!({ "ssn": "219-09-9999", "email": "bob@example.com" }),
+            serde_json::json!({ "note": "nothing sensitive" }),
+            serde_json::json!({ "email": "carol@example.com" }),
+        ]
+    }
+
+    #[test]
+    fn batch_results_are_per_record() {
+        let results = sanitize_batch(&batch(), &SanitizeOptions::default());
+        assert_eq!(results.len(), 3);
+        // Two detections in the first record, one in the last
+        assert_eq!(results[0].audit_entries.len(), 2 * results[2].audit_entries.len());
+        assert!(results[1].clean);
+        assert!(results[1].audit_entries.is_empty());
+        assert_eq!(results[1].sanitized_data, batch()[1]);
+        assert_ne!(results[2].sanitized_data["email"], "carol@example.com");
+    }
+
+    #[test]
+    fn batch_shares_one_audit_chain() {
+        let results = sanitize_batch(&batch(), &SanitizeOptions::default());
+        let chain: Vec<AuditEntry> =
+            results.iter().flat_map(|r| r.audit_entries.iter().cloned()).collect();
+        assert!(audit::verify_chain(&chain, audit::CHAIN_GENESIS));
+        // The clean second record leaves the chain at the first record's end
+        let first_head = &results[0].audit_entries.last().unwrap().chain_hash;
+        assert!(audit::verify_chain(&results[2].audit_entries, first_head));
+        assert!(!audit::verify_chain(&results[2].audit_entries, audit::CHAIN_GENESIS));
+    }
+
+    #[test]
+    fn batch_without_shared_chain_restarts_each_record() {
+        let options = SanitizeOptions { share_audit_chain: false, ..Default::default() };
+        let results = sanitize_batch(&batch(), &options);
+        for result in &results {
+            assert!(audit::verify_chain(&result.audit_entries, audit::CHAIN_GENESIS));
+        }
+    }
+
+    #[test]
+    fn clean_document_is_affirmatively_clean() {
+        let result = sanitize(&serde_json::json!({ "note": "nothing sensitive", "count": 2 }));
+        assert!(result.scanned);
+        assert!(result.clean);
+    }
+
+    #[test]
+    fn document_with_pii_is_not_clean() {
+        let result = sanitize(&serde_json::json!({ "ssn": "219-09-9999" }));
+        assert!(result.scanned);
+        assert!(!result.clean);
+    }
+
+    #[test]
+    fn matches_below_threshold_are_not_clean() {
+        // Left in place by Public's 0.90 threshold, but still a detection
+        let input = serde_json::json!({ "name": "Alice Smith" });
+        let result = sanitize_with(&input, &options_for(Classification::Public));
+        assert_eq!(result.sanitized_data, input);
+        assert!(!result.clean);
+
+        let mut rng = polykit_core::crypto::SeededRng::seed_from_u64(1);
+        let options = options_for(Classification::Public);
+        let encrypted = sanitize_encrypted(&input, &options, &[7; 32], &mut rng).unwrap();
+        assert!(!encrypted.clean);
+    }
+
+    #[test]
+    fn ssn_like_field_is_detected() {
+        let detection = detect_field("ssn", "219-09-9999").expect("SSN should be detected");
+        assert!(matches!(detection.data_type, DataType::Ssn));
+        assert_eq!(detection.field_path, "ssn");
+    }
+
+    #[test]
+    fn benign_field_is_not_detected() {
+        assert!(detect_field("nickname", "sunny").is_none());
     }
 }
@@ -1,7 +1,15 @@
 //! Stage 2: Value Transform (redaction / abstraction)
 
+use polykit_core::crypto;
 use crate::{DataType, Detection};
 
+/// A token and the value it stands for.
+#[derive(Debug, Clone)]
+pub struct TokenMapping {
+    pub token: String,
+    pub original: serde_json::Value,
+}
+
 /// Replace detected sensitive values with safe placeholders.
 pub fn redact(input: &serde_json::Value, detections: &[Detection]) -> serde_json::Value {
     let mut output = input.clone();
@@ -14,6 +22,56 @@ pub fn redact(input: &serde_json::Value, detections: &[Detection]) -> serde_json
     output
 }
 
+/// Replace detected values with deterministic tokens. The same value
+/// always maps to the same token, so tokenized records stay joinable.
+pub fn tokenize(input: &serde_json::Value, detections: &[Detection]) -> (serde_json::Value, Vec<TokenMapping>) {
+    let mut output = input.clone();
+    let mut vault: Vec<TokenMapping> = Vec::new();
+
+    for detection in detections {
+        let original = match get_at_path(input, &detection.field_path) {
+            Some(v) => v.clone(),
+            None => continue,
+        };
+        let digest = crypto::sha3_256(original.to_string().as_bytes());
+        let token = format!(
+            "[TOK_{}_{}]",
+            type_tag(&detection.data_type),
+            digest[..6].iter().map(|b| format!("{:02x}", b)).collect::<String>()
+        );
+        set_at_path(&mut output, &detection.field_path, serde_json::Value::String(token.clone()));
+        if !vault.iter().any(|m| m.token == token) {
+            vault.push(TokenMapping { token, original });
+        }
+    }
+
+    (output, vault)
+}
+
+/// Restore tokenized values from a vault.
+pub fn detokenize(input: &serde_json::Value, vault: &[TokenMapping]) -> serde_json::Value {
+    match input {
+        serde_json::Value::String(s) => vault
+            .iter()
+            .find(|m| &m.token == s)
+            .map(|m| m.original.clone())
+            .unwrap_or_else(|| input.clone()),
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.iter().map(|v| detokenize(v, vault)).collect())
+        }
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter().map(|(k, v)| (k.clone(), detokenize(v, vault))).collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Short type tag used inside tokens.
+fn type_tag(data_type: &DataType) -> String {
+    let placeholder = placeholder_for(data_type);
+    placeholder.trim_start_matches('[').trim_end_matches(']').to_string()
+}
+
 /// Generate a safe placeholder for a data type.
 fn placeholder_for(data_type: &DataType) -> String {
     match data_type {
@@ -27,28 +85,75 @@ fn placeholder_for(data_type: &DataType) -> String {
         DataType::MedicalRecord => "[HIPAA_MEDICAL]".to_string(),
         DataType::FinancialAccount => "[PII_FINANCIAL]".to_string(),
         DataType::BiometricData => "[PII_BIOMETRIC]".to_string(),
+        DataType::PassportNumber => "[PII_PASSPORT]".to_string(),
+        DataType::DriversLicense => "[PII_DRIVERS_LICENSE]".to_string(),
+        DataType::SecretMaterial => "[SECRET]".to_string(),
         DataType::Custom(name) => format!("[PII_{}]", name.to_uppercase()),
     }
 }
 
+/// One step of a detection path: `logs[2].message` → Key, Index, Key.
+enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+fn segments(path: &str) -> Vec<Segment<'_>> {
+    let mut out = Vec::new();
+    for part in path.split('.') {
+        let (key, mut rest) = match part.find('[') {
+            Some(i) => (&part[..i], &part[i..]),
+            None => (part, ""),
+        };
+        if !key.is_empty() || rest.is_empty() {
+            out.push(Segment::Key(key));
+        }
+        while let Some(end) = rest.find(']') {
+            if let Ok(index) = rest[1..end].parse() {
+                out.push(Segment::Index(index));
+            }
+            rest = &rest[end + 1..];
+        }
+    }
+    out
+}
+
+pub(crate) fn get_at_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    segments(path).iter().try_fold(value, |current, segment| match segment {
+        Segment::Key(key) => current.as_object()?.get(*key),
+        Segment::Index(i) => current.as_array()?.get(*i),
+    })
+}
+
 fn set_at_path(value: &mut serde_json::Value, path: &str, replacement: serde_json::Value) {
-    let parts: Vec<&str> = path.split('.').collect();
+    let segments = segments(path);
+    let (last, parents) = match segments.split_last() {
+        Some(split) => split,
+        None => return,
+    };
+
     let mut current = value;
+    for segment in parents {
+        let next = match segment {
+            Segment::Key(key) => current.as_object_mut().and_then(|obj| obj.get_mut(*key)),
+            Segment::Index(i) => current.as_array_mut().and_then(|arr| arr.get_mut(*i)),
+        };
+        current = match next {
+            Some(next) => next,
+            None => return,
+        };
+    }
 
-    for (i, part) in parts.iter().enumerate() {
-        if i == parts.len() - 1 {
+    match last {
+        Segment::Key(key) => {
             if let Some(obj) = current.as_object_mut() {
-                obj.insert(part.to_string(), replacement);
-                return;
+                obj.insert(key.to_string(), replacement);
             }
-        } else {
-            if let Some(obj) = current.as_object_mut() {
-                if let Some(next) = obj.get_mut(*part) {
-                    current = next;
-                    continue;
-                }
+        }
+        Segment::Index(i) => {
+            if let Some(slot) = current.as_array_mut().and_then(|arr| arr.get_mut(*i)) {
+                *slot = replacement;
             }
-            return;
         }
     }
 }
@@ -0,0 +1,182 @@
+//! Typed app configuration for init_app
+//!
+//! One JSON document carries everything an app sets at init: identity
+//! context, lex namespace and region, tier limits, classification policy,
+//! widget RBAC, and demo mode. `from_json` checks the fields against each
+//! other so a misconfigured app fails at init rather than at first use.
+
+use std::collections::HashMap;
+
+use polykit_core::classification::{ClassificationPolicy, PolicyBuilder};
+use polykit_core::error::MeteringDimension;
+use polykit_core::identity::{self, AppContext};
+use polykit_eslite::sync::Region;
+use serde::{Deserialize, Serialize};
+
+/// Tier limits per metering dimension (mirrors `TierLimits` in
+/// polykit_metering.fl). Dimensions left out are unlimited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TierConfig {
+    pub tier_name: String,
+    #[serde(default)]
+    pub limits: HashMap<MeteringDimension, u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolykitConfig {
+    pub app_id: String,
+    /// HKDF derivation context (e.g., "poly-data-v1")
+    pub hkdf_context: String,
+    /// Lex namespace (e.g., "polylabs.data" or "esn/region/eu/org/polylabs")
+    pub lex_namespace: String,
+    #[serde(default)]
+    pub region: Region,
+    #[serde(default)]
+    pub tier: Option<TierConfig>,
+    #[serde(default)]
+    pub classification_policy: Option<ClassificationPolicy>,
+    /// Required roles per widget id, checked with `rbac::check_access`
+    #[serde(default)]
+    pub widget_roles: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub demo_mode: bool,
+}
+
+impl PolykitConfig {
+    /// Parse and validate a config document.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let config: PolykitConfig =
+            serde_json::from_str(json).map_err(|e| format!("invalid config: {}", e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Cross-field checks: required fields set, the namespace's region (if
+    /// it names one) matches `region`, and the policy's patterns compile.
+    pub fn validate(&self) -> Result<(), String> {
+        for (field, value) in [
+            ("app_id", &self.app_id),
+            ("hkdf_context", &self.hkdf_context),
+            ("lex_namespace", &self.lex_namespace),
+        ] {
+            if value.trim().is_empty() {
+                return Err(format!("config field {} is empty", field));
+            }
+        }
+
+        if let Some(named) = namespace_region(&self.lex_namespace)? {
+            if named != self.region {
+                return Err(format!(
+                    "lex_namespace {} is in region {} but config region is {}",
+                    self.lex_namespace,
+                    named.as_str(),
+                    self.region.as_str()
+                ));
+            }
+        }
+
+        if let Some(tier) = &self.tier {
+            if tier.tier_name.trim().is_empty() {
+                return Err("tier_name is empty".to_string());
+            }
+        }
+
+        if let Some(policy) = &self.classification_policy {
+            let mut builder = PolicyBuilder::new();
+            for rule in &policy.rules {
+                builder = builder.rule(&rule.pattern, rule.classification);
+            }
+            builder.build().map_err(|e| format!("classification_policy: {:?}", e))?;
+        }
+
+        for (widget, roles) in &self.widget_roles {
+            if roles.iter().any(|r| r.trim().is_empty()) {
+                return Err(format!("widget {} has an empty role", widget));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn app_context(&self) -> AppContext {
+        identity::create_app_context(&self.app_id, &self.hkdf_context, &self.lex_namespace)
+    }
+}
+
+/// Region named by a lex namespace path: `esn/region/<r>/...` or
+/// `esn/global/...`. Dotted app namespaces name no region.
+fn namespace_region(namespace: &str) -> Result<Option<Region>, String> {
+    let segments: Vec<&str> = namespace.split('/').collect();
+    match segments.as_slice() {
+        ["esn", "global", ..] => Ok(Some(Region::Global)),
+        ["esn", "region", name, ..] => match *name {
+            "us" => Ok(Some(Region::Us)),
+            "eu" => Ok(Some(Region::Eu)),
+            other => Err(format!("unknown region {} in lex_namespace", other)),
+        },
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polykit_core::classification::Classification;
+
+    const VALID: &str = r#"{
+        "app_id": "poly-data",
+        "hkdf_context": "poly-data-v1",
+        "lex_namespace": "esn/region/eu/org/polylabs",
+        "region": "Eu",
+        "tier": { "tier_name": "pro", "limits": { "Storage": 1000000 } },
+        "classification_policy": {
+            "rules": [{ "pattern": "/finance/**", "classification": "Confidential" }],
+            "minimum": "Internal"
+        },
+        "widget_roles": { "polykit-li-review-queue": ["compliance"] },
+        "demo_mode": true
+    }"#;
+
+    #[test]
+    fn valid_config_loads() {
+        let config = PolykitConfig::from_json(VALID).unwrap();
+        assert_eq!(config.region, Region::Eu);
+        assert_eq!(config.tier.unwrap().limits[&MeteringDimension::Storage], 1_000_000);
+        let policy = config.classification_policy.unwrap();
+        assert_eq!(policy.minimum, Some(Classification::Internal));
+        assert!(config.demo_mode);
+    }
+
+    #[test]
+    fn region_and_namespace_must_agree() {
+        let mismatched = VALID.replace(r#""region": "Eu""#, r#""region": "Us""#);
+        let err = PolykitConfig::from_json(&mismatched).unwrap_err();
+        assert!(err.contains("is in region eu but config region is us"), "{}", err);
+    }
+
+    #[test]
+    fn dotted_namespace_accepts_any_region() {
+        let config = PolykitConfig::from_json(
+            r#"{"app_id": "poly-data", "hkdf_context": "poly-data-v1",
+                "lex_namespace": "polylabs.data", "region": "Us"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.region, Region::Us);
+        assert_eq!(config.app_context().lex_namespace, "polylabs.data");
+    }
+
+    #[test]
+    fn empty_fields_and_bad_policies_are_rejected() {
+        let empty_app = VALID.replace(r#""app_id": "poly-data""#, r#""app_id": " ""#);
+        assert!(PolykitConfig::from_json(&empty_app).is_err());
+        let unknown_region = VALID.replace("esn/region/eu", "esn/region/apac");
+        assert!(PolykitConfig::from_json(&unknown_region).is_err());
+        let empty_role = VALID.replace(r#"["compliance"]"#, r#"[""]"#);
+        assert!(PolykitConfig::from_json(&empty_role).is_err());
+    }
+
+    #[test]
+    fn init_app_reports_config_errors() {
+        let report: serde_json::Value = serde_json::from_str(&crate::init_app("{}")).unwrap();
+        assert!(report["error"].as_str().unwrap().starts_with("invalid config"));
+    }
+}
@@ -0,0 +1,221 @@
+//! WASM-side keystore for secret-key handles
+//!
+//! Master seeds (`generate_master_seed`, `decode_mnemonic`), derived
+//! identities (`derive_identity`), and shared secrets
+//! (`decapsulate_with_handle`) are parked here, and JS gets an opaque
+//! `u32` handle; only public keys, signatures, ciphertexts, and handles
+//! cross the boundary (plus the mnemonic words, shown to the user). A
+//! shared-secret handle is used through `seal_with_handle` /
+//! `open_with_handle`. Entries are zeroized on release.
+
+use std::collections::HashMap;
+
+use polykit_core::crypto::{self, Rng, AEAD_NONCE_LEN};
+use polykit_core::error::{PolykitError, Result};
+use polykit_core::identity::DerivedIdentity;
+use polykit_core::secret::Secret;
+
+/// HKDF info for message keys derived from a shared-secret handle.
+const HANDLE_AEAD_INFO: &[u8] = b"polykit-handle-aead-v1";
+
+/// Secret material behind one handle.
+pub enum KeyEntry {
+    /// SPARK master seed, generated here or recovered from its mnemonic
+    MasterSeed(Secret<[u8; 32]>),
+    Identity(DerivedIdentity),
+    /// ML-KEM shared secret from `decapsulate`
+    SharedSecret(Secret<[u8; 32]>),
+}
+
+/// Handle table. Handles start at 1 and are never reused, so a stale
+/// handle fails instead of reaching another key.
+#[derive(Default)]
+pub struct KeyStore {
+    entries: HashMap<u32, KeyEntry>,
+    next_handle: u32,
+}
+
+impl KeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store `entry` under a fresh handle. Fails once the handle space is
+    /// used up rather than wrapping onto a live handle.
+    pub fn insert(&mut self, entry: KeyEntry) -> Result<u32> {
+        let handle = self
+            .next_handle
+            .checked_add(1)
+            .ok_or_else(|| PolykitError::Crypto("key handles exhausted".to_string()))?;
+        self.next_handle = handle;
+        self.entries.insert(handle, entry);
+        Ok(handle)
+    }
+
+    pub fn master_seed(&self, handle: u32) -> Result<&[u8; 32]> {
+        match self.entries.get(&handle) {
+            Some(KeyEntry::MasterSeed(seed)) => Ok(seed),
+            Some(_) => Err(PolykitError::Crypto(format!("handle {} is not a master seed", handle))),
+            None => Err(unknown(handle)),
+        }
+    }
+
+    pub fn identity(&self, handle: u32) -> Result<&DerivedIdentity> {
+        match self.entries.get(&handle) {
+            Some(KeyEntry::Identity(identity)) => Ok(identity),
+            Some(_) => Err(PolykitError::Crypto(format!("handle {} is not an identity", handle))),
+            None => Err(unknown(handle)),
+        }
+    }
+
+    /// ML-DSA-87 signature with the identity's signing key.
+    pub fn sign(&self, handle: u32, message: &[u8]) -> Result<Vec<u8>> {
+        crypto::sign(&self.identity(handle)?.signing_secret_key, message)
+    }
+
+    /// Decapsulate with the identity's ML-KEM-1024 key, storing the shared
+    /// secret under a new handle.
+    pub fn decapsulate(&mut self, handle: u32, kem_ciphertext: &[u8]) -> Result<u32> {
+        let identity = self.identity(handle)?;
+        let shared_secret =
+            crypto::kem_decapsulate(&identity.encryption_secret_key, kem_ciphertext)?;
+        self.insert(KeyEntry::SharedSecret(shared_secret))
+    }
+
+    /// AES-256-GCM under the shared secret behind `handle`, with a fresh
+    /// random nonce. Returns `(nonce, ciphertext)`.
+    pub fn seal(
+        &self,
+        handle: u32,
+        plaintext: &[u8],
+        aad: &[u8],
+        rng: &mut dyn Rng,
+    ) -> Result<([u8; AEAD_NONCE_LEN], Vec<u8>)> {
+        let key = self.message_key(handle)?;
+        let mut nonce = [0u8; AEAD_NONCE_LEN];
+        rng.fill_bytes(&mut nonce);
+        Ok((nonce, crypto::aead_seal(&key, &nonce, aad, plaintext)?))
+    }
+
+    /// Decrypt a `seal` ciphertext with the shared secret behind `handle`.
+    pub fn open(
+        &self,
+        handle: u32,
+        nonce: &[u8; AEAD_NONCE_LEN],
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>> {
+        let key = self.message_key(handle)?;
+        crypto::aead_open(&key, nonce, aad, ciphertext)
+    }
+
+    /// AEAD key derived from a shared secret, never the raw secret itself.
+    fn message_key(&self, handle: u32) -> Result<Secret<[u8; 32]>> {
+        match self.entries.get(&handle) {
+            Some(KeyEntry::SharedSecret(secret)) => {
+                crypto::hkdf_sha3_256(&secret[..], HANDLE_AEAD_INFO)
+            }
+            Some(_) => Err(PolykitError::Crypto(format!(
+                "handle {} is not a shared secret",
+                handle
+            ))),
+            None => Err(unknown(handle)),
+        }
+    }
+
+    /// Drop a handle, zeroizing its secret material.
+    pub fn release(&mut self, handle: u32) -> Result<()> {
+        match self.entries.remove(&handle) {
+            Some(KeyEntry::Identity(identity)) => {
+                identity.destroy();
+                Ok(())
+            }
+            Some(KeyEntry::MasterSeed(_)) | Some(KeyEntry::SharedSecret(_)) => Ok(()),
+            None => Err(unknown(handle)),
+        }
+    }
+}
+
+fn unknown(handle: u32) -> PolykitError {
+    PolykitError::Crypto(format!("unknown key handle {}", handle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polykit_core::crypto::SeededRng;
+    use polykit_core::identity::{create_app_context, derive_identity};
+
+    fn store_with_identity() -> (KeyStore, u32, DerivedIdentity) {
+        let ctx = create_app_context("polydata", "poly-data-v1", "polydata");
+        let identity = derive_identity(&[7; 32], &ctx).unwrap();
+        let mut store = KeyStore::new();
+        let handle = store.insert(KeyEntry::Identity(identity.clone())).unwrap();
+        (store, handle, identity)
+    }
+
+    #[test]
+    fn handle_signs_and_decapsulates_without_exposing_keys() {
+        let (mut store, handle, identity) = store_with_identity();
+        let signature = store.sign(handle, b"hello").unwrap();
+        assert!(crypto::verify(&identity.signing_public_key, b"hello", &signature).unwrap());
+
+        let (sender_pk, sender_sk) = crypto::signing_keypair(&mut SeededRng::seed_from_u64(1));
+        let sealed = crypto::seal(
+            &identity.encryption_public_key,
+            &sender_sk,
+            &sender_pk,
+            b"payload",
+            b"",
+            &mut SeededRng::seed_from_u64(2),
+        )
+        .unwrap();
+        let shared = store.decapsulate(handle, &sealed.kem_ciphertext).unwrap();
+        assert_ne!(shared, handle);
+        // A shared-secret handle can't be used as an identity
+        assert!(store.sign(shared, b"hello").is_err());
+    }
+
+    #[test]
+    fn released_handles_are_never_reused() {
+        let (mut store, handle, identity) = store_with_identity();
+        store.release(handle).unwrap();
+        assert!(store.sign(handle, b"hello").is_err());
+        assert!(store.release(handle).is_err());
+        let next = store.insert(KeyEntry::Identity(identity)).unwrap();
+        assert_ne!(next, handle);
+    }
+
+    #[test]
+    fn exhausted_handles_fail_instead_of_wrapping() {
+        let (mut store, handle, identity) = store_with_identity();
+        store.next_handle = u32::MAX;
+        assert!(store.insert(KeyEntry::Identity(identity)).is_err());
+        assert!(store.sign(handle, b"hello").is_ok());
+    }
+
+    #[test]
+    fn shared_secret_handles_seal_and_open() {
+        let (mut store, handle, identity) = store_with_identity();
+        let (sender_pk, sender_sk) = crypto::signing_keypair(&mut SeededRng::seed_from_u64(1));
+        let sealed = crypto::seal(
+            &identity.encryption_public_key,
+            &sender_sk,
+            &sender_pk,
+            b"payload",
+            b"",
+            &mut SeededRng::seed_from_u64(2),
+        )
+        .unwrap();
+        // Both ends of the exchange hold the same secret
+        let ours = store.decapsulate(handle, &sealed.kem_ciphertext).unwrap();
+        let theirs = store.decapsulate(handle, &sealed.kem_ciphertext).unwrap();
+
+        let mut rng = SeededRng::seed_from_u64(3);
+        let (nonce, ciphertext) = store.seal(ours, b"hello", b"aad", &mut rng).unwrap();
+        assert_eq!(store.open(theirs, &nonce, &ciphertext, b"aad").unwrap(), b"hello");
+        assert!(store.open(theirs, &nonce, &ciphertext, b"other").is_err());
+        // An identity handle isn't a message key
+        assert!(store.seal(handle, b"hello", b"aad", &mut rng).is_err());
+    }
+}
@@ -10,28 +10,432 @@
 //! `wasm_abi` annotations. This file provides only the bootstrap and
 //! any hand-written glue that can't be expressed in FastLang.
 
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use polykit_core::cancel::CancellationToken;
+use polykit_core::clock::HostClock;
+use polykit_core::crypto::Rng;
+use polykit_core::error::PolykitError;
+use polykit_core::identity::{AppContext, AppContextRegistry};
+use polykit_core::secret::Secret;
 use wasm_bindgen::prelude::*;
 
+pub mod config;
+pub mod keystore;
+
+pub use config::PolykitConfig;
+use keystore::{KeyEntry, KeyStore};
+
+thread_local! {
+    /// Validated config from the last init_app call (WASM is
+    /// single-threaded); later exports read tier, policy, and roles here.
+    static APP_CONFIG: RefCell<Option<PolykitConfig>> = const { RefCell::new(None) };
+    /// Error from the most recent failed evaluate call, for last_error.
+    static LAST_ERROR: RefCell<Option<PolykitError>> = const { RefCell::new(None) };
+    /// Every app context initialized in this instance, for collision checks.
+    static CONTEXTS: RefCell<AppContextRegistry> = RefCell::new(AppContextRegistry::new());
+    /// Secret keys behind the handles given to JS.
+    static KEYSTORE: RefCell<KeyStore> = RefCell::new(KeyStore::new());
+}
+
 // --- App Initialization (hand-written: not a circuit) ---
 
+/// Initialize from a `PolykitConfig` JSON document. Returns
+/// `{"error": ...}` if the config is malformed or inconsistent, or if its
+/// HKDF context or lex namespace collides with another app's.
 #[wasm_bindgen]
-pub fn init_app(app_id: &str, hkdf_context: &str, lex_namespace: &str, demo_mode: bool) -> String {
-    let ctx = polykit_core::identity::create_app_context(app_id, hkdf_context, lex_namespace);
-    serde_json::json!({
+pub fn init_app(config_json: &str) -> String {
+    let config = match PolykitConfig::from_json(config_json) {
+        Ok(config) => config,
+        Err(e) => return serde_json::json!({ "error": e }).to_string(),
+    };
+    let ctx = config.app_context();
+    if let Err(e) = CONTEXTS.with(|contexts| contexts.borrow_mut().register(&ctx)) {
+        return serde_json::json!({ "error": e.to_string() }).to_string();
+    }
+    let response = serde_json::json!({
         "app_id": ctx.app_id,
         "lex_namespace": ctx.lex_namespace,
-        "demo_mode": demo_mode,
+        "region": config.region,
+        "demo_mode": config.demo_mode,
         "status": "initialized",
+    });
+    APP_CONFIG.with(|app| *app.borrow_mut() = Some(config));
+    response.to_string()
+}
+
+// --- Identity Handles (hand-written: secret keys stay in WASM) ---
+
+/// Fresh random master seed, kept in the WASM keystore:
+/// `{"handle", "seed_commitment"}`. The commitment is what the platform
+/// attests to; show the seed to the user with `encode_mnemonic`.
+#[wasm_bindgen]
+pub fn generate_master_seed() -> String {
+    let mut seed = Secret::new([0u8; 32]);
+    polykit_core::crypto::HostRng.fill_bytes(&mut seed[..]);
+    store_seed(seed)
+}
+
+/// Derive the identity for `app_id` from the master seed behind
+/// `seed_handle`, keeping its secret keys in the WASM keystore. The app's
+/// context comes from the registry `init_app` fills, so its collision
+/// checks always apply; an app that hasn't been initialized is an error.
+/// The seed is only accepted with an attestation blob over its commitment
+/// (hardware-backed if `require_hardware`). Returns `{"handle", "user_id",
+/// "signing_public_key", "encryption_public_key", "provenance"}`; the
+/// handle is the only way JS can use the secret keys.
+#[wasm_bindgen]
+pub fn derive_identity(
+    seed_handle: u32,
+    app_id: &str,
+    attestation_blob: &[u8],
+    require_hardware: bool,
+) -> String {
+    let ctx = match CONTEXTS.with(|contexts| contexts.borrow().get(app_id).cloned()) {
+        Some(ctx) => ctx,
+        None => {
+            return serde_json::json!({
+                "error": format!("app {} is not registered; call init_app first", app_id)
+            })
+            .to_string();
+        }
+    };
+    let derived = KEYSTORE.with(|store| {
+        let seed = store.borrow().master_seed(seed_handle).copied().map(Secret::new)?;
+        polykit_core::identity::derive_identity_attested(
+            &seed,
+            &ctx,
+            attestation_blob,
+            require_hardware,
+        )
+    });
+    match derived {
+        Ok((identity, attestation)) => {
+            let mut response = serde_json::json!({
+                "user_id": identity.user_id,
+                "signing_public_key": identity.signing_public_key,
+                "encryption_public_key": identity.encryption_public_key,
+                "provenance": {
+                    "platform": attestation.platform,
+                    "hardware_backed": attestation.is_hardware_backed(),
+                },
+            });
+            match KEYSTORE.with(|store| store.borrow_mut().insert(KeyEntry::Identity(identity))) {
+                Ok(handle) => {
+                    response["handle"] = handle.into();
+                    response.to_string()
+                }
+                Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+            }
+        }
+        Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+    }
+}
+
+/// ML-DSA-87 signature over `message` with the identity behind `handle`:
+/// `{"signature": [...]}`.
+#[wasm_bindgen]
+pub fn sign_with_handle(handle: u32, message: &[u8]) -> String {
+    match KEYSTORE.with(|store| store.borrow().sign(handle, message)) {
+        Ok(signature) => serde_json::json!({ "signature": signature }).to_string(),
+        Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+    }
+}
+
+/// Decapsulate an ML-KEM-1024 ciphertext with the identity behind
+/// `handle`. The shared secret stays in WASM: `{"handle": ...}` names it.
+#[wasm_bindgen]
+pub fn decapsulate_with_handle(handle: u32, kem_ciphertext: &[u8]) -> String {
+    match KEYSTORE.with(|store| store.borrow_mut().decapsulate(handle, kem_ciphertext)) {
+        Ok(shared) => serde_json::json!({ "handle": shared }).to_string(),
+        Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+    }
+}
+
+/// Encrypt with the shared secret behind `handle` (from
+/// `decapsulate_with_handle`): `{"nonce": [...], "ciphertext": [...]}`.
+#[wasm_bindgen]
+pub fn seal_with_handle(handle: u32, plaintext: &[u8], aad: &[u8]) -> String {
+    let sealed = KEYSTORE.with(|store| {
+        store.borrow().seal(handle, plaintext, aad, &mut polykit_core::crypto::HostRng)
+    });
+    match sealed {
+        Ok((nonce, ciphertext)) => {
+            serde_json::json!({ "nonce": nonce, "ciphertext": ciphertext }).to_string()
+        }
+        Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+    }
+}
+
+/// Decrypt a `seal_with_handle` ciphertext with the shared secret behind
+/// `handle`: `{"plaintext": [...]}`.
+#[wasm_bindgen]
+pub fn open_with_handle(handle: u32, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> String {
+    let opened = <&[u8; polykit_core::crypto::AEAD_NONCE_LEN]>::try_from(nonce)
+        .map_err(|_| PolykitError::Crypto("nonce must be 12 bytes".to_string()))
+        .and_then(|nonce| {
+            KEYSTORE.with(|store| store.borrow().open(handle, nonce, ciphertext, aad))
+        });
+    match opened {
+        Ok(plaintext) => serde_json::json!({ "plaintext": plaintext }).to_string(),
+        Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+    }
+}
+
+/// Zeroize and forget a handle's secret material (e.g., on sign-out).
+/// Returns false for an unknown handle.
+#[wasm_bindgen]
+pub fn release_handle(handle: u32) -> bool {
+    KEYSTORE.with(|store| store.borrow_mut().release(handle)).is_ok()
+}
+
+/// 24-word phrase for the master seed behind `seed_handle`, for recovery
+/// UIs: `{"words": [...]}`.
+#[wasm_bindgen]
+pub fn encode_mnemonic(seed_handle: u32) -> String {
+    let phrase = KEYSTORE.with(|store| {
+        store.borrow().master_seed(seed_handle).map(polykit_core::identity::mnemonic::encode)
+    });
+    match phrase {
+        Ok(phrase) => {
+            serde_json::json!({ "words": phrase.split(' ').collect::<Vec<_>>() }).to_string()
+        }
+        Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+    }
+}
+
+/// Recover a master seed from its 24-word phrase into the keystore:
+/// `{"handle", "seed_commitment"}`, or `{"error": ...}` naming the bad word's position
+/// or a checksum mismatch. The seed itself never reaches JS.
+#[wasm_bindgen]
+pub fn decode_mnemonic(phrase: &str) -> String {
+    match polykit_core::identity::mnemonic::decode(phrase) {
+        Ok(seed) => store_seed(seed),
+        Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+    }
+}
+
+/// Park a master seed in the keystore: `{"handle", "seed_commitment"}`.
+fn store_seed(seed: Secret<[u8; 32]>) -> String {
+    let commitment = polykit_core::identity::seed_commitment(&seed);
+    match KEYSTORE.with(|store| store.borrow_mut().insert(KeyEntry::MasterSeed(seed))) {
+        Ok(handle) => {
+            serde_json::json!({ "handle": handle, "seed_commitment": commitment }).to_string()
+        }
+        Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+    }
+}
+
+// --- Health (hand-written: aggregates runtime state) ---
+
+/// Readiness report per subsystem plus an overall status. The app is
+/// ready once init_app has run and the crypto backend is bound; sync and
+/// wire are reported for display but don't gate readiness.
+#[wasm_bindgen]
+pub fn health() -> String {
+    APP_CONFIG
+        .with(|app| health_report(app.borrow().as_ref().map(PolykitConfig::app_context).as_ref()))
+        .to_string()
+}
+
+fn health_report(app: Option<&AppContext>) -> serde_json::Value {
+    let crypto_ready = polykit_core::crypto::backend_available();
+    let ready = app.is_some() && crypto_ready;
+    serde_json::json!({
+        "status": if ready { "ready" } else { "not_ready" },
+        "subsystems": {
+            "identity": {
+                "ready": app.is_some(),
+                "app_id": app.map(|ctx| ctx.app_id.clone()),
+            },
+            "crypto": { "ready": crypto_ready },
+            // In production: read from the SyncManager and WireSession held by the codegen'd runtime
+            "sync": { "registered_tables": 0 },
+            "wire": { "session": "disconnected" },
+        },
+    })
+}
+
+// --- Inline Field Check (hand-written: per-keystroke fast path) ---
+
+/// Returns `{"pii": bool, "data_type": ..., "confidence": ...}` for one
+/// form field. The value itself is never echoed back to JS.
+#[wasm_bindgen]
+pub fn check_field(field_name: &str, value: &str) -> String {
+    match polykit_sanitize::detect_field(field_name, value) {
+        Some(detection) => serde_json::json!({
+            "pii": true,
+            "data_type": format!("{:?}", detection.data_type),
+            "confidence": detection.confidence,
+        }),
+        None => serde_json::json!({ "pii": false }),
+    }
+    .to_string()
+}
+
+// --- Batch Classification (hand-written: one crossing per ingest batch) ---
+
+/// Classify a JSON array of paths against a `ClassificationPolicy`,
+/// returning per path its tier, scatter policy, and the policy's retention
+/// for that tier (`null` when none is mandated). With `budget_ms`, a batch
+/// still running when the budget runs out returns `{"error", "processed"}`.
+#[wasm_bindgen]
+pub fn classify_full(paths_json: &str, policy_json: &str, budget_ms: Option<u32>) -> String {
+    use polykit_core::classification::{classify_batch, ClassificationPolicy};
+
+    let parsed = serde_json::from_str::<Vec<String>>(paths_json)
+        .map_err(|e| format!("invalid paths: {}", e))
+        .and_then(|paths| {
+            serde_json::from_str::<ClassificationPolicy>(policy_json)
+                .map(|policy| (paths, policy))
+                .map_err(|e| format!("invalid policy: {}", e))
+        });
+    let (paths, policy) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => return serde_json::json!({ "error": e }).to_string(),
+    };
+
+    let refs: Vec<&str> = paths.iter().map(String::as_str).collect();
+    let tiers = match classify_batch(&refs, &policy, &budget_token(budget_ms)) {
+        Ok(tiers) => tiers,
+        Err(cancelled) => return budget_exhausted("classify_full", cancelled.processed),
+    };
+    let results: Vec<serde_json::Value> = paths
+        .iter()
+        .zip(tiers)
+        .map(|(path, tier)| {
+            serde_json::json!({
+                "path": path,
+                "tier": tier.as_str(),
+                "scatter": tier.scatter_policy(),
+                "retention_ms": policy.retention_ms(tier),
+            })
+        })
+        .collect();
+    serde_json::json!(results).to_string()
+}
+
+// --- Budgeted Sanitize (hand-written: host time budget) ---
+
+/// Run the default sanitize pipeline over a JSON document, giving up once
+/// `budget_ms` of host time has passed. A timed-out run returns
+/// `{"error", "processed"}` and no output, since unscanned fields may
+/// still hold PII.
+#[wasm_bindgen]
+pub fn sanitize_with_budget(input_json: &str, budget_ms: u32) -> String {
+    let input: serde_json::Value = match serde_json::from_str(input_json) {
+        Ok(input) => input,
+        Err(e) => {
+            return serde_json::json!({ "error": format!("invalid input: {}", e) }).to_string();
+        }
+    };
+    let options = polykit_sanitize::SanitizeOptions::default();
+    match polykit_sanitize::sanitize_cancellable(&input, &options, &budget_token(Some(budget_ms))) {
+        Ok(result) => serde_json::to_string(&result)
+            .unwrap_or_else(|e| serde_json::json!({ "error": e.to_string() }).to_string()),
+        Err(cancelled) => budget_exhausted("sanitize", cancelled.processed),
+    }
+}
+
+/// Token for one export call. WASM is single-threaded, so JS can't cancel
+/// a running call; the host bounds it with a time budget instead.
+fn budget_token(budget_ms: Option<u32>) -> CancellationToken {
+    match budget_ms {
+        Some(budget) => CancellationToken::with_budget(Arc::new(HostClock), u64::from(budget)),
+        None => CancellationToken::new(),
+    }
+}
+
+fn budget_exhausted(export: &str, processed: usize) -> String {
+    serde_json::json!({
+        "error": format!("{}: time budget exhausted", export),
+        "processed": processed,
     })
     .to_string()
 }
 
+// --- Metering Display (hand-written: string formatting) ---
+
+/// Format a raw metering value for display, e.g. `("Storage", 1288490188)`
+/// → "1.2 GiB". `dimension` is a `MeteringDimension` variant name.
+#[wasm_bindgen]
+pub fn format_dimension(dimension: &str, value: u64) -> String {
+    match serde_json::from_value(serde_json::Value::String(dimension.to_string())) {
+        Ok(dimension) => polykit_console::metering_display::format_dimension(dimension, value),
+        Err(e) => serde_json::json!({ "error": format!("unknown dimension: {}", e) }).to_string(),
+    }
+}
+
+// --- Contact Verification (hand-written: display formatting) ---
+
+/// Safety number for two ML-DSA-87 public keys:
+/// `{"number": "12345 67890 ...", "digits": "1234567890..."}`.
+#[wasm_bindgen]
+pub fn safety_number(local_public_key: &[u8], remote_public_key: &[u8]) -> String {
+    use polykit_core::identity::safety_number::SafetyNumber;
+
+    match SafetyNumber::compute(local_public_key, remote_public_key) {
+        Ok(number) => serde_json::json!({
+            "number": number.to_string(),
+            "digits": number.as_str(),
+        })
+        .to_string(),
+        Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+    }
+}
+
+/// Whether a typed or scanned safety number matches these keys. False if
+/// either key is malformed.
+#[wasm_bindgen]
+pub fn verify_safety_number(
+    local_public_key: &[u8],
+    remote_public_key: &[u8],
+    candidate: &str,
+) -> bool {
+    polykit_core::identity::safety_number::verify_safety_number(
+        local_public_key,
+        remote_public_key,
+        candidate,
+    )
+    .unwrap_or(false)
+}
+
 // --- FLIR ABI Required Export ---
 
+/// Returns 0 on success, otherwise `PolykitError::status_code`; the full
+/// error is then available from `last_error`.
 #[wasm_bindgen]
 pub fn evaluate(context_ptr: i32) -> i32 {
-    let _ = context_ptr;
-    0
+    let result = evaluate_context(context_ptr);
+    let code = result.as_ref().map_or_else(PolykitError::status_code, |_| 0);
+    LAST_ERROR.with(|last| *last.borrow_mut() = result.err());
+    code
+}
+
+fn evaluate_context(context_ptr: i32) -> polykit_core::error::Result<()> {
+    // In production: codegen'd dispatch into the circuit named by the context
+    if context_ptr < 0 {
+        return Err(PolykitError::Wire(format!("invalid context pointer {}", context_ptr)));
+    }
+    Ok(())
+}
+
+/// Structured error from the most recent evaluate call:
+/// `{"code": i32, "message": ..., "context": [...]}`, or `{"code": 0}` if
+/// it succeeded.
+#[wasm_bindgen]
+pub fn last_error() -> String {
+    LAST_ERROR
+        .with(|last| match last.borrow().as_ref() {
+            Some(e) => serde_json::json!({
+                "code": e.status_code(),
+                "message": e.root().to_string(),
+                "context": e.context_chain(),
+            }),
+            None => serde_json::json!({ "code": 0 }),
+        })
+        .to_string()
 }
 
 #[wasm_bindgen]
@@ -59,3 +463,318 @@ pub fn circuit_version() -> String {
 // build pipeline and included here at compile time when available.
 #[cfg(feature = "codegen")]
 include!(concat!(env!("OUT_DIR"), "/codegen_exports.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn health_not_ready_before_init() {
+        let report = health_report(None);
+        assert_eq!(report["status"], "not_ready");
+        assert_eq!(report["subsystems"]["identity"]["ready"], false);
+    }
+
+    #[test]
+    fn health_ready_once_initialized() {
+        let ctx = polykit_core::identity::create_app_context("poly-data", "poly-data-v1", "data");
+        let report = health_report(Some(&ctx));
+        assert_eq!(report["status"], "ready");
+        assert_eq!(report["subsystems"]["identity"]["app_id"], "poly-data");
+        assert_eq!(report["subsystems"]["crypto"]["ready"], true);
+    }
+
+    #[test]
+    fn check_field_reports_type_without_echoing_value() {
+        let report: serde_json::Value =
+            serde_json::from_str(&check_field("ssn", "219-09-9999")).unwrap();
+        assert_eq!(report["pii"], true);
+        assert_eq!(report["data_type"], "Ssn");
+        assert!(!report.to_string().contains("219-09-9999"));
+    }
+
+    #[test]
+    fn check_field_passes_benign_value() {
+        let report: serde_json::Value =
+            serde_json::from_str(&check_field("nickname", "sunny")).unwrap();
+        assert_eq!(report, serde_json::json!({ "pii": false }));
+    }
+
+    #[test]
+    fn classify_full_reports_tier_scatter_and_retention() {
+        let policy = serde_json::json!({
+            "rules": [
+                { "pattern": "/finance/**", "classification": "Confidential" },
+                { "pattern": "/sovereign/**", "classification": "Sovereign" },
+            ],
+            "minimum": "Public",
+            "retention": { "Public": 86400000, "Sovereign": 315360000000u64 },
+        });
+        let paths = r#"["/finance/q3.xlsx", "/sovereign/keys.bin", "/readme.md"]"#;
+        let report: serde_json::Value =
+            serde_json::from_str(&classify_full(paths, &policy.to_string(), None)).unwrap();
+
+        assert_eq!(report[0]["tier"], "CONFIDENTIAL");
+        assert_eq!(report[0]["scatter"]["k"], 5);
+        assert_eq!(report[0]["scatter"]["n"], 7);
+        assert_eq!(report[0]["scatter"]["jurisdictions"], 3);
+        assert_eq!(report[0]["retention_ms"], serde_json::Value::Null);
+
+        assert_eq!(report[1]["tier"], "SOVEREIGN");
+        assert_eq!(report[1]["scatter"]["n"], 13);
+        assert_eq!(report[1]["retention_ms"], 315_360_000_000u64);
+
+        assert_eq!(report[2]["path"], "/readme.md");
+        assert_eq!(report[2]["tier"], "PUBLIC");
+        assert_eq!(report[2]["retention_ms"], 86_400_000);
+    }
+
+    #[test]
+    fn classify_full_uses_default_retention_when_policy_omits_it() {
+        let policy = r#"{"rules": [{"pattern": "/hr/**", "classification": "Restricted"}],
+                         "minimum": null}"#;
+        let report: serde_json::Value =
+            serde_json::from_str(&classify_full(r#"["/hr/payroll.csv"]"#, policy, None)).unwrap();
+        assert_eq!(report[0]["retention_ms"], polykit_core::classification::SEVEN_YEARS_MS);
+    }
+
+    #[test]
+    fn classify_full_rejects_malformed_input() {
+        let report: serde_json::Value =
+            serde_json::from_str(&classify_full("nope", "{}", None)).unwrap();
+        assert!(report["error"].as_str().unwrap().starts_with("invalid paths"));
+    }
+
+    #[test]
+    fn failed_evaluate_is_reported_by_last_error() {
+        assert_eq!(evaluate(-1), 5);
+        let error: serde_json::Value = serde_json::from_str(&last_error()).unwrap();
+        assert_eq!(error["code"], 5);
+        assert_eq!(error["message"], "wire: invalid context pointer -1");
+        assert_eq!(error["context"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn successful_evaluate_clears_last_error() {
+        assert_ne!(evaluate(-1), 0);
+        assert_eq!(evaluate(0), 0);
+        assert_eq!(last_error(), r#"{"code":0}"#);
+    }
+
+    /// Register the polydata app for this test thread.
+    fn init_polydata() {
+        let config = r#"{"app_id": "polydata", "hkdf_context": "poly-data-v1",
+                         "lex_namespace": "polylabs.data"}"#;
+        assert!(!init_app(config).contains("error"));
+    }
+
+    /// Software attestation blob over `commitment`.
+    fn software_attestation(commitment: &[u8]) -> Vec<u8> {
+        let mut blob = vec![
+            polykit_core::identity::SEED_ATTESTATION_VERSION,
+            polykit_core::identity::AttestationPlatform::Software.tag(),
+        ];
+        blob.extend_from_slice(&(commitment.len() as u32).to_be_bytes());
+        blob.extend_from_slice(commitment);
+        blob.extend_from_slice(&0u32.to_be_bytes());
+        blob
+    }
+
+    /// Software attestation for the `[7; 32]` seed behind `seed_handle()`.
+    fn attestation() -> Vec<u8> {
+        software_attestation(&polykit_core::identity::seed_commitment(&[7; 32]))
+    }
+
+    /// Register the polydata app and return a handle for the master seed
+    /// `[7; 32]`, recovered from its phrase.
+    fn seed_handle() -> u32 {
+        init_polydata();
+        let phrase = polykit_core::identity::mnemonic::encode(&[7; 32]);
+        let response: serde_json::Value =
+            serde_json::from_str(&decode_mnemonic(&phrase)).unwrap();
+        response["handle"].as_u64().unwrap() as u32
+    }
+
+    #[test]
+    fn derived_identity_crosses_the_boundary_as_a_handle() {
+        let response = json(&derive_identity(seed_handle(), "polydata", &attestation(), false));
+        let fields: Vec<_> = response.as_object().unwrap().keys().cloned().collect();
+        assert_eq!(
+            fields,
+            ["encryption_public_key", "handle", "provenance", "signing_public_key", "user_id"]
+        );
+
+        let handle = response["handle"].as_u64().unwrap() as u32;
+        let signed: serde_json::Value =
+            serde_json::from_str(&sign_with_handle(handle, b"hello")).unwrap();
+        let signature: Vec<u8> = serde_json::from_value(signed["signature"].clone()).unwrap();
+        let public_key: Vec<u8> =
+            serde_json::from_value(response["signing_public_key"].clone()).unwrap();
+        assert!(polykit_core::crypto::verify(&public_key, b"hello", &signature).unwrap());
+
+        assert!(release_handle(handle));
+        assert!(!release_handle(handle));
+        let after: serde_json::Value =
+            serde_json::from_str(&sign_with_handle(handle, b"hello")).unwrap();
+        assert!(after["error"].is_string());
+    }
+
+    #[test]
+    fn derive_identity_needs_a_seed_handle() {
+        let seed = seed_handle();
+        let identity = json(&derive_identity(seed, "polydata", &attestation(), false));
+        let identity_handle = identity["handle"].as_u64().unwrap() as u32;
+        let response = json(&derive_identity(identity_handle, "polydata", &attestation(), false));
+        assert!(response["error"].as_str().unwrap().contains("not a master seed"));
+
+        assert!(release_handle(seed));
+        let response = json(&derive_identity(seed, "polydata", &attestation(), false));
+        assert!(response["error"].as_str().unwrap().contains("unknown key handle"));
+    }
+
+    #[test]
+    fn mnemonic_round_trips_without_the_seed_leaving_wasm() {
+        init_polydata();
+        let generated = json(&generate_master_seed());
+        let mut fields: Vec<&String> = generated.as_object().unwrap().keys().collect();
+        fields.sort();
+        assert_eq!(fields, ["handle", "seed_commitment"]);
+        let seed = generated["handle"].as_u64().unwrap() as u32;
+
+        let words = json(&encode_mnemonic(seed));
+        let phrase: Vec<String> = serde_json::from_value(words["words"].clone()).unwrap();
+        assert_eq!(phrase.len(), 24);
+        let recovered = json(&decode_mnemonic(&phrase.join(" ")));
+        assert_eq!(recovered["seed_commitment"], generated["seed_commitment"]);
+        let recovered = recovered["handle"].as_u64().unwrap() as u32;
+        assert_ne!(recovered, seed);
+
+        let blob = software_attestation(&bytes(&generated["seed_commitment"]));
+        let original = json(&derive_identity(seed, "polydata", &blob, false));
+        let restored = json(&derive_identity(recovered, "polydata", &blob, false));
+        assert!(original["user_id"].is_array());
+        assert_eq!(original["user_id"], restored["user_id"]);
+
+        let bad = json(&decode_mnemonic("abandon abandon"));
+        assert!(bad["error"].is_string());
+    }
+
+    #[test]
+    fn exhausted_budget_stops_classify_full() {
+        // HostClock is stubbed at 0, so a zero budget is spent on entry
+        let policy = r#"{"rules": [], "minimum": null}"#;
+        let report: serde_json::Value =
+            serde_json::from_str(&classify_full(r#"["/a.txt"]"#, policy, Some(0))).unwrap();
+        assert_eq!(report["error"], "classify_full: time budget exhausted");
+        assert_eq!(report["processed"], 0);
+    }
+
+    #[test]
+    fn sanitize_with_budget_runs_or_reports_exhaustion() {
+        let input = r#"{"email": "alice@example.com"}"#;
+        let result: serde_json::Value =
+            serde_json::from_str(&sanitize_with_budget(input, 1_000)).unwrap();
+        assert_eq!(result["scanned"], true);
+        assert!(!result["sanitized_data"].to_string().contains("alice@example.com"));
+
+        let report: serde_json::Value =
+            serde_json::from_str(&sanitize_with_budget(input, 0)).unwrap();
+        assert_eq!(report["error"], "sanitize: time budget exhausted");
+        assert!(report.get("sanitized_data").is_none());
+    }
+
+    #[test]
+    fn init_app_keeps_the_validated_config() {
+        let config = serde_json::json!({
+            "app_id": "polydata",
+            "hkdf_context": "poly-data-v1",
+            "lex_namespace": "polylabs.data",
+            "tier": { "tier_name": "pro", "limits": { "Storage": 1024 } },
+            "widget_roles": { "audit-log": ["admin"] },
+        });
+        let response: serde_json::Value =
+            serde_json::from_str(&init_app(&config.to_string())).unwrap();
+        assert_eq!(response["status"], "initialized");
+        APP_CONFIG.with(|app| {
+            let app = app.borrow();
+            let stored = app.as_ref().unwrap();
+            assert_eq!(stored.tier.as_ref().unwrap().tier_name, "pro");
+            assert_eq!(stored.widget_roles["audit-log"], ["admin"]);
+        });
+    }
+
+    fn json(response: &str) -> serde_json::Value {
+        serde_json::from_str(response).unwrap()
+    }
+
+    fn bytes(value: &serde_json::Value) -> Vec<u8> {
+        serde_json::from_value(value.clone()).unwrap()
+    }
+
+    #[test]
+    fn decapsulated_handles_round_trip_through_seal_and_open() {
+        let identity = json(&derive_identity(seed_handle(), "polydata", &attestation(), false));
+        let handle = identity["handle"].as_u64().unwrap() as u32;
+        let mut rng = polykit_core::crypto::SeededRng::seed_from_u64(1);
+        let (sender_pk, sender_sk) = polykit_core::crypto::signing_keypair(&mut rng);
+        let sealed = polykit_core::crypto::seal(
+            &bytes(&identity["encryption_public_key"]),
+            &sender_sk,
+            &sender_pk,
+            b"payload",
+            b"",
+            &mut rng,
+        )
+        .unwrap();
+
+        let shared = json(&decapsulate_with_handle(handle, &sealed.kem_ciphertext));
+        let shared = shared["handle"].as_u64().unwrap() as u32;
+        let envelope = json(&seal_with_handle(shared, b"hello", b"topic"));
+        let (nonce, ciphertext) = (bytes(&envelope["nonce"]), bytes(&envelope["ciphertext"]));
+        let opened = json(&open_with_handle(shared, &nonce, &ciphertext, b"topic"));
+        assert_eq!(bytes(&opened["plaintext"]), b"hello");
+
+        assert!(json(&open_with_handle(shared, &nonce, &ciphertext, b"other"))["error"]
+            .is_string());
+        assert!(json(&open_with_handle(shared, &nonce[..8], &ciphertext, b"topic"))["error"]
+            .is_string());
+        assert!(json(&seal_with_handle(handle, b"hello", b"topic"))["error"].is_string());
+        assert!(release_handle(shared));
+        assert!(json(&seal_with_handle(shared, b"hello", b"topic"))["error"].is_string());
+    }
+
+    #[test]
+    fn derive_identity_uses_the_registered_context() {
+        let seed = seed_handle();
+        let unknown = json(&derive_identity(seed, "polymail", &attestation(), false));
+        assert_eq!(unknown["error"], "app polymail is not registered; call init_app first");
+
+        let derived = json(&derive_identity(seed, "polydata", &attestation(), false));
+        let ctx = CONTEXTS.with(|contexts| contexts.borrow().get("polydata").cloned()).unwrap();
+        let expected = polykit_core::identity::derive_identity(&[7; 32], &ctx).unwrap();
+        assert_eq!(derived["user_id"], serde_json::json!(expected.user_id));
+
+        // A colliding app never reaches the registry, so it can't derive
+        let clash = r#"{"app_id": "polymail", "hkdf_context": "poly-data-v1",
+                        "lex_namespace": "polylabs.mail"}"#;
+        assert!(init_app(clash).contains("error"));
+        let clashed = json(&derive_identity(seed, "polymail", &attestation(), false));
+        assert!(clashed["error"].is_string());
+    }
+
+    #[test]
+    fn derive_identity_requires_a_matching_attestation() {
+        let seed = seed_handle();
+        let derived = json(&derive_identity(seed, "polydata", &attestation(), false));
+        assert_eq!(derived["provenance"]["platform"], "Software");
+        assert_eq!(derived["provenance"]["hardware_backed"], false);
+
+        let missing = json(&derive_identity(seed, "polydata", &[], false));
+        assert!(missing["error"].is_string());
+        let other_seed = software_attestation(&polykit_core::identity::seed_commitment(&[8; 32]));
+        let mismatched = json(&derive_identity(seed, "polydata", &other_seed, false));
+        assert!(mismatched["error"].as_str().unwrap().contains("different seed"));
+        let software_only = json(&derive_identity(seed, "polydata", &attestation(), true));
+        assert!(software_only["error"].as_str().unwrap().contains("not hardware-backed"));
+    }
+}
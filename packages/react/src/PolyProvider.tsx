@@ -41,6 +41,8 @@ interface PolyProviderProps {
   lexNamespace?: string;
   /** Enable demo mode (?demo=true equivalent) */
   demo?: boolean;
+  /** Extra PolykitConfig fields (region, tier, classification_policy, widget_roles) */
+  config?: Record<string, unknown>;
   children: React.ReactNode;
 }
 
@@ -50,6 +52,7 @@ export function PolyProvider({
   appId = '',
   lexNamespace = '',
   demo = false,
+  config,
   children,
 }: PolyProviderProps) {
   const [state, setState] = useState<PolyContextValue>({
@@ -60,6 +63,9 @@ export function PolyProvider({
     error: null,
   });
 
+  // Keyed by value so an inline config object doesn't re-init every render
+  const configJson = JSON.stringify(config ?? {});
+
   useEffect(() => {
     let cancelled = false;
 
@@ -70,7 +76,13 @@ export function PolyProvider({
         if (cancelled) return;
 
         // Initialize app in WASM
-        const resultJson = wasmModule.init_app(appId, hkdfContext, lexNamespace, demo);
+        const resultJson = wasmModule.init_app(JSON.stringify({
+          ...JSON.parse(configJson),
+          app_id: appId,
+          hkdf_context: hkdfContext,
+          lex_namespace: lexNamespace,
+          demo_mode: demo,
+        }));
         const result = parseWasmResponse<{ app_id: string; lex_namespace: string; status: string }>(resultJson);
 
         setState({
@@ -91,7 +103,7 @@ export function PolyProvider({
 
     init();
     return () => { cancelled = true; };
-  }, [wasmUrl, hkdfContext, appId, lexNamespace, demo]);
+  }, [wasmUrl, hkdfContext, appId, lexNamespace, demo, configJson]);
 
   if (state.error) {
     return <div className="polykit-error">PolyKit initialization failed: {state.error}</div>;
@@ -9,16 +9,44 @@
  */
 
 export interface PolykitWasm {
-  init_app(appId: string, hkdfContext: string, lexNamespace: string, demoMode: boolean): string;
-  derive_identity(masterSeed: Uint8Array, hkdfContext: string, lexNamespace: string): string;
+  init_app(configJson: string): string;
+  generate_master_seed(): string;
+  derive_identity(
+    seedHandle: number,
+    appId: string,
+    attestationBlob: Uint8Array,
+    requireHardware: boolean,
+  ): string;
+  sign_with_handle(handle: number, message: Uint8Array): string;
+  decapsulate_with_handle(handle: number, kemCiphertext: Uint8Array): string;
+  seal_with_handle(handle: number, plaintext: Uint8Array, aad: Uint8Array): string;
+  open_with_handle(
+    handle: number,
+    nonce: Uint8Array,
+    ciphertext: Uint8Array,
+    aad: Uint8Array,
+  ): string;
+  release_handle(handle: number): boolean;
+  encode_mnemonic(seedHandle: number): string;
+  decode_mnemonic(phrase: string): string;
   run_migrations(migrationsJson: string): string;
   query(table: string, filterJson: string): string;
   process_widgets(streamDataJson: string, eventsJson: string): string;
   emit_widget_event(eventJson: string): string;
   sanitize(inputJson: string): string;
+  sanitize_with_budget(inputJson: string, budgetMs: number): string;
   classify(path: string, policyJson: string): string;
+  classify_full(pathsJson: string, policyJson: string, budgetMs?: number): string;
   check_metering_limits(currentJson: string, limitsJson: string): string;
+  format_dimension(dimension: string, value: bigint): string;
+  safety_number(localPublicKey: Uint8Array, remotePublicKey: Uint8Array): string;
+  verify_safety_number(
+    localPublicKey: Uint8Array,
+    remotePublicKey: Uint8Array,
+    candidate: string,
+  ): boolean;
   evaluate(contextPtr: number): number;
+  last_error(): string;
   circuit_name(): string;
   circuit_version(): string;
 }